@@ -0,0 +1,293 @@
+use crate::error::{ParsingError, ParsingErrorKind};
+use nom::{
+    combinator::{fail, map},
+    multi::length_count,
+    number::complete::{be_u16, be_u8},
+    sequence::tuple,
+    IResult,
+};
+
+/// An annotation on a class, field, or method.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Annotation {
+    /// The field descriptor of the annotation's type, like
+    /// `Ljava/lang/Deprecated;`.
+    pub type_index: u16,
+    /// The element name and value pairs the annotation was given.
+    pub elements: Vec<(u16, ElementValue)>,
+}
+
+/// An annotation on a use of a type, like the `@NonNull` in
+/// `List<@NonNull String>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeAnnotation {
+    /// The kind of target, like `0x13` for a field's type or `0x47` for a
+    /// type argument of a cast. It says which location `target` describes.
+    pub target_type: u8,
+    pub target: TypeAnnotationTarget,
+    /// The path from the outermost type down to the annotated part of it,
+    /// empty if the annotation is on the outermost type.
+    pub type_path: Vec<TypePathEntry>,
+    pub annotation: Annotation,
+}
+
+/// Where in a declaration or in code a type annotation appears.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypeAnnotationTarget {
+    /// A type parameter of a generic class or method.
+    TypeParameter {
+        index: u8,
+    },
+    /// A type in the `extends` or `implements` clause. The index is 65535
+    /// for the superclass, otherwise it indexes the interfaces.
+    Supertype {
+        index: u16,
+    },
+    /// A bound of a type parameter of a generic class or method.
+    TypeParameterBound {
+        type_parameter_index: u8,
+        bound_index: u8,
+    },
+    /// The type of a field, the return type of a method, or the receiver of
+    /// a method, which `target_type` tells apart.
+    Empty,
+    FormalParameter {
+        index: u8,
+    },
+    /// A type in the `throws` clause, indexing the Exceptions attribute.
+    Throws {
+        index: u16,
+    },
+    /// A local variable or a resource variable, along with the ranges of
+    /// code it is live in.
+    LocalVariable(Vec<LocalVariableTarget>),
+    /// The type in a `catch` clause, indexing the exception table.
+    Catch {
+        exception_table_index: u16,
+    },
+    /// An `instanceof`, `new`, or method reference expression, identified by
+    /// the offset of its instruction.
+    Offset(u16),
+    /// A type argument of a cast, constructor call, method call, or method
+    /// reference expression.
+    TypeArgument {
+        offset: u16,
+        type_argument_index: u8,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LocalVariableTarget {
+    pub start_pc: u16,
+    pub length: u16,
+    pub index: u16,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TypePathEntry {
+    /// 0 to step into an array's component type, 1 into a nested type, 2
+    /// into a wildcard's bound, and 3 into a type argument.
+    pub type_path_kind: u8,
+    /// Which type argument a kind 3 step goes into, otherwise zero.
+    pub type_argument_index: u8,
+}
+
+/// The value of an annotation element. Constants are stored as indices of
+/// constant pool entries.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ElementValue {
+    Byte(u16),
+    Char(u16),
+    Double(u16),
+    Float(u16),
+    Int(u16),
+    Long(u16),
+    Short(u16),
+    Boolean(u16),
+    String(u16),
+    Enum {
+        type_name_index: u16,
+        const_name_index: u16,
+    },
+    /// A class literal, stored as the index of its return descriptor, like
+    /// `Ljava/lang/Object;` or `V`.
+    Class(u16),
+    Annotation(Annotation),
+    Array(Vec<ElementValue>),
+}
+
+/// How deeply element values can nest inside arrays and annotations. Java
+/// source never comes close, and the limit keeps a malformed class from
+/// recursing until the stack overflows.
+const MAX_ELEMENT_VALUE_DEPTH: usize = 64;
+
+pub(crate) fn annotation(input: &[u8]) -> IResult<&[u8], Annotation, ParsingError> {
+    nested_annotation(input, 0)
+}
+
+fn nested_annotation(input: &[u8], depth: usize) -> IResult<&[u8], Annotation, ParsingError> {
+    map(
+        tuple((
+            be_u16,
+            length_count(
+                be_u16,
+                tuple((be_u16, |input| nested_element_value(input, depth))),
+            ),
+        )),
+        |(type_index, elements)| Annotation {
+            type_index,
+            elements,
+        },
+    )(input)
+}
+
+pub(crate) fn type_annotation(input: &[u8]) -> IResult<&[u8], TypeAnnotation, ParsingError> {
+    let (input, target_type) = be_u8(input)?;
+    let (input, target) = match target_type {
+        0x00 | 0x01 => map(be_u8, |index| TypeAnnotationTarget::TypeParameter { index })(input)?,
+        0x10 => map(be_u16, |index| TypeAnnotationTarget::Supertype { index })(input)?,
+        0x11 | 0x12 => map(
+            tuple((be_u8, be_u8)),
+            |(type_parameter_index, bound_index)| TypeAnnotationTarget::TypeParameterBound {
+                type_parameter_index,
+                bound_index,
+            },
+        )(input)?,
+        0x13..=0x15 => (input, TypeAnnotationTarget::Empty),
+        0x16 => map(be_u8, |index| TypeAnnotationTarget::FormalParameter {
+            index,
+        })(input)?,
+        0x17 => map(be_u16, |index| TypeAnnotationTarget::Throws { index })(input)?,
+        0x40 | 0x41 => map(
+            length_count(be_u16, local_variable_target),
+            TypeAnnotationTarget::LocalVariable,
+        )(input)?,
+        0x42 => map(be_u16, |exception_table_index| {
+            TypeAnnotationTarget::Catch {
+                exception_table_index,
+            }
+        })(input)?,
+        0x43..=0x46 => map(be_u16, TypeAnnotationTarget::Offset)(input)?,
+        0x47..=0x4b => map(tuple((be_u16, be_u8)), |(offset, type_argument_index)| {
+            TypeAnnotationTarget::TypeArgument {
+                offset,
+                type_argument_index,
+            }
+        })(input)?,
+        _ => return fail(input),
+    };
+    let (input, type_path) = length_count(be_u8, type_path_entry)(input)?;
+    let (input, annotation) = annotation(input)?;
+
+    Ok((
+        input,
+        TypeAnnotation {
+            target_type,
+            target,
+            type_path,
+            annotation,
+        },
+    ))
+}
+
+fn local_variable_target(input: &[u8]) -> IResult<&[u8], LocalVariableTarget, ParsingError> {
+    map(
+        tuple((be_u16, be_u16, be_u16)),
+        |(start_pc, length, index)| LocalVariableTarget {
+            start_pc,
+            length,
+            index,
+        },
+    )(input)
+}
+
+fn type_path_entry(input: &[u8]) -> IResult<&[u8], TypePathEntry, ParsingError> {
+    map(
+        tuple((be_u8, be_u8)),
+        |(type_path_kind, type_argument_index)| TypePathEntry {
+            type_path_kind,
+            type_argument_index,
+        },
+    )(input)
+}
+
+pub(crate) fn element_value(input: &[u8]) -> IResult<&[u8], ElementValue, ParsingError> {
+    nested_element_value(input, 0)
+}
+
+/// Parses an element value inside `depth` arrays and annotations.
+fn nested_element_value(input: &[u8], depth: usize) -> IResult<&[u8], ElementValue, ParsingError> {
+    if depth > MAX_ELEMENT_VALUE_DEPTH {
+        return Err(nom::Err::Failure(ParsingError::new(
+            input,
+            ParsingErrorKind::ElementValueTooDeep,
+        )));
+    }
+
+    let (input, tag) = be_u8(input)?;
+    match tag {
+        b'B' => map(be_u16, ElementValue::Byte)(input),
+        b'C' => map(be_u16, ElementValue::Char)(input),
+        b'D' => map(be_u16, ElementValue::Double)(input),
+        b'F' => map(be_u16, ElementValue::Float)(input),
+        b'I' => map(be_u16, ElementValue::Int)(input),
+        b'J' => map(be_u16, ElementValue::Long)(input),
+        b'S' => map(be_u16, ElementValue::Short)(input),
+        b'Z' => map(be_u16, ElementValue::Boolean)(input),
+        b's' => map(be_u16, ElementValue::String)(input),
+        b'e' => map(
+            tuple((be_u16, be_u16)),
+            |(type_name_index, const_name_index)| ElementValue::Enum {
+                type_name_index,
+                const_name_index,
+            },
+        )(input),
+        b'c' => map(be_u16, ElementValue::Class)(input),
+        b'@' => map(
+            |input| nested_annotation(input, depth + 1),
+            ElementValue::Annotation,
+        )(input),
+        b'[' => map(
+            length_count(be_u16, |input| nested_element_value(input, depth + 1)),
+            ElementValue::Array,
+        )(input),
+        _ => fail(input),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nested_arrays(depth: usize) -> Vec<u8> {
+        let mut input = [b'[', 0, 1].repeat(depth);
+        input.extend_from_slice(&[b'I', 0, 1]);
+        input
+    }
+
+    #[test]
+    fn nested_element_values() {
+        let input = [b'[', 0, 1, b'@', 0, 5, 0, 1, 0, 6, b'Z', 0, 7];
+        let (_, value) = element_value(&input).unwrap();
+        assert_eq!(
+            value,
+            ElementValue::Array(vec![ElementValue::Annotation(Annotation {
+                type_index: 5,
+                elements: vec![(6, ElementValue::Boolean(7))],
+            })])
+        );
+    }
+
+    #[test]
+    fn element_values_nest_up_to_the_limit() {
+        assert!(element_value(&nested_arrays(MAX_ELEMENT_VALUE_DEPTH)).is_ok());
+        for depth in [MAX_ELEMENT_VALUE_DEPTH + 1, 100_000] {
+            match element_value(&nested_arrays(depth)) {
+                Err(nom::Err::Failure(err)) => {
+                    assert_eq!(err.kind, ParsingErrorKind::ElementValueTooDeep)
+                }
+                other => panic!("expected a failure, got {other:?}"),
+            }
+        }
+    }
+}
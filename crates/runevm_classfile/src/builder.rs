@@ -0,0 +1,302 @@
+use crate::{Constant, ConstantPool, ConstantPoolError};
+use std::collections::HashMap;
+
+/// The highest index a constant can be given, as `constant_pool_count` is
+/// itself a u16 and counts one past the last entry.
+const MAX_INDEX: usize = u16::MAX as usize - 1;
+
+/// Builds a [`ConstantPool`] by hand, for tools that generate or patch class
+/// files.
+///
+/// Every `add_*` method returns the 1-based index the new constant was
+/// assigned, which is what other constants and bytecode refer to it by.
+/// Adding a constant that is already in the pool returns the existing index
+/// instead of appending a duplicate.
+#[derive(Debug, Default)]
+pub struct ConstantPoolBuilder {
+    items: Vec<Constant>,
+    indices: HashMap<Key, u16>,
+}
+
+/// A hashable stand-in for a [`Constant`]. Floating-point values are compared
+/// by their bits, the same way the class file stores them.
+#[derive(Debug, PartialEq, Eq, Hash)]
+enum Key {
+    Utf8(String),
+    Integer(i32),
+    Float(u32),
+    Long(i64),
+    Double(u64),
+    Class(u16),
+    String(u16),
+    Field(u16, u16),
+    Method(u16, u16),
+    InterfaceMethod(u16, u16),
+    NameAndType(u16, u16),
+    MethodHandle(u8, u16),
+    MethodType(u16),
+    Dynamic(u16, u16),
+    InvokeDynamic(u16, u16),
+    Module(u16),
+    Package(u16),
+}
+
+impl Key {
+    fn new(constant: &Constant) -> Option<Key> {
+        Some(match *constant {
+            Constant::Utf8(ref value) => Key::Utf8(value.clone()),
+            Constant::Integer(value) => Key::Integer(value),
+            Constant::Float(value) => Key::Float(value.to_bits()),
+            Constant::Long(value) => Key::Long(value),
+            Constant::Double(value) => Key::Double(value.to_bits()),
+            Constant::Class(name_index) => Key::Class(name_index),
+            Constant::String(string_index) => Key::String(string_index),
+            Constant::Field {
+                class_index,
+                nametype_index,
+            } => Key::Field(class_index, nametype_index),
+            Constant::Method {
+                class_index,
+                nametype_index,
+            } => Key::Method(class_index, nametype_index),
+            Constant::InterfaceMethod {
+                class_index,
+                nametype_index,
+            } => Key::InterfaceMethod(class_index, nametype_index),
+            Constant::NameAndType {
+                name_index,
+                descriptor_index,
+            } => Key::NameAndType(name_index, descriptor_index),
+            Constant::MethodHandle {
+                reference_kind,
+                reference_index,
+            } => Key::MethodHandle(reference_kind, reference_index),
+            Constant::MethodType(descriptor_index) => Key::MethodType(descriptor_index),
+            Constant::Dynamic {
+                bootstrap_method_attr_index,
+                nametype_index,
+            } => Key::Dynamic(bootstrap_method_attr_index, nametype_index),
+            Constant::InvokeDynamic {
+                bootstrap_method_attr_index,
+                nametype_index,
+            } => Key::InvokeDynamic(bootstrap_method_attr_index, nametype_index),
+            Constant::Module(name_index) => Key::Module(name_index),
+            Constant::Package(name_index) => Key::Package(name_index),
+            Constant::Unusable => return None,
+        })
+    }
+}
+
+impl ConstantPool {
+    pub fn builder() -> ConstantPoolBuilder {
+        ConstantPoolBuilder::default()
+    }
+}
+
+/// Starts from the entries of an existing pool, so new constants are
+/// appended after them and can reuse them.
+impl From<ConstantPool> for ConstantPoolBuilder {
+    fn from(pool: ConstantPool) -> Self {
+        let mut indices = HashMap::new();
+        for (i, constant) in pool.items.iter().enumerate() {
+            if let Some(key) = Key::new(constant) {
+                indices.entry(key).or_insert(i as u16 + 1);
+            }
+        }
+
+        ConstantPoolBuilder {
+            items: pool.items,
+            indices,
+        }
+    }
+}
+
+impl ConstantPoolBuilder {
+    /// Adds `constant` unless an identical one is already present, returning
+    /// its index either way. Fails once the pool has no room left for it.
+    pub fn add(&mut self, constant: Constant) -> Result<u16, ConstantPoolError> {
+        let key = Key::new(&constant);
+        if let Some(&index) = key.as_ref().and_then(|key| self.indices.get(key)) {
+            return Ok(index);
+        }
+
+        let wide = matches!(constant, Constant::Long(_) | Constant::Double(_));
+        let slots = if wide { 2 } else { 1 };
+        if self.remaining_capacity() < slots {
+            return Err(ConstantPoolError::Overflow);
+        }
+
+        self.items.push(constant);
+        let index = self.items.len() as u16;
+        if wide {
+            self.items.push(Constant::Unusable);
+        }
+        if let Some(key) = key {
+            self.indices.insert(key, index);
+        }
+
+        Ok(index)
+    }
+
+    /// The number of index slots still free. Long and Double constants take
+    /// up two.
+    pub fn remaining_capacity(&self) -> usize {
+        MAX_INDEX.saturating_sub(self.items.len())
+    }
+
+    pub fn add_utf8(&mut self, value: &str) -> Result<u16, ConstantPoolError> {
+        self.add(Constant::Utf8(value.to_owned()))
+    }
+
+    pub fn add_integer(&mut self, value: i32) -> Result<u16, ConstantPoolError> {
+        self.add(Constant::Integer(value))
+    }
+
+    pub fn add_float(&mut self, value: f32) -> Result<u16, ConstantPoolError> {
+        self.add(Constant::Float(value))
+    }
+
+    pub fn add_long(&mut self, value: i64) -> Result<u16, ConstantPoolError> {
+        self.add(Constant::Long(value))
+    }
+
+    pub fn add_double(&mut self, value: f64) -> Result<u16, ConstantPoolError> {
+        self.add(Constant::Double(value))
+    }
+
+    pub fn add_class(&mut self, name: &str) -> Result<u16, ConstantPoolError> {
+        let name_index = self.add_utf8(name)?;
+        self.add(Constant::Class(name_index))
+    }
+
+    pub fn add_string(&mut self, value: &str) -> Result<u16, ConstantPoolError> {
+        let string_index = self.add_utf8(value)?;
+        self.add(Constant::String(string_index))
+    }
+
+    pub fn add_name_and_type(
+        &mut self,
+        name: &str,
+        descriptor: &str,
+    ) -> Result<u16, ConstantPoolError> {
+        let name_index = self.add_utf8(name)?;
+        let descriptor_index = self.add_utf8(descriptor)?;
+        self.add(Constant::NameAndType {
+            name_index,
+            descriptor_index,
+        })
+    }
+
+    pub fn add_field_ref(
+        &mut self,
+        class: &str,
+        name: &str,
+        descriptor: &str,
+    ) -> Result<u16, ConstantPoolError> {
+        let class_index = self.add_class(class)?;
+        let nametype_index = self.add_name_and_type(name, descriptor)?;
+        self.add(Constant::Field {
+            class_index,
+            nametype_index,
+        })
+    }
+
+    pub fn add_method_ref(
+        &mut self,
+        class: &str,
+        name: &str,
+        descriptor: &str,
+    ) -> Result<u16, ConstantPoolError> {
+        let class_index = self.add_class(class)?;
+        let nametype_index = self.add_name_and_type(name, descriptor)?;
+        self.add(Constant::Method {
+            class_index,
+            nametype_index,
+        })
+    }
+
+    pub fn add_interface_method_ref(
+        &mut self,
+        class: &str,
+        name: &str,
+        descriptor: &str,
+    ) -> Result<u16, ConstantPoolError> {
+        let class_index = self.add_class(class)?;
+        let nametype_index = self.add_name_and_type(name, descriptor)?;
+        self.add(Constant::InterfaceMethod {
+            class_index,
+            nametype_index,
+        })
+    }
+
+    pub fn build(self) -> ConstantPool {
+        ConstantPool { items: self.items }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identical_constants_share_an_index() {
+        let mut builder = ConstantPool::builder();
+        let println = builder
+            .add_method_ref("java/io/PrintStream", "println", "(I)V")
+            .unwrap();
+        let capacity = builder.remaining_capacity();
+
+        assert_eq!(
+            builder.add_method_ref("java/io/PrintStream", "println", "(I)V"),
+            Ok(println)
+        );
+        assert_eq!(builder.add_utf8("println"), Ok(3));
+        assert_eq!(builder.remaining_capacity(), capacity);
+
+        // A second NameAndType reuses both of the strings the first added.
+        let print = builder.add_name_and_type("print", "(I)V").unwrap();
+        assert_eq!(builder.remaining_capacity(), capacity - 2);
+        assert!(matches!(
+            builder.build().at(print),
+            Ok(&Constant::NameAndType {
+                descriptor_index: 4,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn floats_are_compared_by_their_bits() {
+        let mut builder = ConstantPool::builder();
+        let zero = builder.add_float(0.0).unwrap();
+        assert_ne!(builder.add_float(-0.0), Ok(zero));
+        let nan = builder.add_double(f64::NAN).unwrap();
+        assert_eq!(builder.add_double(f64::NAN), Ok(nan));
+    }
+
+    #[test]
+    fn appending_to_a_parsed_pool_reuses_its_constants() {
+        let mut builder = ConstantPool::builder();
+        let class = builder.add_class("Test").unwrap();
+        let mut builder = ConstantPoolBuilder::from(builder.build());
+        assert_eq!(builder.add_class("Test"), Ok(class));
+        assert_eq!(builder.add_utf8("other"), Ok(3));
+    }
+
+    #[test]
+    fn full_pool_reports_an_overflow() {
+        let mut builder = ConstantPool::builder();
+        assert_eq!(builder.remaining_capacity(), 65534);
+        for value in 0..65533 {
+            builder.add_integer(value).unwrap();
+        }
+        assert_eq!(builder.remaining_capacity(), 1);
+
+        // A long needs two slots, so it no longer fits, but an int does.
+        assert_eq!(builder.add_long(0), Err(ConstantPoolError::Overflow));
+        assert_eq!(builder.add_integer(-1), Ok(65534));
+        assert_eq!(builder.remaining_capacity(), 0);
+        assert_eq!(builder.add_integer(-2), Err(ConstantPoolError::Overflow));
+        assert_eq!(builder.add_integer(0), Ok(1));
+    }
+}
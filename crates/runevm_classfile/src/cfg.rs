@@ -0,0 +1,262 @@
+//! Splits a method's code into basic blocks and the edges between them.
+
+use crate::{error::VerifyError, parser::Attribute, Instruction, Method};
+use std::{
+    collections::{BTreeSet, HashMap},
+    ops::Range,
+};
+
+/// A run of instructions that is only entered at its first instruction and
+/// only left after its last.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BasicBlock {
+    /// The offset of the block's first instruction.
+    pub start: u32,
+    /// The indices of the block's instructions in the method's code.
+    pub instructions: Range<usize>,
+    pub successors: Vec<Edge>,
+    /// Whether any path from the method's entry point reaches the block.
+    pub reachable: bool,
+}
+
+/// An edge from one basic block to another.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Edge {
+    /// The index of the block the edge leads to.
+    pub target: usize,
+    pub kind: EdgeKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgeKind {
+    /// Execution runs off the end of the block into the next one.
+    Fallthrough,
+    /// The block ends in a jump, branch, or switch to the target.
+    Branch,
+    /// The block is covered by an exception handler that starts the target.
+    /// The catch type is the index of the exception class in the constant
+    /// pool, or zero if the handler catches everything.
+    Exception { catch_type: u16 },
+}
+
+/// Splits `method`'s code into basic blocks, in the order they appear in the
+/// code. Blocks also start at the bounds of every range an exception handler
+/// covers, so each block is either wholly inside a range or outside of it.
+/// Methods without code have no blocks.
+pub fn basic_blocks(method: &Method) -> Result<Vec<BasicBlock>, VerifyError> {
+    let (code, exceptions) = match method.attributes.iter().find_map(|attr| {
+        if let Attribute::Code {
+            code, exceptions, ..
+        } = attr
+        {
+            Some((code, exceptions))
+        } else {
+            None
+        }
+    }) {
+        Some(code) => code,
+        None => return Ok(Vec::new()),
+    };
+
+    let indices: HashMap<u32, usize> = code
+        .iter()
+        .enumerate()
+        .map(|(index, &(offset, _))| (offset, index))
+        .collect();
+    let index_of = |offset: i64| {
+        u32::try_from(offset)
+            .ok()
+            .and_then(|offset| indices.get(&offset).copied())
+            .ok_or(VerifyError::InvalidBranchTarget(offset))
+    };
+
+    // The indices of the instructions that start a block. A handler range
+    // can end at the end of the code, where there is no block to start.
+    let mut leaders = BTreeSet::from([0]);
+    for entry in exceptions {
+        leaders.insert(index_of(entry.handler_pc as i64)?);
+        leaders.insert(index_of(entry.start_pc as i64)?);
+        if let Some(&end) = indices.get(&(entry.end_pc as u32)) {
+            leaders.insert(end);
+        }
+    }
+    for (index, (offset, inst)) in code.iter().enumerate() {
+        for relative in inst.branches() {
+            leaders.insert(index_of(*offset as i64 + relative as i64)?);
+        }
+        if ends_block(inst) && index + 1 < code.len() {
+            leaders.insert(index + 1);
+        }
+    }
+    leaders.retain(|&leader| leader < code.len());
+
+    let starts: Vec<usize> = leaders.into_iter().collect();
+    let block_of: HashMap<usize, usize> = starts
+        .iter()
+        .enumerate()
+        .map(|(block, &start)| (start, block))
+        .collect();
+
+    let mut blocks = Vec::with_capacity(starts.len());
+    for (block, &start) in starts.iter().enumerate() {
+        let end = starts.get(block + 1).copied().unwrap_or(code.len());
+        let (offset, last) = &code[end - 1];
+
+        let mut successors = Vec::new();
+        let mut edge = |target: usize, kind| {
+            let edge = Edge {
+                target: block_of[&target],
+                kind,
+            };
+            if !successors.contains(&edge) {
+                successors.push(edge);
+            }
+        };
+        for relative in last.branches() {
+            edge(
+                index_of(*offset as i64 + relative as i64)?,
+                EdgeKind::Branch,
+            );
+        }
+        if falls_through(last) && end < code.len() {
+            edge(end, EdgeKind::Fallthrough);
+        }
+        for entry in exceptions {
+            let range = entry.start_pc as u32..entry.end_pc as u32;
+            if range.contains(&code[start].0) {
+                edge(
+                    index_of(entry.handler_pc as i64)?,
+                    EdgeKind::Exception {
+                        catch_type: entry.catch_type,
+                    },
+                );
+            }
+        }
+
+        blocks.push(BasicBlock {
+            start: code[start].0,
+            instructions: start..end,
+            successors,
+            reachable: false,
+        });
+    }
+
+    let mut pending = vec![0];
+    while let Some(block) = pending.pop() {
+        if blocks.is_empty() || blocks[block].reachable {
+            continue;
+        }
+        blocks[block].reachable = true;
+        pending.extend(blocks[block].successors.iter().map(|edge| edge.target));
+    }
+
+    Ok(blocks)
+}
+
+/// Whether the instruction after `inst` starts a new block, because `inst`
+/// transfers control somewhere else.
+fn ends_block(inst: &Instruction) -> bool {
+    !inst.branches().is_empty() || !falls_through(inst)
+}
+
+/// Whether execution can continue with the instruction after `inst`. A
+/// subroutine returns to the instruction after its `jsr`.
+fn falls_through(inst: &Instruction) -> bool {
+    use Instruction::*;
+
+    !matches!(
+        inst,
+        Goto(_)
+            | Gotow(_)
+            | Lookupswitch { .. }
+            | Tableswitch { .. }
+            | Areturn
+            | Dreturn
+            | Freturn
+            | Ireturn
+            | Lreturn
+            | Return
+            | Athrow
+            | Ret(_)
+            | Wide(0xa9, _)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_class_checked;
+
+    const SAMPLE: &[u8] = include_bytes!("../tests/fixtures/Sample.class");
+
+    /// The blocks of a method of the sample class, as each one's start and
+    /// its successors sorted by target.
+    fn blocks(name: &str, descriptor: &str) -> Vec<(u32, Vec<Edge>)> {
+        let class = parse_class_checked(SAMPLE).unwrap();
+        let method = class.get_method(name, descriptor).unwrap();
+        basic_blocks(method)
+            .unwrap()
+            .into_iter()
+            .map(|mut block| {
+                assert!(block.reachable);
+                block.successors.sort_by_key(|edge| edge.target);
+                (block.start, block.successors)
+            })
+            .collect()
+    }
+
+    fn edge(target: usize, kind: EdgeKind) -> Edge {
+        Edge { target, kind }
+    }
+
+    #[test]
+    fn if_else_blocks() {
+        assert_eq!(
+            blocks("sign", "(I)I"),
+            [
+                (
+                    0,
+                    vec![edge(1, EdgeKind::Fallthrough), edge(2, EdgeKind::Branch)]
+                ),
+                (4, vec![]),
+                (6, vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn loop_blocks() {
+        assert_eq!(
+            blocks("sum", "(I)I"),
+            [
+                (0, vec![edge(1, EdgeKind::Fallthrough)]),
+                (
+                    4,
+                    vec![edge(2, EdgeKind::Fallthrough), edge(3, EdgeKind::Branch)]
+                ),
+                (9, vec![edge(1, EdgeKind::Branch)]),
+                (19, vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn try_catch_blocks() {
+        // The handler covers the division but not the return after it.
+        let blocks = blocks("divide", "(II)I");
+        assert_eq!(blocks.len(), 3);
+        assert_eq!(blocks[0].0, 0);
+        match blocks[0].1[..] {
+            [Edge {
+                target: 1,
+                kind: EdgeKind::Fallthrough,
+            }, Edge {
+                target: 2,
+                kind: EdgeKind::Exception { catch_type },
+            }] => assert_ne!(catch_type, 0),
+            ref successors => panic!("unexpected successors {successors:?}"),
+        }
+        assert_eq!(blocks[1], (3, vec![]));
+        assert_eq!(blocks[2], (4, vec![]));
+    }
+}
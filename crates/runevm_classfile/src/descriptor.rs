@@ -0,0 +1,289 @@
+//! Parsing of the descriptors that spell out the types of fields and
+//! methods, like `[[Ljava/lang/String;` and `(IJ)V`.
+
+use crate::error::DescriptorError;
+use std::fmt;
+
+/// The most dimensions an array type can have.
+pub(crate) const MAX_DIMENSIONS: usize = 255;
+
+/// The type of a field, local variable, or array element.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum FieldType {
+    Byte,
+    Char,
+    Double,
+    Float,
+    Int,
+    Long,
+    Short,
+    Boolean,
+    /// An instance of the class with the given binary name.
+    Object(String),
+    /// An array with the given component type.
+    Array(Box<FieldType>),
+}
+
+impl FieldType {
+    /// Parses a field descriptor, which must make up the whole of
+    /// `descriptor`.
+    pub fn parse(descriptor: &str) -> Result<FieldType, DescriptorError> {
+        let (field_type, rest) = FieldType::parse_prefix(descriptor)?;
+        if !rest.is_empty() {
+            return Err(DescriptorError::TrailingCharacters(rest.to_owned()));
+        }
+        Ok(field_type)
+    }
+
+    /// Parses the field type at the start of `descriptor`, returning it along
+    /// with the rest of the descriptor.
+    pub(crate) fn parse_prefix(descriptor: &str) -> Result<(FieldType, &str), DescriptorError> {
+        let mut chars = descriptor.chars();
+        let c = chars.next().ok_or(DescriptorError::UnexpectedEnd)?;
+        let rest = chars.as_str();
+
+        let field_type = match c {
+            'B' => FieldType::Byte,
+            'C' => FieldType::Char,
+            'D' => FieldType::Double,
+            'F' => FieldType::Float,
+            'I' => FieldType::Int,
+            'J' => FieldType::Long,
+            'S' => FieldType::Short,
+            'Z' => FieldType::Boolean,
+            'L' => {
+                let (name, rest) = rest
+                    .split_once(';')
+                    .ok_or(DescriptorError::UnterminatedClassName)?;
+                let valid = name
+                    .split('/')
+                    .all(|part| !part.is_empty() && !part.contains(['.', '[']));
+                if !valid {
+                    return Err(DescriptorError::InvalidClassName(name.to_owned()));
+                }
+                return Ok((FieldType::Object(name.to_owned()), rest));
+            }
+            '[' => {
+                if descriptor.bytes().take_while(|&b| b == b'[').count() > MAX_DIMENSIONS {
+                    return Err(DescriptorError::TooManyDimensions);
+                }
+                let (component, rest) = FieldType::parse_prefix(rest)?;
+                return Ok((FieldType::Array(Box::new(component)), rest));
+            }
+            c => return Err(DescriptorError::InvalidType(c)),
+        };
+        Ok((field_type, rest))
+    }
+
+    /// Whether values of the type are references rather than primitives.
+    pub fn is_reference(&self) -> bool {
+        matches!(self, FieldType::Object(_) | FieldType::Array(_))
+    }
+
+    /// The number of local variable or operand stack slots a value of the
+    /// type takes up, which is two for longs and doubles and one otherwise.
+    pub fn slots(&self) -> usize {
+        match self {
+            FieldType::Long | FieldType::Double => 2,
+            _ => 1,
+        }
+    }
+}
+
+/// The parameter and return types of a method.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MethodDescriptor {
+    pub parameters: Vec<FieldType>,
+    /// The type of the returned value, or `None` for `void`.
+    pub return_type: Option<FieldType>,
+}
+
+impl MethodDescriptor {
+    /// Parses a method descriptor like `(I[Ljava/lang/String;)V`, which must
+    /// make up the whole of `descriptor`.
+    pub fn parse(descriptor: &str) -> Result<MethodDescriptor, DescriptorError> {
+        let mut rest = descriptor
+            .strip_prefix('(')
+            .ok_or(DescriptorError::MissingParameters)?;
+
+        let mut parameters = Vec::new();
+        loop {
+            if let Some(after) = rest.strip_prefix(')') {
+                rest = after;
+                break;
+            }
+            let (parameter, after) = FieldType::parse_prefix(rest)?;
+            parameters.push(parameter);
+            rest = after;
+        }
+
+        let return_type = match rest.strip_prefix('V') {
+            Some("") => None,
+            Some(after) => return Err(DescriptorError::TrailingCharacters(after.to_owned())),
+            None => Some(FieldType::parse(rest)?),
+        };
+        Ok(MethodDescriptor {
+            parameters,
+            return_type,
+        })
+    }
+
+    /// The number of local variable slots the parameters take up, not
+    /// counting the receiver of an instance method.
+    pub fn parameter_slots(&self) -> usize {
+        self.parameters.iter().map(FieldType::slots).sum()
+    }
+
+    /// The number of local variable slots an invocation fills in, which is
+    /// the parameters plus one for the receiver unless the method is static.
+    /// The count saturates at `u16::MAX`, which no valid method reaches.
+    pub fn slot_count(&self, is_static: bool) -> u16 {
+        let receiver = if is_static { 0 } else { 1 };
+        u16::try_from(receiver + self.parameter_slots()).unwrap_or(u16::MAX)
+    }
+}
+
+/// Writes the method's types back out as a descriptor.
+impl fmt::Display for MethodDescriptor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "(")?;
+        for parameter in &self.parameters {
+            write!(f, "{parameter}")?;
+        }
+        match &self.return_type {
+            Some(return_type) => write!(f, "){return_type}"),
+            None => write!(f, ")V"),
+        }
+    }
+}
+
+/// Writes the type back out as a descriptor.
+impl fmt::Display for FieldType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldType::Byte => write!(f, "B"),
+            FieldType::Char => write!(f, "C"),
+            FieldType::Double => write!(f, "D"),
+            FieldType::Float => write!(f, "F"),
+            FieldType::Int => write!(f, "I"),
+            FieldType::Long => write!(f, "J"),
+            FieldType::Short => write!(f, "S"),
+            FieldType::Boolean => write!(f, "Z"),
+            FieldType::Object(name) => write!(f, "L{name};"),
+            FieldType::Array(component) => write!(f, "[{component}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_nested_arrays() {
+        let string = FieldType::Object("java/lang/String".to_owned());
+        assert_eq!(
+            FieldType::parse("[[Ljava/lang/String;"),
+            Ok(FieldType::Array(Box::new(FieldType::Array(Box::new(
+                string
+            )))))
+        );
+        assert_eq!(
+            FieldType::parse("[I"),
+            Ok(FieldType::Array(Box::new(FieldType::Int)))
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_descriptors() {
+        assert_eq!(
+            FieldType::parse("II"),
+            Err(DescriptorError::TrailingCharacters("I".to_owned()))
+        );
+        assert_eq!(
+            FieldType::parse("Ljava/lang/String;;"),
+            Err(DescriptorError::TrailingCharacters(";".to_owned()))
+        );
+        assert_eq!(
+            FieldType::parse("Ljava/lang/String"),
+            Err(DescriptorError::UnterminatedClassName)
+        );
+        assert_eq!(FieldType::parse("[["), Err(DescriptorError::UnexpectedEnd));
+        assert_eq!(FieldType::parse(""), Err(DescriptorError::UnexpectedEnd));
+        assert_eq!(
+            FieldType::parse("V"),
+            Err(DescriptorError::InvalidType('V'))
+        );
+        assert_eq!(
+            FieldType::parse("Ljava.lang.String;"),
+            Err(DescriptorError::InvalidClassName(
+                "java.lang.String".to_owned()
+            ))
+        );
+        assert_eq!(
+            FieldType::parse(&format!("{}I", "[".repeat(256))),
+            Err(DescriptorError::TooManyDimensions)
+        );
+    }
+
+    #[test]
+    fn slot_count_includes_the_receiver_and_wide_parameters() {
+        let descriptor = MethodDescriptor::parse("(JDI)V").unwrap();
+        assert_eq!(descriptor.slot_count(false), 6);
+        assert_eq!(descriptor.slot_count(true), 5);
+        assert_eq!(MethodDescriptor::parse("()V").unwrap().slot_count(true), 0);
+        assert_eq!(MethodDescriptor::parse("()V").unwrap().slot_count(false), 1);
+    }
+
+    #[test]
+    fn parses_method_descriptors() {
+        let main = MethodDescriptor::parse("(I[Ljava/lang/String;)V").unwrap();
+        assert_eq!(
+            main.parameters,
+            [
+                FieldType::Int,
+                FieldType::Array(Box::new(FieldType::Object("java/lang/String".to_owned())))
+            ]
+        );
+        assert_eq!(main.return_type, None);
+        assert_eq!(main.to_string(), "(I[Ljava/lang/String;)V");
+
+        let wide = MethodDescriptor::parse("(JD)J").unwrap();
+        assert_eq!(wide.parameters, [FieldType::Long, FieldType::Double]);
+        assert_eq!(wide.return_type, Some(FieldType::Long));
+
+        assert_eq!(
+            MethodDescriptor::parse("I)V"),
+            Err(DescriptorError::MissingParameters)
+        );
+        assert_eq!(
+            MethodDescriptor::parse("(I)VI"),
+            Err(DescriptorError::TrailingCharacters("I".to_owned()))
+        );
+        assert_eq!(
+            MethodDescriptor::parse("(I)II"),
+            Err(DescriptorError::TrailingCharacters("I".to_owned()))
+        );
+        assert_eq!(
+            MethodDescriptor::parse("(I)"),
+            Err(DescriptorError::UnexpectedEnd)
+        );
+        assert_eq!(
+            MethodDescriptor::parse("(V)V"),
+            Err(DescriptorError::InvalidType('V'))
+        );
+    }
+
+    #[test]
+    fn long_and_double_parameters_take_two_slots() {
+        assert_eq!(FieldType::Long.slots(), 2);
+        assert_eq!(FieldType::Double.slots(), 2);
+        assert_eq!(FieldType::Float.slots(), 1);
+        assert_eq!(FieldType::parse("[J").unwrap().slots(), 1);
+
+        let descriptor = MethodDescriptor::parse("(IJLjava/lang/Object;D[D)V").unwrap();
+        assert_eq!(descriptor.parameter_slots(), 7);
+        assert_eq!(descriptor.slot_count(true), 7);
+        assert_eq!(descriptor.slot_count(false), 8);
+    }
+}
@@ -0,0 +1,562 @@
+//! Renders class files as text, in a format modelled after `javap -c -v`.
+
+use crate::{
+    descriptor::{FieldType, MethodDescriptor},
+    parser::Attribute,
+    pretty::{self, Names},
+    ClassFile, Constant, ConstantPool, ConstantPoolError, Instruction, Method,
+};
+use std::fmt::{self, Write};
+
+impl ConstantPool {
+    /// Lists every constant with its index, kind, raw operands, and what the
+    /// operands resolve to.
+    pub fn dump(&self) -> String {
+        let mut out = String::new();
+
+        for (i, constant) in self.items.iter().enumerate() {
+            let (kind, operands, comment) = match *constant {
+                Constant::Utf8(ref value) => ("Utf8", value.clone(), None),
+                Constant::Integer(value) => ("Integer", value.to_string(), None),
+                Constant::Float(value) => ("Float", format!("{value}f"), None),
+                Constant::Long(value) => ("Long", format!("{value}l"), None),
+                Constant::Double(value) => ("Double", format!("{value}d"), None),
+                Constant::Class(name_index) => (
+                    "Class",
+                    format!("#{name_index}"),
+                    Some(self.utf8(name_index).map(str::to_owned)),
+                ),
+                Constant::String(string_index) => (
+                    "String",
+                    format!("#{string_index}"),
+                    Some(self.utf8(string_index).map(str::to_owned)),
+                ),
+                Constant::Field {
+                    class_index,
+                    nametype_index,
+                } => (
+                    "Fieldref",
+                    format!("#{class_index}.#{nametype_index}"),
+                    Some(self.field_ref(i as u16 + 1).map(|field| {
+                        format!("{}.{}:{}", field.class, field.name, field.descriptor)
+                    })),
+                ),
+                Constant::Method {
+                    class_index,
+                    nametype_index,
+                } => (
+                    "Methodref",
+                    format!("#{class_index}.#{nametype_index}"),
+                    Some(self.method_ref(i as u16 + 1).map(|method| {
+                        format!("{}.{}:{}", method.class, method.name, method.descriptor)
+                    })),
+                ),
+                Constant::InterfaceMethod {
+                    class_index,
+                    nametype_index,
+                } => (
+                    "InterfaceMethodref",
+                    format!("#{class_index}.#{nametype_index}"),
+                    Some(self.class(class_index).and_then(|class| {
+                        let (name, descriptor) = self.name_and_type(nametype_index)?;
+                        Ok(format!("{class}.{name}:{descriptor}"))
+                    })),
+                ),
+                Constant::NameAndType {
+                    name_index,
+                    descriptor_index,
+                } => (
+                    "NameAndType",
+                    format!("#{name_index}:#{descriptor_index}"),
+                    Some(
+                        self.name_and_type(i as u16 + 1)
+                            .map(|(name, descriptor)| format!("{name}:{descriptor}")),
+                    ),
+                ),
+                Constant::MethodHandle {
+                    reference_kind,
+                    reference_index,
+                } => (
+                    "MethodHandle",
+                    format!("{reference_kind}:#{reference_index}"),
+                    Some(self.method_handle(i as u16 + 1).map(|handle| {
+                        let member = handle.member;
+                        format!(
+                            "{} {}.{}:{}",
+                            reference_kind_name(reference_kind),
+                            member.class,
+                            member.name,
+                            member.descriptor
+                        )
+                    })),
+                ),
+                Constant::MethodType(descriptor_index) => (
+                    "MethodType",
+                    format!("#{descriptor_index}"),
+                    Some(self.utf8(descriptor_index).map(str::to_owned)),
+                ),
+                Constant::Dynamic {
+                    bootstrap_method_attr_index,
+                    nametype_index,
+                } => (
+                    "Dynamic",
+                    format!("#{bootstrap_method_attr_index}:#{nametype_index}"),
+                    Some(
+                        self.name_and_type(nametype_index)
+                            .map(|(name, descriptor)| {
+                                format!("#{bootstrap_method_attr_index}:{name}:{descriptor}")
+                            }),
+                    ),
+                ),
+                Constant::InvokeDynamic {
+                    bootstrap_method_attr_index,
+                    nametype_index,
+                } => (
+                    "InvokeDynamic",
+                    format!("#{bootstrap_method_attr_index}:#{nametype_index}"),
+                    Some(
+                        self.name_and_type(nametype_index)
+                            .map(|(name, descriptor)| {
+                                format!("#{bootstrap_method_attr_index}:{name}:{descriptor}")
+                            }),
+                    ),
+                ),
+                Constant::Module(name_index) => (
+                    "Module",
+                    format!("#{name_index}"),
+                    Some(self.utf8(name_index).map(str::to_owned)),
+                ),
+                Constant::Package(name_index) => (
+                    "Package",
+                    format!("#{name_index}"),
+                    Some(self.utf8(name_index).map(str::to_owned)),
+                ),
+                Constant::Unusable => continue,
+            };
+
+            let index = format!("#{}", i + 1);
+            let line = match comment {
+                Some(Ok(comment)) => format!("{kind:<18} {operands:<14} // {comment}"),
+                Some(Err(err)) => format!("{kind:<18} {operands:<14} // <{err}>"),
+                None => format!("{kind:<18} {operands}"),
+            };
+            let _ = writeln!(out, "{index:>6} = {line}");
+        }
+
+        out
+    }
+}
+
+/// The name javap gives a method handle's reference kind.
+fn reference_kind_name(kind: u8) -> &'static str {
+    match kind {
+        1 => "REF_getField",
+        2 => "REF_getStatic",
+        3 => "REF_putField",
+        4 => "REF_putStatic",
+        5 => "REF_invokeVirtual",
+        6 => "REF_invokeStatic",
+        7 => "REF_invokeSpecial",
+        8 => "REF_newInvokeSpecial",
+        9 => "REF_invokeInterface",
+        _ => "REF_unknown",
+    }
+}
+
+impl Method {
+    /// Lists the method's instructions along with their bytecode offsets.
+    pub fn disassemble(&self, pool: &ConstantPool) -> String {
+        let mut out = String::new();
+
+        for attr in &self.attributes {
+            if let Attribute::Code {
+                max_stack,
+                max_locals,
+                code,
+                exceptions,
+                attributes,
+            } = attr
+            {
+                let _ = writeln!(out, "stack={max_stack}, locals={max_locals}");
+                for (offset, inst) in code {
+                    let _ = writeln!(out, "{}", inst.disassemble(*offset, pool));
+                }
+
+                if !exceptions.is_empty() {
+                    let _ = writeln!(out, "Exception table:");
+                    let _ = writeln!(out, "   from    to  target type");
+                    for entry in exceptions {
+                        let catch_type = match entry.catch_type {
+                            0 => "any".to_owned(),
+                            index => format!("Class {}", pool.class(index).unwrap_or("<invalid>")),
+                        };
+                        let _ = writeln!(
+                            out,
+                            "  {:>5} {:>5} {:>5}   {catch_type}",
+                            entry.start_pc, entry.end_pc, entry.handler_pc
+                        );
+                    }
+                }
+
+                for attr in attributes {
+                    if let Attribute::LineNumberTable(entries) = attr {
+                        let _ = writeln!(out, "LineNumberTable:");
+                        for entry in entries {
+                            let _ =
+                                writeln!(out, "  line {}: {}", entry.line_number, entry.start_pc);
+                        }
+                    }
+                }
+            }
+        }
+
+        out
+    }
+}
+
+impl Instruction {
+    /// Formats the instruction at `offset` the way a line of
+    /// [`Method::disassemble`] does, with a comment describing the constant
+    /// it refers to, if any.
+    pub fn disassemble(&self, offset: u32, pool: &ConstantPool) -> String {
+        let line = format!("{offset:>4}: {}", At(offset, self));
+        match constant_operand(self).map(|index| describe(pool, index)) {
+            Some(Ok(comment)) => format!("{line:<39} // {comment}"),
+            Some(Err(err)) => format!("{line:<39} // <{err}>"),
+            None => line,
+        }
+    }
+}
+
+impl ClassFile {
+    /// Renders the class header, constant pool, and every method's bytecode.
+    pub fn disassemble_full(&self) -> String {
+        let pool = &self.constant_pool;
+        let mut out = String::new();
+        let name = |index| pool.class(index).unwrap_or("<invalid>");
+
+        let _ = writeln!(out, "class {}", name(self.this_class));
+        if let Some(source_file) = self.source_file() {
+            let _ = writeln!(out, "  Compiled from \"{source_file}\"");
+        }
+        let _ = writeln!(out, "  minor version: {}", self.version.minor);
+        let _ = writeln!(out, "  major version: {}", self.version.major);
+        let _ = writeln!(
+            out,
+            "  flags: (0x{:04x}) {:?}",
+            self.access_flags.bits(),
+            self.access_flags
+        );
+        let _ = writeln!(out, "  this_class: #{}", self.this_class);
+        if self.super_class != 0 {
+            let _ = writeln!(
+                out,
+                "  super_class: #{} // {}",
+                self.super_class,
+                name(self.super_class)
+            );
+        }
+        for &interface in &self.interfaces {
+            let _ = writeln!(out, "  interface: #{interface} // {}", name(interface));
+        }
+        let _ = writeln!(
+            out,
+            "  interfaces: {}, fields: {}, methods: {}, attributes: {}",
+            self.interfaces.len(),
+            self.fields.len(),
+            self.methods.len(),
+            self.attributes.len()
+        );
+
+        let _ = writeln!(out, "Constant pool:");
+        out.push_str(&pool.dump());
+
+        let _ = writeln!(out, "{{");
+        for field in &self.fields {
+            let descriptor = pool.utf8(field.descriptor_index).unwrap_or("<invalid>");
+            let _ = writeln!(
+                out,
+                "  {} {};",
+                FieldType::parse(descriptor)
+                    .map(|field_type| pretty::field_type(&field_type, Names::Qualified))
+                    .unwrap_or_else(|_| descriptor.to_owned()),
+                pool.utf8(field.name_index).unwrap_or("<invalid>"),
+            );
+            let _ = writeln!(out, "    descriptor: {descriptor}");
+            let _ = writeln!(out, "    flags: {:?}", field.access_flags);
+            let _ = writeln!(out);
+        }
+        for method in &self.methods {
+            let name = pool.utf8(method.name_index).unwrap_or("<invalid>");
+            let descriptor = pool.utf8(method.descriptor_index).unwrap_or("<invalid>");
+            let _ = writeln!(
+                out,
+                "  {};",
+                MethodDescriptor::parse(descriptor)
+                    .map(|parsed| pretty::method(name, &parsed, Names::Qualified))
+                    .unwrap_or_else(|_| format!("{name}{descriptor}")),
+            );
+            let _ = writeln!(out, "    descriptor: {descriptor}");
+            let _ = writeln!(out, "    flags: {:?}", method.access_flags);
+            let code = method.disassemble(pool);
+            if !code.is_empty() {
+                let _ = writeln!(out, "    Code:");
+                for line in code.lines() {
+                    let _ = writeln!(out, "      {line}");
+                }
+            }
+            if let Ok(exceptions) = method.declared_exceptions(pool) {
+                if !exceptions.is_empty() {
+                    let _ = writeln!(out, "    Exceptions:");
+                    let _ = writeln!(out, "      throws {}", exceptions.join(", "));
+                }
+            }
+            let _ = writeln!(out);
+        }
+        let _ = writeln!(out, "}}");
+
+        for attr in &self.attributes {
+            if let Attribute::Unknown { name, data } = attr {
+                let _ = writeln!(
+                    out,
+                    "{name}: length = 0x{:x} (unknown attribute)",
+                    data.len()
+                );
+                for chunk in data.chunks(16) {
+                    let bytes: Vec<_> = chunk.iter().map(|byte| format!("{byte:02x}")).collect();
+                    let _ = writeln!(out, "   {}", bytes.join(" "));
+                }
+            }
+        }
+
+        out
+    }
+}
+
+/// An instruction along with its offset in the code array, which displays
+/// its branch targets as offsets too.
+struct At<'a>(u32, &'a Instruction);
+
+impl fmt::Display for At<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.1.write(f, Some(self.0))
+    }
+}
+
+/// The constant pool index an instruction refers to, if it has one.
+fn constant_operand(inst: &Instruction) -> Option<u16> {
+    match *inst {
+        Instruction::Ldc(index) => Some(index as u16),
+        Instruction::Ldcw(index)
+        | Instruction::Ldc2w(index)
+        | Instruction::Getstatic(index)
+        | Instruction::Putstatic(index)
+        | Instruction::Getfield(index)
+        | Instruction::Putfield(index)
+        | Instruction::Invokevirtual(index)
+        | Instruction::Invokespecial(index)
+        | Instruction::Invokestatic(index)
+        | Instruction::Invokeinterface(index, _)
+        | Instruction::Invokedynamic(index)
+        | Instruction::New(index)
+        | Instruction::Anewarray(index)
+        | Instruction::Checkcast(index)
+        | Instruction::Instanceof(index)
+        | Instruction::Multianewarray(index, _) => Some(index),
+        _ => None,
+    }
+}
+
+/// Describes the constant at `index` the way javap's comments do, like
+/// `Method java/io/PrintStream.println:(Ljava/lang/String;)V`.
+fn describe(pool: &ConstantPool, index: u16) -> Result<String, ConstantPoolError> {
+    Ok(match *pool.at(index)? {
+        Constant::Integer(value) => format!("int {value}"),
+        Constant::Float(value) => format!("float {value}f"),
+        Constant::Long(value) => format!("long {value}l"),
+        Constant::Double(value) => format!("double {value}d"),
+        Constant::Class(_) => format!("class {}", quoted(pool.class(index)?)),
+        Constant::String(_) => format!("String {}", pool.string(index)?),
+        Constant::Field { .. } => {
+            let field = pool.field_ref(index)?;
+            format!(
+                "Field {}.{}:{}",
+                quoted(field.class),
+                field.name,
+                field.descriptor
+            )
+        }
+        Constant::Method { .. } => {
+            let method = pool.method_ref(index)?;
+            format!(
+                "Method {}.{}:{}",
+                quoted(method.class),
+                quoted(method.name),
+                method.descriptor
+            )
+        }
+        Constant::InterfaceMethod {
+            class_index,
+            nametype_index,
+        } => {
+            let (name, descriptor) = pool.name_and_type(nametype_index)?;
+            format!(
+                "InterfaceMethod {}.{}:{descriptor}",
+                quoted(pool.class(class_index)?),
+                quoted(name)
+            )
+        }
+        Constant::MethodType(_) => format!("MethodType {}", pool.method_type(index)?),
+        Constant::InvokeDynamic {
+            bootstrap_method_attr_index,
+            nametype_index,
+        } => {
+            let (name, descriptor) = pool.name_and_type(nametype_index)?;
+            format!("InvokeDynamic #{bootstrap_method_attr_index}:{name}:{descriptor}")
+        }
+        ref constant => format!("{:?}", constant.kind()),
+    })
+}
+
+/// Quotes the names javap quotes: array classes and the `<init>` and
+/// `<clinit>` methods.
+fn quoted(name: &str) -> String {
+    if name.starts_with('[') || name.starts_with('<') {
+        format!("\"{name}\"")
+    } else {
+        name.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_class_checked;
+
+    const SAMPLE: &[u8] = include_bytes!("../tests/fixtures/Sample.class");
+
+    #[test]
+    fn full_disassembly_has_the_header_pool_and_code() {
+        let class = parse_class_checked(SAMPLE).unwrap();
+        let text = class.disassemble_full();
+
+        let header = "\
+class Sample
+  Compiled from \"Sample.java\"
+  minor version: 0
+  major version: 61
+  flags: (0x0021) PUBLIC | SUPER
+  this_class: #12
+  super_class: #2 // java/lang/Object
+  interfaces: 0, fields: 0, methods: 7, attributes: 2
+Constant pool:
+    #1 = Methodref          #2.#3          // java/lang/Object.<init>:()V
+    #2 = Class              #4             // java/lang/Object
+    #3 = NameAndType        #5:#6          // <init>:()V
+    #4 = Utf8               java/lang/Object
+";
+        assert!(text.starts_with(header), "{text}");
+
+        let sum = "\
+  int sum(int);
+    descriptor: (I)I
+    flags: STATIC
+    Code:
+      stack=2, locals=3
+         0: iconst_0
+         1: istore_1
+         2: iconst_0
+         3: istore_2
+         4: iload_2
+         5: iload_0
+         6: if_icmpge     19
+         9: iload_1
+        10: iload_2
+        11: iadd
+        12: istore_1
+        13: iinc          2, 1
+        16: goto          4
+        19: iload_1
+        20: ireturn
+";
+        assert!(text.contains(sum), "{text}");
+        assert!(text.contains("    Exceptions:\n      throws java/io/IOException\n"));
+        assert!(text.ends_with("}\n"));
+    }
+
+    #[test]
+    fn method_disassembly_resolves_constant_operands() {
+        let class = parse_class_checked(SAMPLE).unwrap();
+        let read = class.get_method("read", "()V").unwrap();
+        assert_eq!(
+            read.disassemble(&class.constant_pool),
+            "\
+stack=2, locals=1
+   0: new           #9                  // class java/io/IOException
+   3: dup
+   4: invokespecial #11                 // Method java/io/IOException.\"<init>\":()V
+   7: athrow
+LineNumberTable:
+  line 34: 0
+"
+        );
+    }
+
+    #[test]
+    fn instruction_comments_describe_their_constant() {
+        let mut builder = ConstantPool::builder();
+        let println = builder
+            .add_method_ref("java/io/PrintStream", "println", "(Ljava/lang/String;)V")
+            .unwrap();
+        let dangling = builder.add_integer(0).unwrap() + 1;
+        let pool = builder.build();
+
+        assert_eq!(
+            Instruction::Invokevirtual(println).disassemble(5, &pool),
+            "   5: invokevirtual #6                  \
+             // Method java/io/PrintStream.println:(Ljava/lang/String;)V"
+        );
+        assert!(Instruction::Getstatic(dangling)
+            .disassemble(0, &pool)
+            .ends_with(&format!(
+                "// <{}>",
+                ConstantPoolError::InvalidIndex(dangling)
+            )));
+        assert_eq!(Instruction::Iadd.disassemble(12, &pool), "  12: iadd");
+    }
+
+    #[test]
+    fn code_matches_javap_for_hello_world() {
+        let class =
+            parse_class_checked(include_bytes!("../../../tests/fixtures/Hello.class")).unwrap();
+        let code = |name, descriptor| {
+            let method = class.get_method(name, descriptor).unwrap();
+            // The instructions sit between the stack size and the line numbers.
+            let listing = method.disassemble(&class.constant_pool);
+            listing
+                .lines()
+                .skip(1)
+                .take_while(|line| line.starts_with(' '))
+                .map(str::trim)
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+
+        // The output of `javap -c Hello`, without its indentation.
+        assert_eq!(
+            code("<init>", "()V"),
+            "\
+0: aload_0
+1: invokespecial #1                  // Method java/lang/Object.\"<init>\":()V
+4: return"
+        );
+        assert_eq!(
+            code("main", "([Ljava/lang/String;)V"),
+            "\
+0: getstatic     #7                  // Field java/lang/System.out:Ljava/io/PrintStream;
+3: ldc           #13                 // String hello from the classpath
+5: invokevirtual #15                 // Method java/io/PrintStream.println:(Ljava/lang/String;)V
+8: return"
+        );
+    }
+}
@@ -0,0 +1,430 @@
+use crate::{ConstantKind, ConstantPoolViolation};
+use nom::error::{ErrorKind, ParseError};
+use std::{fmt, io};
+
+/// An error produced while parsing a class file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsingError {
+    /// The number of input bytes that were left when the error occurred.
+    /// Attributes are parsed from their own slice of the file, so this only
+    /// counts the bytes left in that slice.
+    pub remaining: usize,
+    pub kind: ParsingErrorKind,
+    /// The address of the byte the error occurred at, which locates it
+    /// within the whole file no matter which slice was being parsed.
+    address: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParsingErrorKind {
+    /// A nom combinator failed.
+    Nom(ErrorKind),
+    /// The Utf8 constant at the given index is not valid modified UTF-8.
+    MalformedUtf8 { index: u16 },
+    /// The attribute whose name is the constant at the given index holds
+    /// text that is not valid modified UTF-8.
+    MalformedUtf8Attribute { name_index: u16 },
+    /// Bytes that are not valid modified UTF-8, found outside any context
+    /// that could name them.
+    MalformedModifiedUtf8,
+    /// The class has both of two attributes that must not appear together.
+    ConflictingAttributes(&'static str, &'static str),
+    /// A `newarray` instruction's type code doesn't name a primitive type.
+    InvalidArrayType(u8),
+    /// A `multianewarray` instruction creates an array of no dimensions.
+    ZeroDimensions,
+    /// A `tableswitch` instruction's lowest key is above its highest.
+    InvalidSwitchRange { low: i32, high: i32 },
+    /// The long or double constant at the given index is the last entry of
+    /// the constant pool, leaving no room for the entry after it.
+    WideConstantAtEnd { index: u16 },
+    /// An attribute that holds other attributes, named by the constant at
+    /// the given index, is itself inside a Code or Record attribute.
+    NestedAttribute { name_index: u16 },
+    /// An annotation's element values are nested too deeply inside arrays
+    /// and other annotations.
+    ElementValueTooDeep,
+}
+
+impl ParsingError {
+    pub(crate) fn new(input: &[u8], kind: ParsingErrorKind) -> ParsingError {
+        ParsingError {
+            remaining: input.len(),
+            kind,
+            address: input.as_ptr() as usize,
+        }
+    }
+
+    /// Replaces the kind of the error, keeping its location.
+    pub(crate) fn with_kind(self, kind: ParsingErrorKind) -> ParsingError {
+        ParsingError { kind, ..self }
+    }
+
+    /// The offset of the byte the error occurred at from the start of
+    /// `input`, which must be the buffer that was being parsed.
+    pub(crate) fn offset(&self, input: &[u8]) -> usize {
+        self.address.saturating_sub(input.as_ptr() as usize)
+    }
+}
+
+impl ParseError<&[u8]> for ParsingError {
+    fn from_error_kind(input: &[u8], kind: ErrorKind) -> Self {
+        ParsingError::new(input, ParsingErrorKind::Nom(kind))
+    }
+
+    fn append(_: &[u8], _: ErrorKind, other: Self) -> Self {
+        other
+    }
+}
+
+impl fmt::Display for ParsingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind)
+    }
+}
+
+impl fmt::Display for ParsingErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ParsingErrorKind::Nom(kind) => write!(f, "{}", kind.description()),
+            ParsingErrorKind::MalformedUtf8 { index } => {
+                write!(f, "constant #{index} is not valid modified UTF-8")
+            }
+            ParsingErrorKind::MalformedUtf8Attribute { name_index } => write!(
+                f,
+                "attribute named by constant #{name_index} is not valid modified UTF-8"
+            ),
+            ParsingErrorKind::MalformedModifiedUtf8 => {
+                write!(f, "bytes are not valid modified UTF-8")
+            }
+            ParsingErrorKind::ConflictingAttributes(first, second) => {
+                write!(f, "class has both {first} and {second} attributes")
+            }
+            ParsingErrorKind::InvalidArrayType(code) => {
+                write!(f, "newarray has invalid array type code {code}")
+            }
+            ParsingErrorKind::ZeroDimensions => {
+                write!(f, "multianewarray creates an array of zero dimensions")
+            }
+            ParsingErrorKind::InvalidSwitchRange { low, high } => {
+                write!(f, "tableswitch has low key {low} above high key {high}")
+            }
+            ParsingErrorKind::WideConstantAtEnd { index } => {
+                write!(f, "constant #{index} takes two entries but is the last")
+            }
+            ParsingErrorKind::NestedAttribute { name_index } => write!(
+                f,
+                "attribute named by constant #{name_index} is nested inside another attribute"
+            ),
+            ParsingErrorKind::ElementValueTooDeep => {
+                write!(f, "annotation element values are nested too deeply")
+            }
+        }
+    }
+}
+
+/// An error produced by [`parse_class_checked`](crate::parse_class_checked),
+/// [`parse_class_validated`](crate::parse_class_validated), or
+/// [`ClassFile::from_reader`](crate::ClassFile::from_reader).
+#[derive(Debug)]
+pub enum ClassFileError {
+    /// The class file could not be read.
+    Io(io::Error),
+    /// The class file is malformed. The offset is that of the byte parsing
+    /// failed at, from the start of the file.
+    Malformed {
+        offset: usize,
+        kind: ParsingErrorKind,
+    },
+    /// The class file parsed, but constants in its pool refer to each other
+    /// in ways the JVM specification forbids.
+    InvalidConstantPool(Vec<ConstantPoolViolation>),
+}
+
+impl From<io::Error> for ClassFileError {
+    fn from(err: io::Error) -> Self {
+        ClassFileError::Io(err)
+    }
+}
+
+impl fmt::Display for ClassFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClassFileError::Io(err) => write!(f, "{err}"),
+            ClassFileError::Malformed { offset, kind } => {
+                write!(f, "{kind} at byte offset {offset}")
+            }
+            ClassFileError::InvalidConstantPool(violations) => {
+                write!(f, "malformed constant pool:")?;
+                for violation in violations {
+                    write!(f, "\n  {violation}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// An error produced when resolving an entry of the constant pool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConstantPoolError {
+    /// The index is zero or past the end of the pool.
+    InvalidIndex(u16),
+    /// The constant at `index` is not of the kind that was asked for.
+    UnexpectedKind {
+        index: u16,
+        expected: ConstantKind,
+        found: ConstantKind,
+    },
+    /// There is no room left in the pool's 16-bit index space.
+    Overflow,
+    /// No Utf8 constant holds the name of an attribute being written out.
+    MissingAttributeName,
+}
+
+impl fmt::Display for ConstantPoolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConstantPoolError::InvalidIndex(index) => {
+                write!(f, "constant pool index #{index} is out of range")
+            }
+            ConstantPoolError::UnexpectedKind {
+                index,
+                expected,
+                found,
+            } => write!(
+                f,
+                "expected constant #{index} to be {expected:?}, found {found:?}"
+            ),
+            ConstantPoolError::Overflow => write!(f, "constant pool is full"),
+            ConstantPoolError::MissingAttributeName => {
+                write!(f, "no constant holds the name of an attribute")
+            }
+        }
+    }
+}
+
+/// An error produced by [`encode_code`](crate::encode_code). The offsets are
+/// those the instructions were paired with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncodeError {
+    /// A branch of the instruction at `offset` targets an offset no
+    /// instruction was paired with.
+    InvalidBranchTarget { offset: u32, target: i64 },
+    /// A conditional branch at `offset` can no longer reach its target, as
+    /// its offset doesn't fit in 16 bits.
+    BranchOutOfRange { offset: u32 },
+}
+
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EncodeError::InvalidBranchTarget { offset, target } => write!(
+                f,
+                "branch at {offset} targets {target}, which is not an instruction"
+            ),
+            EncodeError::BranchOutOfRange { offset } => {
+                write!(f, "branch at {offset} is too far from its target")
+            }
+        }
+    }
+}
+
+/// An error produced by [`check_maxs`](crate::verify::check_maxs) when a
+/// method's code could overrun its operand stack or local variables. The
+/// offsets are those of the offending instructions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerifyError {
+    /// The instruction could push more values than `max_stack` allows.
+    StackOverflow {
+        offset: u32,
+        max_stack: u16,
+    },
+    /// The instruction could pop more values than are on the stack.
+    StackUnderflow {
+        offset: u32,
+    },
+    /// The instruction uses a local variable past `max_locals`.
+    InvalidLocal {
+        offset: u32,
+        index: u16,
+    },
+    /// The method's parameters don't fit in its local variables.
+    TooManyParameters {
+        parameters: u32,
+        max_locals: u16,
+    },
+    /// Two paths reach the instruction with different stack depths.
+    InconsistentStack {
+        offset: u32,
+    },
+    /// A branch or exception handler targets an offset with no instruction.
+    InvalidBranchTarget(i64),
+    /// The instruction at `offset` branches to a target outside the code.
+    BranchOutOfRange {
+        offset: u32,
+        target: i64,
+    },
+    /// The instruction at `offset` branches into the middle of another
+    /// instruction.
+    BranchIntoInstruction {
+        offset: u32,
+        target: u32,
+    },
+    /// An exception handler's range doesn't start and end at instructions,
+    /// or is empty.
+    InvalidExceptionRange {
+        start_pc: u16,
+        end_pc: u16,
+    },
+    /// An exception handler doesn't start at an instruction.
+    InvalidHandler {
+        handler_pc: u16,
+    },
+    /// Execution can run past the last instruction.
+    FallsOffEnd,
+    /// The instruction's opcode is not one the JVM defines.
+    UnknownOpcode {
+        offset: u32,
+        opcode: u8,
+    },
+    ConstantPool(ConstantPoolError),
+}
+
+impl From<ConstantPoolError> for VerifyError {
+    fn from(err: ConstantPoolError) -> Self {
+        VerifyError::ConstantPool(err)
+    }
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerifyError::StackOverflow { offset, max_stack } => write!(
+                f,
+                "instruction at {offset} overflows the operand stack of {max_stack} slots"
+            ),
+            VerifyError::StackUnderflow { offset } => {
+                write!(f, "instruction at {offset} underflows the operand stack")
+            }
+            VerifyError::InvalidLocal { offset, index } => {
+                write!(
+                    f,
+                    "instruction at {offset} uses invalid local variable {index}"
+                )
+            }
+            VerifyError::TooManyParameters {
+                parameters,
+                max_locals,
+            } => write!(
+                f,
+                "{parameters} parameter slots don't fit in {max_locals} local variables"
+            ),
+            VerifyError::InconsistentStack { offset } => {
+                write!(
+                    f,
+                    "stack depth differs between paths to instruction at {offset}"
+                )
+            }
+            VerifyError::InvalidBranchTarget(target) => {
+                write!(f, "branch target {target} is not an instruction")
+            }
+            VerifyError::BranchOutOfRange { offset, target } => {
+                write!(
+                    f,
+                    "instruction at {offset} branches outside the code to {target}"
+                )
+            }
+            VerifyError::BranchIntoInstruction { offset, target } => write!(
+                f,
+                "instruction at {offset} branches into the middle of an instruction at {target}"
+            ),
+            VerifyError::InvalidExceptionRange { start_pc, end_pc } => {
+                write!(f, "invalid exception handler range {start_pc} to {end_pc}")
+            }
+            VerifyError::InvalidHandler { handler_pc } => {
+                write!(f, "exception handler at {handler_pc} is not an instruction")
+            }
+            VerifyError::FallsOffEnd => write!(f, "execution can fall off the end of the code"),
+            VerifyError::UnknownOpcode { offset, opcode } => {
+                write!(f, "unknown opcode 0x{opcode:02x} at {offset}")
+            }
+            VerifyError::ConstantPool(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+/// An error produced while parsing a field or method descriptor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DescriptorError {
+    /// The descriptor ended where a type was expected.
+    UnexpectedEnd,
+    /// A method descriptor doesn't start with a parameter list.
+    MissingParameters,
+    /// The character doesn't start any type.
+    InvalidType(char),
+    /// A class name was never closed by a `;`.
+    UnterminatedClassName,
+    /// A class name is empty or has an empty or invalid package or class
+    /// part.
+    InvalidClassName(String),
+    /// An array type has more than 255 dimensions.
+    TooManyDimensions,
+    /// There is more after the end of the type.
+    TrailingCharacters(String),
+}
+
+impl fmt::Display for DescriptorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DescriptorError::UnexpectedEnd => {
+                write!(f, "descriptor ends where a type was expected")
+            }
+            DescriptorError::MissingParameters => {
+                write!(f, "method descriptor does not start with '('")
+            }
+            DescriptorError::InvalidType(c) => write!(f, "'{c}' does not start a type"),
+            DescriptorError::UnterminatedClassName => write!(f, "class name is missing its ';'"),
+            DescriptorError::InvalidClassName(name) => write!(f, "invalid class name \"{name}\""),
+            DescriptorError::TooManyDimensions => {
+                write!(f, "array type has more than 255 dimensions")
+            }
+            DescriptorError::TrailingCharacters(rest) => {
+                write!(f, "unexpected \"{rest}\" after the end of the type")
+            }
+        }
+    }
+}
+
+/// An error produced while parsing a generic signature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignatureError {
+    /// The signature ended before the type it was spelling out did.
+    UnexpectedEnd,
+    /// The character can't appear where it does.
+    UnexpectedCharacter { position: usize, found: char },
+    /// There is more after the end of the signature.
+    TrailingCharacters(String),
+    /// An array type has more than 255 dimensions.
+    TooManyDimensions,
+    /// Type arguments are nested more deeply than any real signature would.
+    TooDeep,
+}
+
+impl fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignatureError::UnexpectedEnd => write!(f, "signature ends unexpectedly"),
+            SignatureError::UnexpectedCharacter { position, found } => {
+                write!(f, "unexpected '{found}' at {position} in signature")
+            }
+            SignatureError::TrailingCharacters(rest) => {
+                write!(f, "unexpected \"{rest}\" after the end of the signature")
+            }
+            SignatureError::TooManyDimensions => {
+                write!(f, "array type in signature has more than 255 dimensions")
+            }
+            SignatureError::TooDeep => write!(f, "type arguments in signature nest too deeply"),
+        }
+    }
+}
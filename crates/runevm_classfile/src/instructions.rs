@@ -1,26 +1,32 @@
+use crate::error::{EncodeError, ParsingError, ParsingErrorKind};
 use nom::{
-    combinator::{map, success},
-    number::complete::{be_u16, be_u8},
+    bytes::complete::{tag, take},
+    combinator::{fail, map, success},
+    multi::count,
+    number::complete::{be_i16, be_i32, be_i8, be_u16, be_u32, be_u8},
+    sequence::{preceded, tuple},
     IResult,
 };
+use std::{collections::HashMap, fmt};
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub enum Instruction {
-    /// Represents an opcode that is not handled.
+    /// An opcode the JVM doesn't define, like `breakpoint` or one of the
+    /// reserved `impdep` opcodes.
     Error(u8),
 
     Aaload,
     Aastore,
     AconstNull,
-    Aload(u8),
+    Aload(u16),
     Anewarray(u16),
     Areturn,
     Arraylength,
-    Astore(u8),
+    Astore(u16),
     Athrow,
     Baload,
     Bastore,
-    Bipush(u8),
+    Bipush(i8),
     Caload,
     Castore,
     Checkcast(u16),
@@ -35,12 +41,12 @@ pub enum Instruction {
     Dconst0,
     Dconst1,
     Ddiv,
-    Dload(u8),
+    Dload(u16),
     Dmul,
     Dneg,
     Drem,
     Dreturn,
-    Dstore,
+    Dstore(u16),
     Dsub,
     Dup,
     DupX1,
@@ -56,16 +62,16 @@ pub enum Instruction {
     Fastore,
     Fcmpg,
     Fcmpl,
+    Fconst0,
     Fconst1,
     Fconst2,
-    Fconst3,
     Fdiv,
-    Fload(u8),
+    Fload(u16),
     Fmul,
     Fneg,
     Frem,
     Freturn,
-    Fstore(u8),
+    Fstore(u16),
     Fsub,
     Getfield(u16),
     Getstatic(u16),
@@ -94,8 +100,14 @@ pub enum Instruction {
     If(ComparisonKind, i16),
     Ifnonnull(i16),
     Ifnull(i16),
-    Iinc(u8, i8),
-    Iload(u8),
+    Iinc {
+        index: u16,
+        constant: i16,
+        /// Whether the instruction is prefixed by `wide`, which it must be
+        /// if the operands don't fit in a byte but may be anyway.
+        wide: bool,
+    },
+    Iload(u16),
     Imul,
     Ineg,
     Instanceof(u16),
@@ -109,7 +121,7 @@ pub enum Instruction {
     Ireturn,
     Ishl,
     Ishr,
-    Istore,
+    Istore(u16),
     Isub,
     Iushr,
     Ixor,
@@ -129,38 +141,57 @@ pub enum Instruction {
     Ldcw(u16),
     Ldc2w(u16),
     Ldiv,
-    Lload(u8),
+    Lload(u16),
     Lmul,
     Lneg,
-    Lookupswitch, // TODO: i aint doing allat
+    /// Jumps to the offset paired with the matching key, or to `default`
+    /// if no key matches. The pairs are sorted by key.
+    Lookupswitch {
+        default: i32,
+        pairs: Vec<(i32, i32)>,
+    },
     Lor,
     Lrem,
     Lreturn,
     Lshl,
     Lshr,
-    Lstore(u8),
+    Lstore(u16),
     Lsub,
     Lushr,
     Lxor,
     Monitorenter,
     Monitorexit,
+    /// Creates an array of the class at the index, popping a length for
+    /// each of its first dimensions, of which there is at least one.
     Multianewarray(u16, u8),
     New(u16),
-    Newarray(u8),
+    Newarray(ArrayType),
     Nop,
     Pop,
     Pop2,
     Putfield(u16),
     Putstatic(u16),
-    Ret(u8),
+    Ret(u16),
     Return,
     Saload,
     Sastore,
-    Sipush(u16),
+    Sipush(i16),
     Swap,
-    Tableswitch, // TODO: i aint doing allat pt.2
+    /// Jumps to the offset at `index - low` for an index between `low` and
+    /// `high`, or to `default` otherwise.
+    Tableswitch {
+        default: i32,
+        low: i32,
+        high: i32,
+        offsets: Vec<i32>,
+    },
+    /// A load, store, or `ret` prefixed by `wide` although its index fits in
+    /// a byte, stored as that instruction's opcode and the index.
     Wide(u8, u16),
-    Wide2(u16, u16),
+    /// A load or store of one of the first four locals that takes the index
+    /// as an operand instead of using its one-byte form, stored as the
+    /// instruction's opcode and the index.
+    Local(u8, u8),
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -173,23 +204,1792 @@ pub enum ComparisonKind {
     Le,
 }
 
-pub(crate) fn instruction(input: &[u8]) -> IResult<&[u8], Instruction> {
+/// The element type of an array created by `newarray`, numbered by its type
+/// code in the instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArrayType {
+    Boolean = 4,
+    Char = 5,
+    Float = 6,
+    Double = 7,
+    Byte = 8,
+    Short = 9,
+    Int = 10,
+    Long = 11,
+}
+
+impl ArrayType {
+    /// Returns the descriptor of the element type, like `I` for `int`.
+    pub fn descriptor(self) -> char {
+        match self {
+            ArrayType::Boolean => 'Z',
+            ArrayType::Char => 'C',
+            ArrayType::Float => 'F',
+            ArrayType::Double => 'D',
+            ArrayType::Byte => 'B',
+            ArrayType::Short => 'S',
+            ArrayType::Int => 'I',
+            ArrayType::Long => 'J',
+        }
+    }
+
+    /// Returns the Java keyword for the element type.
+    pub fn name(self) -> &'static str {
+        match self {
+            ArrayType::Boolean => "boolean",
+            ArrayType::Char => "char",
+            ArrayType::Float => "float",
+            ArrayType::Double => "double",
+            ArrayType::Byte => "byte",
+            ArrayType::Short => "short",
+            ArrayType::Int => "int",
+            ArrayType::Long => "long",
+        }
+    }
+
+    /// Returns the size of one element in bytes. Booleans take up a byte
+    /// each, as in `baload` and `bastore`.
+    pub fn element_size(self) -> usize {
+        match self {
+            ArrayType::Boolean | ArrayType::Byte => 1,
+            ArrayType::Char | ArrayType::Short => 2,
+            ArrayType::Float | ArrayType::Int => 4,
+            ArrayType::Double | ArrayType::Long => 8,
+        }
+    }
+}
+
+impl TryFrom<u8> for ArrayType {
+    /// The type code, which doesn't name a primitive type.
+    type Error = u8;
+
+    fn try_from(code: u8) -> Result<Self, Self::Error> {
+        Ok(match code {
+            4 => ArrayType::Boolean,
+            5 => ArrayType::Char,
+            6 => ArrayType::Float,
+            7 => ArrayType::Double,
+            8 => ArrayType::Byte,
+            9 => ArrayType::Short,
+            10 => ArrayType::Int,
+            11 => ArrayType::Long,
+            _ => return Err(code),
+        })
+    }
+}
+
+/// Decodes a method's bytecode, pairing each instruction with its byte offset
+/// from the start of the code array. Branch targets are relative to these
+/// offsets, so they must be kept around.
+pub(crate) fn code(input: &[u8]) -> IResult<&[u8], Vec<(u32, Instruction)>, ParsingError> {
+    let mut instructions = Vec::new();
+    let mut remaining = input;
+
+    while !remaining.is_empty() {
+        let offset = (input.len() - remaining.len()) as u32;
+        let (rest, inst) = instruction(remaining, offset)?;
+        instructions.push((offset, inst));
+        remaining = rest;
+    }
+
+    Ok((remaining, instructions))
+}
+
+/// Encodes a method's bytecode, the inverse of decoding it. Each instruction
+/// is paired with the offset its branches were computed from, so
+/// instructions can be added, removed, or changed in size and the branches
+/// are pointed back at the same instructions. A `goto` or `jsr` that can no
+/// longer reach its target is widened to `goto_w` or `jsr_w`; any other
+/// branch that can't is an error.
+pub fn encode_code(code: &[(u32, Instruction)]) -> Result<Vec<u8>, EncodeError> {
+    let indices: HashMap<u32, usize> = code
+        .iter()
+        .enumerate()
+        .map(|(index, &(offset, _))| (offset, index))
+        .collect();
+
+    // The index of the instruction each branch operand targets.
+    let targets = code
+        .iter()
+        .map(|&(offset, ref inst)| {
+            inst.branches()
+                .into_iter()
+                .map(|relative| {
+                    let target = offset as i64 + relative as i64;
+                    u32::try_from(target)
+                        .ok()
+                        .and_then(|target| indices.get(&target).copied())
+                        .ok_or(EncodeError::InvalidBranchTarget { offset, target })
+                })
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut instructions: Vec<_> = code.iter().map(|(_, inst)| inst.clone()).collect();
+    loop {
+        // Lay the instructions out first, as how far a branch has to go
+        // depends on the size of everything in between.
+        let mut out = Vec::new();
+        let mut offsets = Vec::with_capacity(instructions.len());
+        for inst in &instructions {
+            offsets.push(out.len() as i64);
+            inst.encode(&mut out);
+        }
+
+        let mut widened = false;
+        let mut retargeted = Vec::with_capacity(instructions.len());
+        for (index, inst) in instructions.iter_mut().enumerate() {
+            let branches: Vec<_> = targets[index]
+                .iter()
+                .map(|&target| (offsets[target] - offsets[index]) as i32)
+                .collect();
+            match inst.with_branches(&branches) {
+                Some(inst) => retargeted.push(inst),
+                None => {
+                    *inst = match *inst {
+                        Instruction::Goto(_) => Instruction::Gotow(0),
+                        Instruction::Jsr(_) => Instruction::Jsrw(0),
+                        _ => {
+                            return Err(EncodeError::BranchOutOfRange {
+                                offset: code[index].0,
+                            })
+                        }
+                    };
+                    widened = true;
+                }
+            }
+        }
+
+        // Widening moves everything after it, so lay the code out again.
+        if !widened {
+            out.clear();
+            for inst in &retargeted {
+                inst.encode(&mut out);
+            }
+            return Ok(out);
+        }
+    }
+}
+
+/// Decodes the instruction at `offset` in the code array, which the switch
+/// instructions need to find where their operands start.
+fn instruction(input: &[u8], offset: u32) -> IResult<&[u8], Instruction, ParsingError> {
     let (input, opcode) = be_u8(input)?;
     match opcode {
         0x32 => zero_operands(Instruction::Aaload)(input),
         0x53 => zero_operands(Instruction::Aastore)(input),
         0x1 => zero_operands(Instruction::AconstNull)(input),
-        0x19 => map(be_u8, |index| Instruction::Aload(index))(input),
-        0x2a..=0x2d => zero_operands(Instruction::Aload(opcode - 42))(input),
+        0x19 => local(opcode, Instruction::Aload)(input),
+        0x2a..=0x2d => zero_operands(Instruction::Aload((opcode - 42).into()))(input),
+        0x3a => local(opcode, Instruction::Astore)(input),
+        0x4b..=0x4e => zero_operands(Instruction::Astore((opcode - 75).into()))(input),
+        0xbf => zero_operands(Instruction::Athrow)(input),
+        0xa7 => map(be_i16, Instruction::Goto)(input),
+        0xc8 => map(be_i32, Instruction::Gotow)(input),
+        0xa8 => map(be_i16, Instruction::Jsr)(input),
+        0xc9 => map(be_i32, Instruction::Jsrw)(input),
+        0x99..=0x9e => map(be_i16, |branch| {
+            Instruction::If(comparison_kind(opcode - 0x99), branch)
+        })(input),
+        0x9f..=0xa4 => map(be_i16, |branch| {
+            Instruction::Ificmp(comparison_kind(opcode - 0x9f), branch)
+        })(input),
+        0xa5..=0xa6 => map(be_i16, |branch| {
+            Instruction::Ifacmp(comparison_kind(opcode - 0xa5), branch)
+        })(input),
+        0xc6 => map(be_i16, Instruction::Ifnull)(input),
+        0xc7 => map(be_i16, Instruction::Ifnonnull)(input),
+        // Switch operands are aligned to a multiple of four bytes from the
+        // start of the code array, after zero to three bytes of padding.
+        0xaa => preceded(take(3 - offset % 4), tableswitch)(input),
+        0xab => preceded(take(3 - offset % 4), lookupswitch)(input),
+        0x2 => zero_operands(Instruction::IconstM1)(input),
+        0x3 => zero_operands(Instruction::Iconst0)(input),
+        0x4 => zero_operands(Instruction::Iconst1)(input),
+        0x5 => zero_operands(Instruction::Iconst2)(input),
+        0x6 => zero_operands(Instruction::Iconst3)(input),
+        0x7 => zero_operands(Instruction::Iconst4)(input),
+        0x8 => zero_operands(Instruction::Iconst5)(input),
+        0x15 => local(opcode, Instruction::Iload)(input),
+        0x1a..=0x1d => zero_operands(Instruction::Iload((opcode - 26).into()))(input),
+        0x36 => local(opcode, Instruction::Istore)(input),
+        0x3b..=0x3e => zero_operands(Instruction::Istore((opcode - 59).into()))(input),
+        0x9 => zero_operands(Instruction::Lconst0)(input),
+        0xa => zero_operands(Instruction::Lconst1)(input),
+        0x16 => local(opcode, Instruction::Lload)(input),
+        0x1e..=0x21 => zero_operands(Instruction::Lload((opcode - 30).into()))(input),
+        0x37 => local(opcode, Instruction::Lstore)(input),
+        0x3f..=0x42 => zero_operands(Instruction::Lstore((opcode - 63).into()))(input),
+        0x61 => zero_operands(Instruction::Ladd)(input),
+        0x65 => zero_operands(Instruction::Lsub)(input),
+        0x69 => zero_operands(Instruction::Lmul)(input),
+        0x6d => zero_operands(Instruction::Ldiv)(input),
+        0x71 => zero_operands(Instruction::Lrem)(input),
+        0x75 => zero_operands(Instruction::Lneg)(input),
+        0x79 => zero_operands(Instruction::Lshl)(input),
+        0x7b => zero_operands(Instruction::Lshr)(input),
+        0x7d => zero_operands(Instruction::Lushr)(input),
+        0x7f => zero_operands(Instruction::Land)(input),
+        0x81 => zero_operands(Instruction::Lor)(input),
+        0x83 => zero_operands(Instruction::Lxor)(input),
+        0x94 => zero_operands(Instruction::Lcmp)(input),
+        0xb => zero_operands(Instruction::Fconst0)(input),
+        0xc => zero_operands(Instruction::Fconst1)(input),
+        0xd => zero_operands(Instruction::Fconst2)(input),
+        0x17 => local(opcode, Instruction::Fload)(input),
+        0x22..=0x25 => zero_operands(Instruction::Fload((opcode - 34).into()))(input),
+        0x38 => local(opcode, Instruction::Fstore)(input),
+        0x43..=0x46 => zero_operands(Instruction::Fstore((opcode - 67).into()))(input),
+        0x62 => zero_operands(Instruction::Fadd)(input),
+        0x66 => zero_operands(Instruction::Fsub)(input),
+        0x6a => zero_operands(Instruction::Fmul)(input),
+        0x6e => zero_operands(Instruction::Fdiv)(input),
+        0x72 => zero_operands(Instruction::Frem)(input),
+        0x76 => zero_operands(Instruction::Fneg)(input),
+        0x95 => zero_operands(Instruction::Fcmpl)(input),
+        0x96 => zero_operands(Instruction::Fcmpg)(input),
+        0xe => zero_operands(Instruction::Dconst0)(input),
+        0xf => zero_operands(Instruction::Dconst1)(input),
+        0x18 => local(opcode, Instruction::Dload)(input),
+        0x26..=0x29 => zero_operands(Instruction::Dload((opcode - 38).into()))(input),
+        0x39 => local(opcode, Instruction::Dstore)(input),
+        0x47..=0x4a => zero_operands(Instruction::Dstore((opcode - 71).into()))(input),
+        0x63 => zero_operands(Instruction::Dadd)(input),
+        0x67 => zero_operands(Instruction::Dsub)(input),
+        0x6b => zero_operands(Instruction::Dmul)(input),
+        0x6f => zero_operands(Instruction::Ddiv)(input),
+        0x73 => zero_operands(Instruction::Drem)(input),
+        0x77 => zero_operands(Instruction::Dneg)(input),
+        0x97 => zero_operands(Instruction::Dcmpl)(input),
+        0x98 => zero_operands(Instruction::Dcmpg)(input),
+        0x85 => zero_operands(Instruction::I2l)(input),
+        0x86 => zero_operands(Instruction::I2f)(input),
+        0x87 => zero_operands(Instruction::I2d)(input),
+        0x88 => zero_operands(Instruction::L2i)(input),
+        0x89 => zero_operands(Instruction::L2f)(input),
+        0x8a => zero_operands(Instruction::L2d)(input),
+        0x8b => zero_operands(Instruction::F2i)(input),
+        0x8c => zero_operands(Instruction::F2l)(input),
+        0x8d => zero_operands(Instruction::F2d)(input),
+        0x8e => zero_operands(Instruction::D2i)(input),
+        0x8f => zero_operands(Instruction::D2l)(input),
+        0x90 => zero_operands(Instruction::D2f)(input),
+        0x91 => zero_operands(Instruction::I2b)(input),
+        0x92 => zero_operands(Instruction::I2c)(input),
+        0x93 => zero_operands(Instruction::I2s)(input),
+        0x57 => zero_operands(Instruction::Pop)(input),
+        0x58 => zero_operands(Instruction::Pop2)(input),
+        0x59 => zero_operands(Instruction::Dup)(input),
+        0x5a => zero_operands(Instruction::DupX1)(input),
+        0x5b => zero_operands(Instruction::DupX2)(input),
+        0x5c => zero_operands(Instruction::Dup2)(input),
+        0x5d => zero_operands(Instruction::Dup2X1)(input),
+        0x5e => zero_operands(Instruction::Dup2X2)(input),
+        0x5f => zero_operands(Instruction::Swap)(input),
+        0x84 => map(tuple((be_u8, be_i8)), |(index, constant)| {
+            Instruction::Iinc {
+                index: index as u16,
+                constant: constant as i16,
+                wide: false,
+            }
+        })(input),
+        0xc4 => wide(input),
+        0xac => zero_operands(Instruction::Ireturn)(input),
         0xb1 => zero_operands(Instruction::Return)(input),
-        0xb2 => map(be_u16, |index| Instruction::Getstatic(index))(input),
-        0x12 => map(be_u8, |index| Instruction::Ldc(index))(input),
-        0xb6 => map(be_u16, |index| Instruction::Invokevirtual(index))(input),
-        0xb7 => map(be_u16, |index| Instruction::Invokespecial(index))(input),
+        0x10 => map(be_i8, Instruction::Bipush)(input),
+        0x11 => map(be_i16, Instruction::Sipush)(input),
+        0xb2 => map(be_u16, Instruction::Getstatic)(input),
+        0xb3 => map(be_u16, Instruction::Putstatic)(input),
+        0xb4 => map(be_u16, Instruction::Getfield)(input),
+        0xb5 => map(be_u16, Instruction::Putfield)(input),
+        0xbb => map(be_u16, Instruction::New)(input),
+        0xbd => map(be_u16, Instruction::Anewarray)(input),
+        0xbe => zero_operands(Instruction::Arraylength)(input),
+        0xbc => {
+            let (rest, code) = be_u8(input)?;
+            match ArrayType::try_from(code) {
+                Ok(atype) => Ok((rest, Instruction::Newarray(atype))),
+                Err(code) => Err(nom::Err::Failure(ParsingError::new(
+                    input,
+                    ParsingErrorKind::InvalidArrayType(code),
+                ))),
+            }
+        }
+        0x2e => zero_operands(Instruction::Iaload)(input),
+        0x2f => zero_operands(Instruction::Laload)(input),
+        0x30 => zero_operands(Instruction::Faload)(input),
+        0x31 => zero_operands(Instruction::Daload)(input),
+        0x33 => zero_operands(Instruction::Baload)(input),
+        0x34 => zero_operands(Instruction::Caload)(input),
+        0x35 => zero_operands(Instruction::Saload)(input),
+        0x4f => zero_operands(Instruction::Iastore)(input),
+        0x50 => zero_operands(Instruction::Lastore)(input),
+        0x51 => zero_operands(Instruction::Fastore)(input),
+        0x52 => zero_operands(Instruction::Dastore)(input),
+        0x54 => zero_operands(Instruction::Bastore)(input),
+        0x55 => zero_operands(Instruction::Castore)(input),
+        0x56 => zero_operands(Instruction::Sastore)(input),
+        0x12 => map(be_u8, Instruction::Ldc)(input),
+        0x14 => map(be_u16, Instruction::Ldc2w)(input),
+        0xb6 => map(be_u16, Instruction::Invokevirtual)(input),
+        0xb7 => map(be_u16, Instruction::Invokespecial)(input),
+        0xb8 => map(be_u16, Instruction::Invokestatic)(input),
+        // The count operand is followed by a byte that must be zero.
+        0xb9 => map(tuple((be_u16, be_u8, tag([0]))), |(index, count, _)| {
+            Instruction::Invokeinterface(index, count)
+        })(input),
+        0x0 => zero_operands(Instruction::Nop)(input),
+        0x13 => map(be_u16, Instruction::Ldcw)(input),
+        0x60 => zero_operands(Instruction::Iadd)(input),
+        0x64 => zero_operands(Instruction::Isub)(input),
+        0x68 => zero_operands(Instruction::Imul)(input),
+        0x6c => zero_operands(Instruction::Idiv)(input),
+        0x70 => zero_operands(Instruction::Irem)(input),
+        0x74 => zero_operands(Instruction::Ineg)(input),
+        0x78 => zero_operands(Instruction::Ishl)(input),
+        0x7a => zero_operands(Instruction::Ishr)(input),
+        0x7c => zero_operands(Instruction::Iushr)(input),
+        0x7e => zero_operands(Instruction::Iand)(input),
+        0x80 => zero_operands(Instruction::Ior)(input),
+        0x82 => zero_operands(Instruction::Ixor)(input),
+        0xa9 => map(be_u8, |index| Instruction::Ret(index.into()))(input),
+        0xad => zero_operands(Instruction::Lreturn)(input),
+        0xae => zero_operands(Instruction::Freturn)(input),
+        0xaf => zero_operands(Instruction::Dreturn)(input),
+        0xb0 => zero_operands(Instruction::Areturn)(input),
+        0xc0 => map(be_u16, Instruction::Checkcast)(input),
+        0xc1 => map(be_u16, Instruction::Instanceof)(input),
+        0xc2 => zero_operands(Instruction::Monitorenter)(input),
+        0xc3 => zero_operands(Instruction::Monitorexit)(input),
+        0xc5 => {
+            let (rest, (index, dimensions)) = tuple((be_u16, be_u8))(input)?;
+            if dimensions == 0 {
+                return Err(nom::Err::Failure(ParsingError::new(
+                    input,
+                    ParsingErrorKind::ZeroDimensions,
+                )));
+            }
+            Ok((rest, Instruction::Multianewarray(index, dimensions)))
+        }
+        // The index is followed by two bytes that must be zero.
+        0xba => map(tuple((be_u16, tag([0, 0]))), |(index, _)| {
+            Instruction::Invokedynamic(index)
+        })(input),
         _ => success(Instruction::Error(opcode))(input),
     }
 }
 
-fn zero_operands(instruction: Instruction) -> impl Fn(&[u8]) -> IResult<&[u8], Instruction> {
-    move |input| success(instruction)(input)
+/// The comparisons in the order the `if<cond>`, `if_icmp<cond>`, and
+/// `if_acmp<cond>` opcodes list them.
+fn comparison_kind(n: u8) -> ComparisonKind {
+    match n {
+        0 => ComparisonKind::Eq,
+        1 => ComparisonKind::Ne,
+        2 => ComparisonKind::Lt,
+        3 => ComparisonKind::Ge,
+        4 => ComparisonKind::Gt,
+        _ => ComparisonKind::Le,
+    }
+}
+
+fn tableswitch(input: &[u8]) -> IResult<&[u8], Instruction, ParsingError> {
+    let (rest, (default, low, high)) = tuple((be_i32, be_i32, be_i32))(input)?;
+    if low > high {
+        return Err(nom::Err::Failure(ParsingError::new(
+            input,
+            ParsingErrorKind::InvalidSwitchRange { low, high },
+        )));
+    }
+    // A table too long to count in a `usize` can't fit in the code either,
+    // so the count fails on the input running out.
+    let len = usize::try_from(high as i64 - low as i64 + 1).unwrap_or(usize::MAX);
+    map(count(be_i32, len), move |offsets| {
+        Instruction::Tableswitch {
+            default,
+            low,
+            high,
+            offsets,
+        }
+    })(rest)
+}
+
+fn lookupswitch(input: &[u8]) -> IResult<&[u8], Instruction, ParsingError> {
+    let (input, (default, npairs)) = tuple((be_i32, be_u32))(input)?;
+    map(
+        count(tuple((be_i32, be_i32)), npairs as usize),
+        move |pairs| Instruction::Lookupswitch { default, pairs },
+    )(input)
+}
+
+fn wide(input: &[u8]) -> IResult<&[u8], Instruction, ParsingError> {
+    let (input, opcode) = be_u8(input)?;
+    match opcode {
+        0x84 => map(tuple((be_u16, be_i16)), |(index, constant)| {
+            Instruction::Iinc {
+                index,
+                constant,
+                wide: true,
+            }
+        })(input),
+        // Only the loads, the stores, and `ret` can take a wide index. One
+        // that fits in a byte didn't need the prefix, so it's kept as `Wide`
+        // to be encoded the same way again.
+        0x15..=0x19 | 0x36..=0x3a | 0xa9 => map(be_u16, |index| {
+            let instruction = Instruction::Wide(opcode, index);
+            if index > u8::MAX.into() {
+                instruction.canonical()
+            } else {
+                instruction
+            }
+        })(input),
+        _ => fail(input),
+    }
+}
+
+/// Decodes a load or store taking the index of its local as an operand.
+/// `instruction` makes the variant for it, unless the local is one of the
+/// first four, which is kept apart from the one-byte form as a `Local`.
+fn local(
+    opcode: u8,
+    instruction: fn(u16) -> Instruction,
+) -> impl Fn(&[u8]) -> IResult<&[u8], Instruction, ParsingError> {
+    move |input| {
+        map(be_u8, |index| {
+            if index <= 3 {
+                Instruction::Local(opcode, index)
+            } else {
+                instruction(index.into())
+            }
+        })(input)
+    }
+}
+
+fn zero_operands(
+    instruction: Instruction,
+) -> impl Fn(&[u8]) -> IResult<&[u8], Instruction, ParsingError> {
+    move |input| success(instruction.clone())(input)
+}
+
+impl Instruction {
+    /// Appends the instruction's bytecode to `out`. Loads and stores of the
+    /// first four locals use their one-byte forms, the way javac emits them,
+    /// unless they were decoded from the form taking an index as a `Local`,
+    /// and those of locals past the first 256 are prefixed by `wide`.
+    /// Re-encoding decoded code gives back the same bytes.
+    ///
+    /// `out` must hold the method's code from its first byte, as the switch
+    /// instructions pad their operands to a multiple of four bytes from
+    /// there.
+    pub fn encode(&self, out: &mut Vec<u8>) {
+        use Instruction::*;
+
+        // Loads and stores have a one-byte form for locals 0 to 3, at
+        // `short` plus the index, and a form taking the index otherwise,
+        // which needs the `wide` prefix past the first 256 locals.
+        let local = |out: &mut Vec<u8>, short: u8, long: u8, index: u16| match u8::try_from(index) {
+            Ok(index) if index <= 3 => out.push(short + index),
+            Ok(index) => out.extend([long, index]),
+            Err(_) => {
+                out.extend([0xc4, long]);
+                out.extend(index.to_be_bytes());
+            }
+        };
+        let with_index = |out: &mut Vec<u8>, opcode: u8, index: u16| {
+            out.push(opcode);
+            out.extend(index.to_be_bytes());
+        };
+        let branch = |out: &mut Vec<u8>, opcode: u8, offset: i16| {
+            out.push(opcode);
+            out.extend(offset.to_be_bytes());
+        };
+        let switch = |out: &mut Vec<u8>, opcode: u8, operands: &mut dyn Iterator<Item = i32>| {
+            out.push(opcode);
+            while !out.len().is_multiple_of(4) {
+                out.push(0);
+            }
+            for operand in operands {
+                out.extend(operand.to_be_bytes());
+            }
+        };
+        let comparison = |kind: ComparisonKind| match kind {
+            ComparisonKind::Eq => 0,
+            ComparisonKind::Ne => 1,
+            ComparisonKind::Lt => 2,
+            ComparisonKind::Ge => 3,
+            ComparisonKind::Gt => 4,
+            ComparisonKind::Le => 5,
+        };
+
+        match *self {
+            Error(opcode) => out.push(opcode),
+            Aaload => out.push(0x32),
+            Aastore => out.push(0x53),
+            AconstNull => out.push(0x1),
+            Aload(n) => local(out, 0x2a, 0x19, n),
+            Anewarray(i) => with_index(out, 0xbd, i),
+            Areturn => out.push(0xb0),
+            Arraylength => out.push(0xbe),
+            Astore(n) => local(out, 0x4b, 0x3a, n),
+            Athrow => out.push(0xbf),
+            Baload => out.push(0x33),
+            Bastore => out.push(0x54),
+            Bipush(value) => out.extend([0x10, value as u8]),
+            Caload => out.push(0x34),
+            Castore => out.push(0x55),
+            Checkcast(i) => with_index(out, 0xc0, i),
+            D2f => out.push(0x90),
+            D2i => out.push(0x8e),
+            D2l => out.push(0x8f),
+            Dadd => out.push(0x63),
+            Daload => out.push(0x31),
+            Dastore => out.push(0x52),
+            Dcmpg => out.push(0x98),
+            Dcmpl => out.push(0x97),
+            Dconst0 => out.push(0xe),
+            Dconst1 => out.push(0xf),
+            Ddiv => out.push(0x6f),
+            Dload(n) => local(out, 0x26, 0x18, n),
+            Dmul => out.push(0x6b),
+            Dneg => out.push(0x77),
+            Drem => out.push(0x73),
+            Dreturn => out.push(0xaf),
+            Dstore(n) => local(out, 0x47, 0x39, n),
+            Dsub => out.push(0x67),
+            Dup => out.push(0x59),
+            DupX1 => out.push(0x5a),
+            DupX2 => out.push(0x5b),
+            Dup2 => out.push(0x5c),
+            Dup2X1 => out.push(0x5d),
+            Dup2X2 => out.push(0x5e),
+            F2d => out.push(0x8d),
+            F2i => out.push(0x8b),
+            F2l => out.push(0x8c),
+            Fadd => out.push(0x62),
+            Faload => out.push(0x30),
+            Fastore => out.push(0x51),
+            Fcmpg => out.push(0x96),
+            Fcmpl => out.push(0x95),
+            Fconst0 => out.push(0xb),
+            Fconst1 => out.push(0xc),
+            Fconst2 => out.push(0xd),
+            Fdiv => out.push(0x6e),
+            Fload(n) => local(out, 0x22, 0x17, n),
+            Fmul => out.push(0x6a),
+            Fneg => out.push(0x76),
+            Frem => out.push(0x72),
+            Freturn => out.push(0xae),
+            Fstore(n) => local(out, 0x43, 0x38, n),
+            Fsub => out.push(0x66),
+            Getfield(i) => with_index(out, 0xb4, i),
+            Getstatic(i) => with_index(out, 0xb2, i),
+            Goto(offset) => branch(out, 0xa7, offset),
+            Gotow(offset) => {
+                out.push(0xc8);
+                out.extend(offset.to_be_bytes());
+            }
+            I2b => out.push(0x91),
+            I2c => out.push(0x92),
+            I2d => out.push(0x87),
+            I2f => out.push(0x86),
+            I2l => out.push(0x85),
+            I2s => out.push(0x93),
+            Iadd => out.push(0x60),
+            Iaload => out.push(0x2e),
+            Iand => out.push(0x7e),
+            Iastore => out.push(0x4f),
+            IconstM1 => out.push(0x2),
+            Iconst0 => out.push(0x3),
+            Iconst1 => out.push(0x4),
+            Iconst2 => out.push(0x5),
+            Iconst3 => out.push(0x6),
+            Iconst4 => out.push(0x7),
+            Iconst5 => out.push(0x8),
+            Idiv => out.push(0x6c),
+            // References can only be compared for equality.
+            Ifacmp(kind, offset) => branch(out, 0xa5 + comparison(kind).min(1), offset),
+            Ificmp(kind, offset) => branch(out, 0x9f + comparison(kind), offset),
+            If(kind, offset) => branch(out, 0x99 + comparison(kind), offset),
+            Ifnonnull(offset) => branch(out, 0xc7, offset),
+            Ifnull(offset) => branch(out, 0xc6, offset),
+            Iinc {
+                index,
+                constant,
+                wide,
+            } => match (u8::try_from(index), i8::try_from(constant)) {
+                (Ok(index), Ok(constant)) if !wide => out.extend([0x84, index, constant as u8]),
+                _ => {
+                    out.extend([0xc4, 0x84]);
+                    out.extend(index.to_be_bytes());
+                    out.extend(constant.to_be_bytes());
+                }
+            },
+            Iload(n) => local(out, 0x1a, 0x15, n),
+            Imul => out.push(0x68),
+            Ineg => out.push(0x74),
+            Instanceof(i) => with_index(out, 0xc1, i),
+            Invokedynamic(i) => {
+                with_index(out, 0xba, i);
+                out.extend([0, 0]);
+            }
+            Invokeinterface(i, count) => {
+                with_index(out, 0xb9, i);
+                out.extend([count, 0]);
+            }
+            Invokespecial(i) => with_index(out, 0xb7, i),
+            Invokestatic(i) => with_index(out, 0xb8, i),
+            Invokevirtual(i) => with_index(out, 0xb6, i),
+            Ior => out.push(0x80),
+            Irem => out.push(0x70),
+            Ireturn => out.push(0xac),
+            Ishl => out.push(0x78),
+            Ishr => out.push(0x7a),
+            Istore(n) => local(out, 0x3b, 0x36, n),
+            Isub => out.push(0x64),
+            Iushr => out.push(0x7c),
+            Ixor => out.push(0x82),
+            Jsr(offset) => branch(out, 0xa8, offset),
+            Jsrw(offset) => {
+                out.push(0xc9);
+                out.extend(offset.to_be_bytes());
+            }
+            L2d => out.push(0x8a),
+            L2f => out.push(0x89),
+            L2i => out.push(0x88),
+            Ladd => out.push(0x61),
+            Laload => out.push(0x2f),
+            Land => out.push(0x7f),
+            Lastore => out.push(0x50),
+            Lcmp => out.push(0x94),
+            Lconst0 => out.push(0x9),
+            Lconst1 => out.push(0xa),
+            Ldc(i) => out.extend([0x12, i]),
+            Ldcw(i) => with_index(out, 0x13, i),
+            Ldc2w(i) => with_index(out, 0x14, i),
+            Ldiv => out.push(0x6d),
+            Lload(n) => local(out, 0x1e, 0x16, n),
+            Lmul => out.push(0x69),
+            Lneg => out.push(0x75),
+            Lookupswitch { default, ref pairs } => switch(
+                out,
+                0xab,
+                &mut [default, pairs.len() as i32]
+                    .into_iter()
+                    .chain(pairs.iter().flat_map(|&(key, offset)| [key, offset])),
+            ),
+            Lor => out.push(0x81),
+            Lrem => out.push(0x71),
+            Lreturn => out.push(0xad),
+            Lshl => out.push(0x79),
+            Lshr => out.push(0x7b),
+            Lstore(n) => local(out, 0x3f, 0x37, n),
+            Lsub => out.push(0x65),
+            Lushr => out.push(0x7d),
+            Lxor => out.push(0x83),
+            Monitorenter => out.push(0xc2),
+            Monitorexit => out.push(0xc3),
+            Multianewarray(i, dimensions) => {
+                with_index(out, 0xc5, i);
+                out.push(dimensions);
+            }
+            New(i) => with_index(out, 0xbb, i),
+            Newarray(atype) => out.extend([0xbc, atype as u8]),
+            Nop => out.push(0x0),
+            Pop => out.push(0x57),
+            Pop2 => out.push(0x58),
+            Putfield(i) => with_index(out, 0xb5, i),
+            Putstatic(i) => with_index(out, 0xb3, i),
+            Ret(n) => match u8::try_from(n) {
+                Ok(n) => out.extend([0xa9, n]),
+                Err(_) => {
+                    out.extend([0xc4, 0xa9]);
+                    out.extend(n.to_be_bytes());
+                }
+            },
+            Return => out.push(0xb1),
+            Saload => out.push(0x35),
+            Sastore => out.push(0x56),
+            Sipush(value) => {
+                out.push(0x11);
+                out.extend(value.to_be_bytes());
+            }
+            Swap => out.push(0x5f),
+            Tableswitch {
+                default,
+                low,
+                high,
+                ref offsets,
+            } => switch(
+                out,
+                0xaa,
+                &mut [default, low, high]
+                    .into_iter()
+                    .chain(offsets.iter().copied()),
+            ),
+            Wide(opcode, i) => {
+                out.extend([0xc4, opcode]);
+                out.extend(i.to_be_bytes());
+            }
+            Local(opcode, n) => out.extend([opcode, n]),
+        }
+    }
+}
+
+impl Instruction {
+    /// The opcode the instruction is encoded with. Wide loads, stores, and
+    /// `iinc`s report the opcode of `wide`.
+    pub fn opcode(&self) -> u8 {
+        use Instruction::*;
+
+        // The one-byte forms of loads and stores are at `short` plus the
+        // index, the same as in `encode`.
+        let local = |short: u8, long: u8, index: u16| match u8::try_from(index) {
+            Ok(index) if index <= 3 => short + index,
+            Ok(_) => long,
+            Err(_) => 0xc4,
+        };
+
+        match *self {
+            Error(opcode) => opcode,
+            Aaload => 0x32,
+            Aastore => 0x53,
+            AconstNull => 0x1,
+            Aload(n) => local(0x2a, 0x19, n),
+            Anewarray(..) => 0xbd,
+            Areturn => 0xb0,
+            Arraylength => 0xbe,
+            Astore(n) => local(0x4b, 0x3a, n),
+            Athrow => 0xbf,
+            Baload => 0x33,
+            Bastore => 0x54,
+            Bipush(..) => 0x10,
+            Caload => 0x34,
+            Castore => 0x55,
+            Checkcast(..) => 0xc0,
+            D2f => 0x90,
+            D2i => 0x8e,
+            D2l => 0x8f,
+            Dadd => 0x63,
+            Daload => 0x31,
+            Dastore => 0x52,
+            Dcmpg => 0x98,
+            Dcmpl => 0x97,
+            Dconst0 => 0xe,
+            Dconst1 => 0xf,
+            Ddiv => 0x6f,
+            Dload(n) => local(0x26, 0x18, n),
+            Dmul => 0x6b,
+            Dneg => 0x77,
+            Drem => 0x73,
+            Dreturn => 0xaf,
+            Dstore(n) => local(0x47, 0x39, n),
+            Dsub => 0x67,
+            Dup => 0x59,
+            DupX1 => 0x5a,
+            DupX2 => 0x5b,
+            Dup2 => 0x5c,
+            Dup2X1 => 0x5d,
+            Dup2X2 => 0x5e,
+            F2d => 0x8d,
+            F2i => 0x8b,
+            F2l => 0x8c,
+            Fadd => 0x62,
+            Faload => 0x30,
+            Fastore => 0x51,
+            Fcmpg => 0x96,
+            Fcmpl => 0x95,
+            Fconst0 => 0xb,
+            Fconst1 => 0xc,
+            Fconst2 => 0xd,
+            Fdiv => 0x6e,
+            Fload(n) => local(0x22, 0x17, n),
+            Fmul => 0x6a,
+            Fneg => 0x76,
+            Frem => 0x72,
+            Freturn => 0xae,
+            Fstore(n) => local(0x43, 0x38, n),
+            Fsub => 0x66,
+            Getfield(..) => 0xb4,
+            Getstatic(..) => 0xb2,
+            Goto(..) => 0xa7,
+            Gotow(..) => 0xc8,
+            I2b => 0x91,
+            I2c => 0x92,
+            I2d => 0x87,
+            I2f => 0x86,
+            I2l => 0x85,
+            I2s => 0x93,
+            Iadd => 0x60,
+            Iaload => 0x2e,
+            Iand => 0x7e,
+            Iastore => 0x4f,
+            IconstM1 => 0x2,
+            Iconst0 => 0x3,
+            Iconst1 => 0x4,
+            Iconst2 => 0x5,
+            Iconst3 => 0x6,
+            Iconst4 => 0x7,
+            Iconst5 => 0x8,
+            Idiv => 0x6c,
+            // References can only be compared for equality.
+            Ifacmp(kind, _) => 0xa5 + (kind as u8).min(1),
+            Ificmp(kind, _) => 0x9f + kind as u8,
+            If(kind, _) => 0x99 + kind as u8,
+            Ifnonnull(..) => 0xc7,
+            Ifnull(..) => 0xc6,
+            Iinc {
+                index,
+                constant,
+                wide,
+            } => {
+                if wide || index > u8::MAX.into() || i8::try_from(constant).is_err() {
+                    0xc4
+                } else {
+                    0x84
+                }
+            }
+            Iload(n) => local(0x1a, 0x15, n),
+            Imul => 0x68,
+            Ineg => 0x74,
+            Instanceof(..) => 0xc1,
+            Invokedynamic(..) => 0xba,
+            Invokeinterface(..) => 0xb9,
+            Invokespecial(..) => 0xb7,
+            Invokestatic(..) => 0xb8,
+            Invokevirtual(..) => 0xb6,
+            Ior => 0x80,
+            Irem => 0x70,
+            Ireturn => 0xac,
+            Ishl => 0x78,
+            Ishr => 0x7a,
+            Istore(n) => local(0x3b, 0x36, n),
+            Isub => 0x64,
+            Iushr => 0x7c,
+            Ixor => 0x82,
+            Jsr(..) => 0xa8,
+            Jsrw(..) => 0xc9,
+            L2d => 0x8a,
+            L2f => 0x89,
+            L2i => 0x88,
+            Ladd => 0x61,
+            Laload => 0x2f,
+            Land => 0x7f,
+            Lastore => 0x50,
+            Lcmp => 0x94,
+            Lconst0 => 0x9,
+            Lconst1 => 0xa,
+            Ldc(..) => 0x12,
+            Ldcw(..) => 0x13,
+            Ldc2w(..) => 0x14,
+            Ldiv => 0x6d,
+            Lload(n) => local(0x1e, 0x16, n),
+            Lmul => 0x69,
+            Lneg => 0x75,
+            Lookupswitch { .. } => 0xab,
+            Lor => 0x81,
+            Lrem => 0x71,
+            Lreturn => 0xad,
+            Lshl => 0x79,
+            Lshr => 0x7b,
+            Lstore(n) => local(0x3f, 0x37, n),
+            Lsub => 0x65,
+            Lushr => 0x7d,
+            Lxor => 0x83,
+            Monitorenter => 0xc2,
+            Monitorexit => 0xc3,
+            Multianewarray(..) => 0xc5,
+            New(..) => 0xbb,
+            Newarray(..) => 0xbc,
+            Nop => 0x0,
+            Pop => 0x57,
+            Pop2 => 0x58,
+            Putfield(..) => 0xb5,
+            Putstatic(..) => 0xb3,
+            Ret(n) => {
+                if n > u8::MAX.into() {
+                    0xc4
+                } else {
+                    0xa9
+                }
+            }
+            Return => 0xb1,
+            Saload => 0x35,
+            Sastore => 0x56,
+            Sipush(..) => 0x11,
+            Swap => 0x5f,
+            Tableswitch { .. } => 0xaa,
+            Wide(..) => 0xc4,
+            Local(opcode, _) => opcode,
+        }
+    }
+
+    /// The instruction the way the interpreter and verifier handle it, with
+    /// a [`Local`](Instruction::Local) or [`Wide`](Instruction::Wide) turned
+    /// into the variant for its instruction, like `Aload(2)`.
+    pub fn canonical(&self) -> Instruction {
+        use Instruction::*;
+
+        let typed = |opcode: u8, n: u16| match opcode {
+            0x15 => Iload(n),
+            0x16 => Lload(n),
+            0x17 => Fload(n),
+            0x18 => Dload(n),
+            0x19 => Aload(n),
+            0x36 => Istore(n),
+            0x37 => Lstore(n),
+            0x38 => Fstore(n),
+            0x39 => Dstore(n),
+            0x3a => Astore(n),
+            0xa9 => Ret(n),
+            _ => Error(opcode),
+        };
+        match *self {
+            Local(opcode, n) => typed(opcode, n.into()),
+            Wide(opcode, n) => typed(opcode, n),
+            _ => self.clone(),
+        }
+    }
+
+    /// The instruction's name as javap prints it. Loads and stores of the
+    /// first four locals get their one-byte names, like `aload_0`, matching
+    /// what [`encode`](Instruction::encode) emits.
+    pub fn mnemonic(&self) -> &'static str {
+        use Instruction::*;
+
+        let kind = |kind: ComparisonKind, names: [&'static str; 6]| names[kind as usize];
+        // Past the first 256 locals the index is prefixed by `wide`, which
+        // is named like `iload_w`.
+        let local = |n: u16, names: [&'static str; 6]| match n {
+            0..=3 => names[n as usize],
+            4..=255 => names[4],
+            _ => names[5],
+        };
+
+        match *self {
+            Error(0xca) => "breakpoint",
+            Error(0xfe) => "impdep1",
+            Error(0xff) => "impdep2",
+            Error(_) => "unknown",
+            Aaload => "aaload",
+            Aastore => "aastore",
+            AconstNull => "aconst_null",
+            Aload(n) => local(
+                n,
+                [
+                    "aload_0", "aload_1", "aload_2", "aload_3", "aload", "aload_w",
+                ],
+            ),
+            Anewarray(_) => "anewarray",
+            Areturn => "areturn",
+            Arraylength => "arraylength",
+            Astore(n) => local(
+                n,
+                [
+                    "astore_0", "astore_1", "astore_2", "astore_3", "astore", "astore_w",
+                ],
+            ),
+            Athrow => "athrow",
+            Baload => "baload",
+            Bastore => "bastore",
+            Bipush(_) => "bipush",
+            Caload => "caload",
+            Castore => "castore",
+            Checkcast(_) => "checkcast",
+            D2f => "d2f",
+            D2i => "d2i",
+            D2l => "d2l",
+            Dadd => "dadd",
+            Daload => "daload",
+            Dastore => "dastore",
+            Dcmpg => "dcmpg",
+            Dcmpl => "dcmpl",
+            Dconst0 => "dconst_0",
+            Dconst1 => "dconst_1",
+            Ddiv => "ddiv",
+            Dload(n) => local(
+                n,
+                [
+                    "dload_0", "dload_1", "dload_2", "dload_3", "dload", "dload_w",
+                ],
+            ),
+            Dmul => "dmul",
+            Dneg => "dneg",
+            Drem => "drem",
+            Dreturn => "dreturn",
+            Dstore(n) => local(
+                n,
+                [
+                    "dstore_0", "dstore_1", "dstore_2", "dstore_3", "dstore", "dstore_w",
+                ],
+            ),
+            Dsub => "dsub",
+            Dup => "dup",
+            DupX1 => "dup_x1",
+            DupX2 => "dup_x2",
+            Dup2 => "dup2",
+            Dup2X1 => "dup2_x1",
+            Dup2X2 => "dup2_x2",
+            F2d => "f2d",
+            F2i => "f2i",
+            F2l => "f2l",
+            Fadd => "fadd",
+            Faload => "faload",
+            Fastore => "fastore",
+            Fcmpg => "fcmpg",
+            Fcmpl => "fcmpl",
+            Fconst0 => "fconst_0",
+            Fconst1 => "fconst_1",
+            Fconst2 => "fconst_2",
+            Fdiv => "fdiv",
+            Fload(n) => local(
+                n,
+                [
+                    "fload_0", "fload_1", "fload_2", "fload_3", "fload", "fload_w",
+                ],
+            ),
+            Fmul => "fmul",
+            Fneg => "fneg",
+            Frem => "frem",
+            Freturn => "freturn",
+            Fstore(n) => local(
+                n,
+                [
+                    "fstore_0", "fstore_1", "fstore_2", "fstore_3", "fstore", "fstore_w",
+                ],
+            ),
+            Fsub => "fsub",
+            Getfield(_) => "getfield",
+            Getstatic(_) => "getstatic",
+            Goto(_) => "goto",
+            Gotow(_) => "goto_w",
+            I2b => "i2b",
+            I2c => "i2c",
+            I2d => "i2d",
+            I2f => "i2f",
+            I2l => "i2l",
+            I2s => "i2s",
+            Iadd => "iadd",
+            Iaload => "iaload",
+            Iand => "iand",
+            Iastore => "iastore",
+            IconstM1 => "iconst_m1",
+            Iconst0 => "iconst_0",
+            Iconst1 => "iconst_1",
+            Iconst2 => "iconst_2",
+            Iconst3 => "iconst_3",
+            Iconst4 => "iconst_4",
+            Iconst5 => "iconst_5",
+            Idiv => "idiv",
+            // References can only be compared for equality.
+            Ifacmp(ComparisonKind::Eq, _) => "if_acmpeq",
+            Ifacmp(..) => "if_acmpne",
+            Ificmp(k, _) => kind(
+                k,
+                [
+                    "if_icmpeq",
+                    "if_icmpne",
+                    "if_icmplt",
+                    "if_icmpge",
+                    "if_icmpgt",
+                    "if_icmple",
+                ],
+            ),
+            If(k, _) => kind(k, ["ifeq", "ifne", "iflt", "ifge", "ifgt", "ifle"]),
+            Ifnonnull(_) => "ifnonnull",
+            Ifnull(_) => "ifnull",
+            Iinc {
+                index,
+                constant,
+                wide,
+            } => match (u8::try_from(index), i8::try_from(constant)) {
+                (Ok(_), Ok(_)) if !wide => "iinc",
+                _ => "iinc_w",
+            },
+            Iload(n) => local(
+                n,
+                [
+                    "iload_0", "iload_1", "iload_2", "iload_3", "iload", "iload_w",
+                ],
+            ),
+            Imul => "imul",
+            Ineg => "ineg",
+            Instanceof(_) => "instanceof",
+            Invokedynamic(_) => "invokedynamic",
+            Invokeinterface(..) => "invokeinterface",
+            Invokespecial(_) => "invokespecial",
+            Invokestatic(_) => "invokestatic",
+            Invokevirtual(_) => "invokevirtual",
+            Ior => "ior",
+            Irem => "irem",
+            Ireturn => "ireturn",
+            Ishl => "ishl",
+            Ishr => "ishr",
+            Istore(n) => local(
+                n,
+                [
+                    "istore_0", "istore_1", "istore_2", "istore_3", "istore", "istore_w",
+                ],
+            ),
+            Isub => "isub",
+            Iushr => "iushr",
+            Ixor => "ixor",
+            Jsr(_) => "jsr",
+            Jsrw(_) => "jsr_w",
+            L2d => "l2d",
+            L2f => "l2f",
+            L2i => "l2i",
+            Ladd => "ladd",
+            Laload => "laload",
+            Land => "land",
+            Lastore => "lastore",
+            Lcmp => "lcmp",
+            Lconst0 => "lconst_0",
+            Lconst1 => "lconst_1",
+            Ldc(_) => "ldc",
+            Ldcw(_) => "ldc_w",
+            Ldc2w(_) => "ldc2_w",
+            Ldiv => "ldiv",
+            Lload(n) => local(
+                n,
+                [
+                    "lload_0", "lload_1", "lload_2", "lload_3", "lload", "lload_w",
+                ],
+            ),
+            Lmul => "lmul",
+            Lneg => "lneg",
+            Lookupswitch { .. } => "lookupswitch",
+            Lor => "lor",
+            Lrem => "lrem",
+            Lreturn => "lreturn",
+            Lshl => "lshl",
+            Lshr => "lshr",
+            Lstore(n) => local(
+                n,
+                [
+                    "lstore_0", "lstore_1", "lstore_2", "lstore_3", "lstore", "lstore_w",
+                ],
+            ),
+            Lsub => "lsub",
+            Lushr => "lushr",
+            Lxor => "lxor",
+            Monitorenter => "monitorenter",
+            Monitorexit => "monitorexit",
+            Multianewarray(..) => "multianewarray",
+            New(_) => "new",
+            Newarray(_) => "newarray",
+            Nop => "nop",
+            Pop => "pop",
+            Pop2 => "pop2",
+            Putfield(_) => "putfield",
+            Putstatic(_) => "putstatic",
+            Ret(n) if n > u8::MAX.into() => "ret_w",
+            Ret(_) => "ret",
+            Return => "return",
+            Saload => "saload",
+            Sastore => "sastore",
+            Sipush(_) => "sipush",
+            Swap => "swap",
+            Tableswitch { .. } => "tableswitch",
+            Wide(opcode, _) => match opcode {
+                0x15 => "iload_w",
+                0x16 => "lload_w",
+                0x17 => "fload_w",
+                0x18 => "dload_w",
+                0x19 => "aload_w",
+                0x36 => "istore_w",
+                0x37 => "lstore_w",
+                0x38 => "fstore_w",
+                0x39 => "dstore_w",
+                0x3a => "astore_w",
+                0xa9 => "ret_w",
+                _ => "wide",
+            },
+            Local(opcode, _) => match opcode {
+                0x15 => "iload",
+                0x16 => "lload",
+                0x17 => "fload",
+                0x18 => "dload",
+                0x19 => "aload",
+                0x36 => "istore",
+                0x37 => "lstore",
+                0x38 => "fstore",
+                0x39 => "dstore",
+                0x3a => "astore",
+                _ => "unknown",
+            },
+        }
+    }
+
+    /// Returns the instruction's branch offsets, relative to the instruction.
+    /// The switches list their default first, then the offset for each case.
+    pub fn branches(&self) -> Vec<i32> {
+        use Instruction::*;
+
+        match *self {
+            Goto(branch)
+            | Ifacmp(_, branch)
+            | Ificmp(_, branch)
+            | If(_, branch)
+            | Ifnonnull(branch)
+            | Ifnull(branch)
+            | Jsr(branch) => vec![branch as i32],
+            Gotow(branch) | Jsrw(branch) => vec![branch],
+            Lookupswitch { default, ref pairs } => std::iter::once(default)
+                .chain(pairs.iter().map(|&(_, branch)| branch))
+                .collect(),
+            Tableswitch {
+                default,
+                ref offsets,
+                ..
+            } => std::iter::once(default)
+                .chain(offsets.iter().copied())
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Returns a copy of the instruction with its branch offsets replaced by
+    /// `branches`, in the order [`branches`](Instruction::branches) lists
+    /// them. Returns `None` if an offset doesn't fit in the instruction.
+    fn with_branches(&self, branches: &[i32]) -> Option<Instruction> {
+        use Instruction::*;
+
+        let mut inst = self.clone();
+        let short = |index: usize| i16::try_from(branches[index]).ok();
+        match inst {
+            Goto(ref mut branch)
+            | Ifacmp(_, ref mut branch)
+            | Ificmp(_, ref mut branch)
+            | If(_, ref mut branch)
+            | Ifnonnull(ref mut branch)
+            | Ifnull(ref mut branch)
+            | Jsr(ref mut branch) => *branch = short(0)?,
+            Gotow(ref mut branch) | Jsrw(ref mut branch) => *branch = branches[0],
+            Lookupswitch {
+                ref mut default,
+                ref mut pairs,
+            } => {
+                *default = branches[0];
+                for (pair, &branch) in pairs.iter_mut().zip(&branches[1..]) {
+                    pair.1 = branch;
+                }
+            }
+            Tableswitch {
+                ref mut default,
+                ref mut offsets,
+                ..
+            } => {
+                *default = branches[0];
+                offsets.copy_from_slice(&branches[1..]);
+            }
+            _ => {}
+        }
+        Some(inst)
+    }
+
+    /// Writes the instruction the way javap does. If `offset` is the
+    /// instruction's offset in the code array, branch targets are printed as
+    /// offsets too; otherwise they are printed relative to the instruction.
+    pub(crate) fn write(&self, f: &mut fmt::Formatter<'_>, offset: Option<u32>) -> fmt::Result {
+        use Instruction::*;
+
+        let target = |branch: i32| match offset {
+            Some(offset) => (offset as i64 + branch as i64).to_string(),
+            None => format!("{branch:+}"),
+        };
+        let mnemonic = self.mnemonic();
+
+        let operands = match *self {
+            Aload(n) | Astore(n) | Dload(n) | Dstore(n) | Fload(n) | Fstore(n) | Iload(n)
+            | Istore(n) | Lload(n) | Lstore(n)
+                if n > 3 =>
+            {
+                n.to_string()
+            }
+            Ret(n) => n.to_string(),
+            Wide(_, index) => index.to_string(),
+            Local(_, index) => index.to_string(),
+            Bipush(value) => value.to_string(),
+            Sipush(value) => value.to_string(),
+            Ldc(index) => format!("#{index}"),
+            Anewarray(index) | Checkcast(index) | Getfield(index) | Getstatic(index)
+            | Instanceof(index) | Invokespecial(index) | Invokestatic(index)
+            | Invokevirtual(index) | Ldcw(index) | Ldc2w(index) | New(index) | Putfield(index)
+            | Putstatic(index) => format!("#{index}"),
+            Invokedynamic(index) => format!("#{index},  0"),
+            Invokeinterface(index, count) | Multianewarray(index, count) => {
+                format!("#{index},  {count}")
+            }
+            Goto(branch)
+            | Ifacmp(_, branch)
+            | Ificmp(_, branch)
+            | If(_, branch)
+            | Ifnonnull(branch)
+            | Ifnull(branch)
+            | Jsr(branch) => target(branch as i32),
+            Gotow(branch) | Jsrw(branch) => target(branch),
+            Iinc {
+                index, constant, ..
+            } => format!("{index}, {constant}"),
+            // javap puts an extra space before the type.
+            Newarray(atype) => format!(" {}", atype.name()),
+            Lookupswitch { default, ref pairs } => {
+                write!(f, "{mnemonic:<13} {{ // {}", pairs.len())?;
+                for &(key, branch) in pairs {
+                    write!(f, "\n{key:>18}: {}", target(branch))?;
+                }
+                return write!(f, "\n{:>18}: {}\n      }}", "default", target(default));
+            }
+            Tableswitch {
+                default,
+                low,
+                high,
+                ref offsets,
+            } => {
+                write!(f, "{mnemonic:<13} {{ // {low} to {high}")?;
+                for (key, &branch) in (low..).zip(offsets) {
+                    write!(f, "\n{key:>18}: {}", target(branch))?;
+                }
+                return write!(f, "\n{:>18}: {}\n      }}", "default", target(default));
+            }
+            _ => return write!(f, "{mnemonic}"),
+        };
+
+        write!(f, "{mnemonic:<13} {operands}")
+    }
+}
+
+impl fmt::Display for Instruction {
+    /// Formats the instruction like a line of `javap -c` output, without the
+    /// offset or the comment resolving its constant. Branch targets are
+    /// relative to the instruction, like `goto +12`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(f, None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every opcode, followed by operands for it, and the mnemonic it
+    /// decodes to.
+    const OPCODES: &[(&[u8], &str)] = &[
+        (&[0x00], "nop"),
+        (&[0x01], "aconst_null"),
+        (&[0x02], "iconst_m1"),
+        (&[0x03], "iconst_0"),
+        (&[0x04], "iconst_1"),
+        (&[0x05], "iconst_2"),
+        (&[0x06], "iconst_3"),
+        (&[0x07], "iconst_4"),
+        (&[0x08], "iconst_5"),
+        (&[0x09], "lconst_0"),
+        (&[0x0a], "lconst_1"),
+        (&[0x0b], "fconst_0"),
+        (&[0x0c], "fconst_1"),
+        (&[0x0d], "fconst_2"),
+        (&[0x0e], "dconst_0"),
+        (&[0x0f], "dconst_1"),
+        (&[0x10, 0x05], "bipush"),
+        (&[0x11, 0x00, 0x01], "sipush"),
+        (&[0x12, 0x05], "ldc"),
+        (&[0x13, 0x00, 0x01], "ldc_w"),
+        (&[0x14, 0x00, 0x01], "ldc2_w"),
+        (&[0x15, 0x05], "iload"),
+        (&[0x16, 0x05], "lload"),
+        (&[0x17, 0x05], "fload"),
+        (&[0x18, 0x05], "dload"),
+        (&[0x19, 0x05], "aload"),
+        (&[0x1a], "iload_0"),
+        (&[0x1b], "iload_1"),
+        (&[0x1c], "iload_2"),
+        (&[0x1d], "iload_3"),
+        (&[0x1e], "lload_0"),
+        (&[0x1f], "lload_1"),
+        (&[0x20], "lload_2"),
+        (&[0x21], "lload_3"),
+        (&[0x22], "fload_0"),
+        (&[0x23], "fload_1"),
+        (&[0x24], "fload_2"),
+        (&[0x25], "fload_3"),
+        (&[0x26], "dload_0"),
+        (&[0x27], "dload_1"),
+        (&[0x28], "dload_2"),
+        (&[0x29], "dload_3"),
+        (&[0x2a], "aload_0"),
+        (&[0x2b], "aload_1"),
+        (&[0x2c], "aload_2"),
+        (&[0x2d], "aload_3"),
+        (&[0x2e], "iaload"),
+        (&[0x2f], "laload"),
+        (&[0x30], "faload"),
+        (&[0x31], "daload"),
+        (&[0x32], "aaload"),
+        (&[0x33], "baload"),
+        (&[0x34], "caload"),
+        (&[0x35], "saload"),
+        (&[0x36, 0x05], "istore"),
+        (&[0x37, 0x05], "lstore"),
+        (&[0x38, 0x05], "fstore"),
+        (&[0x39, 0x05], "dstore"),
+        (&[0x3a, 0x05], "astore"),
+        (&[0x3b], "istore_0"),
+        (&[0x3c], "istore_1"),
+        (&[0x3d], "istore_2"),
+        (&[0x3e], "istore_3"),
+        (&[0x3f], "lstore_0"),
+        (&[0x40], "lstore_1"),
+        (&[0x41], "lstore_2"),
+        (&[0x42], "lstore_3"),
+        (&[0x43], "fstore_0"),
+        (&[0x44], "fstore_1"),
+        (&[0x45], "fstore_2"),
+        (&[0x46], "fstore_3"),
+        (&[0x47], "dstore_0"),
+        (&[0x48], "dstore_1"),
+        (&[0x49], "dstore_2"),
+        (&[0x4a], "dstore_3"),
+        (&[0x4b], "astore_0"),
+        (&[0x4c], "astore_1"),
+        (&[0x4d], "astore_2"),
+        (&[0x4e], "astore_3"),
+        (&[0x4f], "iastore"),
+        (&[0x50], "lastore"),
+        (&[0x51], "fastore"),
+        (&[0x52], "dastore"),
+        (&[0x53], "aastore"),
+        (&[0x54], "bastore"),
+        (&[0x55], "castore"),
+        (&[0x56], "sastore"),
+        (&[0x57], "pop"),
+        (&[0x58], "pop2"),
+        (&[0x59], "dup"),
+        (&[0x5a], "dup_x1"),
+        (&[0x5b], "dup_x2"),
+        (&[0x5c], "dup2"),
+        (&[0x5d], "dup2_x1"),
+        (&[0x5e], "dup2_x2"),
+        (&[0x5f], "swap"),
+        (&[0x60], "iadd"),
+        (&[0x61], "ladd"),
+        (&[0x62], "fadd"),
+        (&[0x63], "dadd"),
+        (&[0x64], "isub"),
+        (&[0x65], "lsub"),
+        (&[0x66], "fsub"),
+        (&[0x67], "dsub"),
+        (&[0x68], "imul"),
+        (&[0x69], "lmul"),
+        (&[0x6a], "fmul"),
+        (&[0x6b], "dmul"),
+        (&[0x6c], "idiv"),
+        (&[0x6d], "ldiv"),
+        (&[0x6e], "fdiv"),
+        (&[0x6f], "ddiv"),
+        (&[0x70], "irem"),
+        (&[0x71], "lrem"),
+        (&[0x72], "frem"),
+        (&[0x73], "drem"),
+        (&[0x74], "ineg"),
+        (&[0x75], "lneg"),
+        (&[0x76], "fneg"),
+        (&[0x77], "dneg"),
+        (&[0x78], "ishl"),
+        (&[0x79], "lshl"),
+        (&[0x7a], "ishr"),
+        (&[0x7b], "lshr"),
+        (&[0x7c], "iushr"),
+        (&[0x7d], "lushr"),
+        (&[0x7e], "iand"),
+        (&[0x7f], "land"),
+        (&[0x80], "ior"),
+        (&[0x81], "lor"),
+        (&[0x82], "ixor"),
+        (&[0x83], "lxor"),
+        (&[0x84, 0x01, 0x02], "iinc"),
+        (&[0x85], "i2l"),
+        (&[0x86], "i2f"),
+        (&[0x87], "i2d"),
+        (&[0x88], "l2i"),
+        (&[0x89], "l2f"),
+        (&[0x8a], "l2d"),
+        (&[0x8b], "f2i"),
+        (&[0x8c], "f2l"),
+        (&[0x8d], "f2d"),
+        (&[0x8e], "d2i"),
+        (&[0x8f], "d2l"),
+        (&[0x90], "d2f"),
+        (&[0x91], "i2b"),
+        (&[0x92], "i2c"),
+        (&[0x93], "i2s"),
+        (&[0x94], "lcmp"),
+        (&[0x95], "fcmpl"),
+        (&[0x96], "fcmpg"),
+        (&[0x97], "dcmpl"),
+        (&[0x98], "dcmpg"),
+        (&[0x99, 0x00, 0x03], "ifeq"),
+        (&[0x9a, 0x00, 0x03], "ifne"),
+        (&[0x9b, 0x00, 0x03], "iflt"),
+        (&[0x9c, 0x00, 0x03], "ifge"),
+        (&[0x9d, 0x00, 0x03], "ifgt"),
+        (&[0x9e, 0x00, 0x03], "ifle"),
+        (&[0x9f, 0x00, 0x03], "if_icmpeq"),
+        (&[0xa0, 0x00, 0x03], "if_icmpne"),
+        (&[0xa1, 0x00, 0x03], "if_icmplt"),
+        (&[0xa2, 0x00, 0x03], "if_icmpge"),
+        (&[0xa3, 0x00, 0x03], "if_icmpgt"),
+        (&[0xa4, 0x00, 0x03], "if_icmple"),
+        (&[0xa5, 0x00, 0x03], "if_acmpeq"),
+        (&[0xa6, 0x00, 0x03], "if_acmpne"),
+        (&[0xa7, 0x00, 0x03], "goto"),
+        (&[0xa8, 0x00, 0x03], "jsr"),
+        (&[0xa9, 0x05], "ret"),
+        (
+            &[
+                0xaa, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x10,
+            ],
+            "tableswitch",
+        ),
+        (
+            &[
+                0xab, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x0c, 0x00, 0x00, 0x00, 0x00,
+            ],
+            "lookupswitch",
+        ),
+        (&[0xac], "ireturn"),
+        (&[0xad], "lreturn"),
+        (&[0xae], "freturn"),
+        (&[0xaf], "dreturn"),
+        (&[0xb0], "areturn"),
+        (&[0xb1], "return"),
+        (&[0xb2, 0x00, 0x01], "getstatic"),
+        (&[0xb3, 0x00, 0x01], "putstatic"),
+        (&[0xb4, 0x00, 0x01], "getfield"),
+        (&[0xb5, 0x00, 0x01], "putfield"),
+        (&[0xb6, 0x00, 0x01], "invokevirtual"),
+        (&[0xb7, 0x00, 0x01], "invokespecial"),
+        (&[0xb8, 0x00, 0x01], "invokestatic"),
+        (&[0xb9, 0x00, 0x01, 0x01, 0x00], "invokeinterface"),
+        (&[0xba, 0x00, 0x01, 0x00, 0x00], "invokedynamic"),
+        (&[0xbb, 0x00, 0x01], "new"),
+        (&[0xbc, 0x0a], "newarray"),
+        (&[0xbd, 0x00, 0x01], "anewarray"),
+        (&[0xbe], "arraylength"),
+        (&[0xbf], "athrow"),
+        (&[0xc0, 0x00, 0x01], "checkcast"),
+        (&[0xc1, 0x00, 0x01], "instanceof"),
+        (&[0xc2], "monitorenter"),
+        (&[0xc3], "monitorexit"),
+        (&[0xc4, 0x15, 0x00, 0x05], "iload_w"),
+        (&[0xc5, 0x00, 0x01, 0x01], "multianewarray"),
+        (&[0xc6, 0x00, 0x03], "ifnull"),
+        (&[0xc7, 0x00, 0x03], "ifnonnull"),
+        (&[0xc8, 0x00, 0x00, 0x00, 0x05], "goto_w"),
+        (&[0xc9, 0x00, 0x00, 0x00, 0x05], "jsr_w"),
+        (&[0xca], "breakpoint"),
+        (&[0xcb], "unknown"),
+        (&[0xfd], "unknown"),
+        (&[0xfe], "impdep1"),
+        (&[0xff], "impdep2"),
+    ];
+
+    #[test]
+    fn offsets_follow_operand_widths() {
+        // bipush 5, istore_1, return
+        let (_, decoded) = code(&[0x10, 0x05, 0x3c, 0xb1]).unwrap();
+        let offsets: Vec<_> = decoded.iter().map(|&(offset, _)| offset).collect();
+        assert_eq!(offsets, [0, 2, 3]);
+        assert!(matches!(decoded[1].1, Instruction::Istore(1)));
+    }
+
+    #[test]
+    fn encoding_decoded_code_gives_the_same_bytes() {
+        let bytecode = [
+            0x03, // iconst_0
+            0x36, 0x01, // istore 1
+            0xc4, 0x84, 0x00, 0x01, 0x00, 0x01, // wide iinc 1, 1
+            0x15, 0x01, // iload 1
+            0x10, 0x0a, // bipush 10
+            0xa1, 0xff, 0xf6, // if_icmplt 3
+            0x84, 0x01, 0x02, // iinc 1, 2
+            0xc4, 0x15, 0x00, 0x02, // wide iload 2
+            0x57, // pop
+            0x2a, // aload_0
+            0x57, // pop
+            0xb1, // return
+        ];
+        let (_, decoded) = code(&bytecode).unwrap();
+        assert!(matches!(decoded[1].1.canonical(), Instruction::Istore(1)));
+        assert_eq!(decoded[1].1.mnemonic(), "istore");
+        assert_eq!(decoded[2].1.mnemonic(), "iinc_w");
+        assert_eq!(encode_code(&decoded).unwrap(), bytecode);
+    }
+
+    #[test]
+    fn decodes_every_opcode() {
+        for &(bytes, mnemonic) in OPCODES {
+            let (_, decoded) = code(bytes).unwrap();
+            match decoded.as_slice() {
+                [(0, inst)] => {
+                    assert_eq!(inst.mnemonic(), mnemonic);
+                    assert_eq!(inst.opcode(), bytes[0]);
+                    let mut out = Vec::new();
+                    inst.encode(&mut out);
+                    assert_eq!(out, bytes, "{mnemonic}");
+                }
+                decoded => panic!("{mnemonic} decoded to {decoded:?}"),
+            }
+        }
+        for opcode in 0xcb..=0xfd {
+            let (_, decoded) = code(&[opcode]).unwrap();
+            assert!(matches!(decoded[..], [(0, Instruction::Error(op))] if op == opcode));
+        }
+    }
+
+    #[test]
+    fn array_types_by_code() {
+        let types = [
+            (4, ArrayType::Boolean, 'Z', 1),
+            (5, ArrayType::Char, 'C', 2),
+            (6, ArrayType::Float, 'F', 4),
+            (7, ArrayType::Double, 'D', 8),
+            (8, ArrayType::Byte, 'B', 1),
+            (9, ArrayType::Short, 'S', 2),
+            (10, ArrayType::Int, 'I', 4),
+            (11, ArrayType::Long, 'J', 8),
+        ];
+        for (type_code, atype, descriptor, size) in types {
+            assert_eq!(ArrayType::try_from(type_code), Ok(atype));
+            assert_eq!(atype as u8, type_code);
+            assert_eq!(atype.descriptor(), descriptor);
+            assert_eq!(atype.element_size(), size);
+            let (_, decoded) = code(&[0xbc, type_code]).unwrap();
+            assert!(
+                matches!(decoded[..], [(0, Instruction::Newarray(decoded))] if decoded == atype)
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_array_type_is_rejected() {
+        assert_eq!(ArrayType::try_from(12), Err(12));
+        match code(&[0xbc, 12]) {
+            Err(nom::Err::Failure(err)) => {
+                assert_eq!(err.kind, ParsingErrorKind::InvalidArrayType(12))
+            }
+            other => panic!("expected a failure, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn iinc_constants_are_sign_extended() {
+        let (_, decoded) = code(&[
+            0x84, 0x01, 0x05, // iinc 1, 5
+            0x84, 0x01, 0xff, // iinc 1, -1
+            0xc4, 0x84, 0x01, 0x00, 0xff, 0x00, // wide iinc 256, -256
+        ])
+        .unwrap();
+        assert!(matches!(
+            decoded[..],
+            [
+                (
+                    0,
+                    Instruction::Iinc {
+                        index: 1,
+                        constant: 5,
+                        wide: false,
+                    }
+                ),
+                (
+                    3,
+                    Instruction::Iinc {
+                        index: 1,
+                        constant: -1,
+                        wide: false,
+                    }
+                ),
+                (
+                    6,
+                    Instruction::Iinc {
+                        index: 256,
+                        constant: -256,
+                        wide: true,
+                    }
+                ),
+            ]
+        ));
+    }
+
+    #[test]
+    fn tableswitch_ranges_are_checked() {
+        let switch = |low: i32, high: i32| {
+            let mut bytes = vec![0xaa, 0, 0, 0, 0, 0, 0, 0];
+            bytes.extend_from_slice(&low.to_be_bytes());
+            bytes.extend_from_slice(&high.to_be_bytes());
+            bytes
+        };
+        match code(&switch(1, 0)) {
+            Err(nom::Err::Failure(err)) => assert_eq!(
+                err.kind,
+                ParsingErrorKind::InvalidSwitchRange { low: 1, high: 0 }
+            ),
+            other => panic!("expected a failure, got {other:?}"),
+        }
+        assert!(code(&switch(i32::MIN, i32::MAX)).is_err());
+    }
+
+    #[test]
+    fn invoke_operands_end_in_zero_bytes() {
+        // invokeinterface #1, 1 and invokedynamic #1, each before a return.
+        for bytes in [
+            [0xb9, 0x00, 0x01, 0x01, 0x00, 0xb1],
+            [0xba, 0x00, 0x01, 0x00, 0x00, 0xb1],
+        ] {
+            let (_, decoded) = code(&bytes).unwrap();
+            let offsets: Vec<_> = decoded.iter().map(|&(offset, _)| offset).collect();
+            assert_eq!(offsets, [0, 5]);
+        }
+        assert!(code(&[0xb9, 0x00, 0x01, 0x01, 0x01]).is_err());
+        assert!(code(&[0xba, 0x00, 0x01, 0x00, 0x01]).is_err());
+        assert!(code(&[0xba, 0x00, 0x01, 0x01, 0x00]).is_err());
+    }
+
+    #[test]
+    fn multianewarray_needs_a_dimension() {
+        let (_, decoded) = code(&[0xc5, 0x00, 0x07, 0x02]).unwrap();
+        assert!(matches!(
+            decoded[..],
+            [(0, Instruction::Multianewarray(7, 2))]
+        ));
+        match code(&[0xc5, 0x00, 0x07, 0x00]) {
+            Err(nom::Err::Failure(err)) => assert_eq!(err.kind, ParsingErrorKind::ZeroDimensions),
+            other => panic!("expected a failure, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn wide_locals_decode_to_their_instruction() {
+        let bytecode = [
+            0xc4, 0x36, 0x01, 0x2c, // wide istore 300
+            0xc4, 0x15, 0x00, 0x02, // wide iload 2
+            0xc4, 0xa9, 0x01, 0x00, // wide ret 256
+        ];
+        let (_, decoded) = code(&bytecode).unwrap();
+        assert!(matches!(decoded[0].1, Instruction::Istore(300)));
+        assert!(matches!(decoded[1].1, Instruction::Wide(0x15, 2)));
+        assert!(matches!(decoded[1].1.canonical(), Instruction::Iload(2)));
+        assert!(matches!(decoded[2].1, Instruction::Ret(256)));
+        for (_, inst) in &decoded {
+            assert_eq!(inst.opcode(), 0xc4);
+        }
+        assert_eq!(decoded[0].1.mnemonic(), "istore_w");
+        assert_eq!(decoded[2].1.mnemonic(), "ret_w");
+        assert_eq!(encode_code(&decoded).unwrap(), bytecode);
+
+        let mut out = Vec::new();
+        Instruction::Aload(256).encode(&mut out);
+        Instruction::Aload(255).encode(&mut out);
+        assert_eq!(out, [0xc4, 0x19, 0x01, 0x00, 0x19, 0xff]);
+    }
 }
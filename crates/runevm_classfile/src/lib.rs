@@ -1,9 +1,36 @@
+mod annotations;
+mod builder;
+pub mod cfg;
+pub mod descriptor;
+mod disassembler;
+mod error;
 mod instructions;
+mod module;
+pub mod mutf8;
 mod parser;
+pub mod pretty;
+pub mod signature;
+pub mod verify;
+mod writer;
 
+pub use annotations::{
+    Annotation, ElementValue, LocalVariableTarget, TypeAnnotation, TypeAnnotationTarget,
+    TypePathEntry,
+};
 use bitflags::bitflags;
-pub use instructions::Instruction;
-pub use parser::{parse_class, ClassFile, Constant, ConstantPool, Field, Method, Version};
+pub use builder::ConstantPoolBuilder;
+pub use error::{
+    ClassFileError, ConstantPoolError, DescriptorError, EncodeError, ParsingError,
+    ParsingErrorKind, SignatureError, VerifyError,
+};
+pub use instructions::{encode_code, ArrayType, ComparisonKind, Instruction};
+pub use module::{ModuleDescriptor, ModuleExports, ModuleOpens, ModuleProvides, ModuleRequires};
+pub use parser::{
+    parse_class, parse_class_checked, parse_class_validated, Attribute, BootstrapMethod,
+    BootstrapMethodEntry, ClassFile, Constant, ConstantKind, ConstantPool, ConstantPoolViolation,
+    ConstantValue, ExceptionTableEntry, Field, InnerClass, InnerClassEntry, LineNumberTableEntry,
+    MemberRef, Method, MethodHandle, RecordComponent, Version,
+};
 
 bitflags! {
     pub struct ClassAccessFlags: u16 {
@@ -44,4 +71,40 @@ bitflags! {
         const STRICT = 0x0800;
         const SYNTHETIC = 0x1000;
     }
+
+    pub struct InnerClassAccessFlags: u16 {
+        const PUBLIC = 0x0001;
+        const PRIVATE = 0x0002;
+        const PROTECTED = 0x0004;
+        const STATIC = 0x0008;
+        const FINAL = 0x0010;
+        const INTERFACE = 0x0200;
+        const ABSTRACT = 0x0400;
+        const SYNTHETIC = 0x1000;
+        const ANNOTATION = 0x2000;
+        const ENUM = 0x4000;
+    }
+
+    pub struct ModuleFlags: u16 {
+        const OPEN = 0x0020;
+        const SYNTHETIC = 0x1000;
+        const MANDATED = 0x8000;
+    }
+
+    pub struct RequiresFlags: u16 {
+        const TRANSITIVE = 0x0020;
+        const STATIC_PHASE = 0x0040;
+        const SYNTHETIC = 0x1000;
+        const MANDATED = 0x8000;
+    }
+
+    pub struct ExportsFlags: u16 {
+        const SYNTHETIC = 0x1000;
+        const MANDATED = 0x8000;
+    }
+
+    pub struct OpensFlags: u16 {
+        const SYNTHETIC = 0x1000;
+        const MANDATED = 0x8000;
+    }
 }
@@ -0,0 +1,142 @@
+use crate::{error::ParsingError, ExportsFlags, ModuleFlags, OpensFlags, RequiresFlags};
+use nom::{
+    combinator::map, multi::length_count, number::complete::be_u16, sequence::tuple, IResult,
+};
+
+/// The contents of the Module attribute of a `module-info` class, which
+/// declares what the module depends on and what it makes available.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleDescriptor {
+    /// The Module constant naming this module.
+    pub name_index: u16,
+    pub flags: ModuleFlags,
+    /// The Utf8 constant holding the module's version, or zero if it has
+    /// none.
+    pub version_index: u16,
+    pub requires: Vec<ModuleRequires>,
+    pub exports: Vec<ModuleExports>,
+    pub opens: Vec<ModuleOpens>,
+    /// The Class constants of the services the module uses.
+    pub uses: Vec<u16>,
+    pub provides: Vec<ModuleProvides>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModuleRequires {
+    /// The Module constant of the dependency.
+    pub requires_index: u16,
+    pub flags: RequiresFlags,
+    /// The Utf8 constant holding the version the module was compiled
+    /// against, or zero if it wasn't recorded.
+    pub version_index: u16,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleExports {
+    /// The Package constant of the exported package.
+    pub exports_index: u16,
+    pub flags: ExportsFlags,
+    /// The Module constants of the modules the package is exported to, or
+    /// empty if it is exported to every module.
+    pub exports_to: Vec<u16>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleOpens {
+    /// The Package constant of the opened package.
+    pub opens_index: u16,
+    pub flags: OpensFlags,
+    /// The Module constants of the modules the package is opened to, or
+    /// empty if it is opened to every module.
+    pub opens_to: Vec<u16>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleProvides {
+    /// The Class constant of the service interface.
+    pub provides_index: u16,
+    /// The Class constants of the implementations the module provides.
+    pub provides_with: Vec<u16>,
+}
+
+pub(crate) fn module(input: &[u8]) -> IResult<&[u8], ModuleDescriptor, ParsingError> {
+    map(
+        tuple((
+            be_u16,
+            map(be_u16, ModuleFlags::from_bits_truncate),
+            be_u16,
+            length_count(be_u16, requires),
+            length_count(be_u16, exports),
+            length_count(be_u16, opens),
+            length_count(be_u16, be_u16),
+            length_count(be_u16, provides),
+        )),
+        |(name_index, flags, version_index, requires, exports, opens, uses, provides)| {
+            ModuleDescriptor {
+                name_index,
+                flags,
+                version_index,
+                requires,
+                exports,
+                opens,
+                uses,
+                provides,
+            }
+        },
+    )(input)
+}
+
+fn requires(input: &[u8]) -> IResult<&[u8], ModuleRequires, ParsingError> {
+    map(
+        tuple((
+            be_u16,
+            map(be_u16, RequiresFlags::from_bits_truncate),
+            be_u16,
+        )),
+        |(requires_index, flags, version_index)| ModuleRequires {
+            requires_index,
+            flags,
+            version_index,
+        },
+    )(input)
+}
+
+fn exports(input: &[u8]) -> IResult<&[u8], ModuleExports, ParsingError> {
+    map(
+        tuple((
+            be_u16,
+            map(be_u16, ExportsFlags::from_bits_truncate),
+            length_count(be_u16, be_u16),
+        )),
+        |(exports_index, flags, exports_to)| ModuleExports {
+            exports_index,
+            flags,
+            exports_to,
+        },
+    )(input)
+}
+
+fn opens(input: &[u8]) -> IResult<&[u8], ModuleOpens, ParsingError> {
+    map(
+        tuple((
+            be_u16,
+            map(be_u16, OpensFlags::from_bits_truncate),
+            length_count(be_u16, be_u16),
+        )),
+        |(opens_index, flags, opens_to)| ModuleOpens {
+            opens_index,
+            flags,
+            opens_to,
+        },
+    )(input)
+}
+
+fn provides(input: &[u8]) -> IResult<&[u8], ModuleProvides, ParsingError> {
+    map(
+        tuple((be_u16, length_count(be_u16, be_u16))),
+        |(provides_index, provides_with)| ModuleProvides {
+            provides_index,
+            provides_with,
+        },
+    )(input)
+}
@@ -0,0 +1,127 @@
+//! Decoding and encoding of the "modified UTF-8" used by `CONSTANT_Utf8`
+//! entries.
+//!
+//! It differs from standard UTF-8 in two ways: U+0000 is encoded as the two
+//! bytes `0xC0 0x80`, and supplementary characters are encoded as a UTF-16
+//! surrogate pair, each half taking up a three-byte sequence.
+
+use crate::error::{ParsingError, ParsingErrorKind};
+
+/// Decodes modified UTF-8 bytes into a string.
+///
+/// Malformed bytes and unpaired surrogates are reported as
+/// [`ParsingErrorKind::MalformedModifiedUtf8`], located at the start of the
+/// offending sequence.
+pub fn decode(bytes: &[u8]) -> Result<String, ParsingError> {
+    let malformed =
+        |at: usize| ParsingError::new(&bytes[at..], ParsingErrorKind::MalformedModifiedUtf8);
+    let mut units = Vec::with_capacity(bytes.len());
+    let mut starts = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let byte = bytes[i];
+        let (unit, len) = match byte {
+            0x01..=0x7f => (byte as u16, 1),
+            0xc0..=0xdf => {
+                let second = continuation(bytes, i + 1).ok_or_else(|| malformed(i))?;
+                (((byte & 0x1f) as u16) << 6 | second, 2)
+            }
+            0xe0..=0xef => {
+                let second = continuation(bytes, i + 1).ok_or_else(|| malformed(i))?;
+                let third = continuation(bytes, i + 2).ok_or_else(|| malformed(i))?;
+                (((byte & 0x0f) as u16) << 12 | second << 6 | third, 3)
+            }
+            _ => return Err(malformed(i)),
+        };
+        units.push(unit);
+        starts.push(i);
+        i += len;
+    }
+
+    let mut string = String::with_capacity(units.len());
+    let mut unit = 0;
+    for decoded in char::decode_utf16(units) {
+        match decoded {
+            Ok(c) => {
+                string.push(c);
+                unit += c.len_utf16();
+            }
+            Err(_) => return Err(malformed(starts[unit])),
+        }
+    }
+    Ok(string)
+}
+
+/// Returns the six payload bits of the continuation byte at `index`.
+fn continuation(bytes: &[u8], index: usize) -> Option<u16> {
+    match bytes.get(index) {
+        Some(byte) if byte & 0xc0 == 0x80 => Some((byte & 0x3f) as u16),
+        _ => None,
+    }
+}
+
+/// Encodes a string as modified UTF-8, the inverse of [`decode`].
+pub fn encode(string: &str) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(string.len());
+
+    for unit in string.encode_utf16() {
+        match unit {
+            0x0001..=0x007f => bytes.push(unit as u8),
+            0x0000 | 0x0080..=0x07ff => {
+                bytes.push(0xc0 | (unit >> 6) as u8);
+                bytes.push(0x80 | (unit & 0x3f) as u8);
+            }
+            _ => {
+                bytes.push(0xe0 | (unit >> 12) as u8);
+                bytes.push(0x80 | (unit >> 6 & 0x3f) as u8);
+                bytes.push(0x80 | (unit & 0x3f) as u8);
+            }
+        }
+    }
+
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn embedded_nul() {
+        assert_eq!(decode(&[b'a', 0xc0, 0x80, b'b']).as_deref(), Ok("a\0b"));
+        assert_eq!(encode("a\0b"), [b'a', 0xc0, 0x80, b'b']);
+    }
+
+    #[test]
+    fn supplementary_character() {
+        // U+1F600 as the surrogate pair D83D DE00.
+        let bytes = [0xed, 0xa0, 0xbd, 0xed, 0xb8, 0x80];
+        assert_eq!(decode(&bytes).as_deref(), Ok("\u{1f600}"));
+        assert_eq!(encode("\u{1f600}"), bytes);
+    }
+
+    /// Returns the offset within `bytes` that decoding them failed at.
+    fn error_offset(bytes: &[u8]) -> usize {
+        let err = decode(bytes).unwrap_err();
+        assert_eq!(err.kind, ParsingErrorKind::MalformedModifiedUtf8);
+        err.offset(bytes)
+    }
+
+    #[test]
+    fn truncated_sequence() {
+        assert_eq!(error_offset(&[b'a', 0xe2, 0x82]), 1);
+        assert_eq!(error_offset(&[0xc3]), 0);
+    }
+
+    #[test]
+    fn invalid_lead_byte() {
+        assert_eq!(error_offset(&[b'a', b'b', 0xff]), 2);
+        assert_eq!(error_offset(&[0]), 0);
+    }
+
+    #[test]
+    fn unpaired_surrogate() {
+        assert_eq!(error_offset(&[b'a', 0xed, 0xa0, 0xbd]), 1);
+    }
+}
@@ -1,14 +1,22 @@
 use crate::{
-    instructions::instruction, ClassAccessFlags, FieldAccessFields, Instruction, MethodAccessFlags,
+    annotations::{
+        annotation, element_value, type_annotation, Annotation, ElementValue, TypeAnnotation,
+    },
+    error::{ClassFileError, ConstantPoolError, ParsingError, ParsingErrorKind},
+    instructions::code,
+    module::{module, ModuleDescriptor},
+    mutf8, ClassAccessFlags, FieldAccessFields, InnerClassAccessFlags, Instruction,
+    MethodAccessFlags,
 };
 use nom::{
     bytes::complete::tag,
-    combinator::{fail, map, success},
-    multi::{count, length_count, length_data, length_value, many0},
+    combinator::{eof, fail, map},
+    multi::{length_count, length_data, length_value},
     number::complete::{be_f32, be_f64, be_i32, be_i64, be_u16, be_u32, be_u8},
     sequence::tuple,
     IResult,
 };
+use std::{fmt, io::Read};
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Version {
@@ -16,7 +24,7 @@ pub struct Version {
     pub minor: u16,
 }
 
-fn version(input: &[u8]) -> IResult<&[u8], Version> {
+fn version(input: &[u8]) -> IResult<&[u8], Version, ParsingError> {
     map(tuple((be_u16, be_u16)), |(minor, major)| Version {
         major,
         minor,
@@ -48,46 +56,192 @@ pub enum Constant {
         name_index: u16,
         descriptor_index: u16,
     },
+    /// A handle to a field or method. The kind, from 1 to 9, says how the
+    /// member is accessed, like `REF_getField` or `REF_invokeStatic`.
+    MethodHandle {
+        reference_kind: u8,
+        reference_index: u16,
+    },
+    /// A method type, stored as the index of its descriptor.
+    MethodType(u16),
+    /// A constant computed by a bootstrap method, indexing the class's
+    /// BootstrapMethods attribute.
+    Dynamic {
+        bootstrap_method_attr_index: u16,
+        nametype_index: u16,
+    },
+    /// A call site linked by a bootstrap method for `invokedynamic`.
+    InvokeDynamic {
+        bootstrap_method_attr_index: u16,
+        nametype_index: u16,
+    },
+    /// A module, stored as the index of its name. Only found in
+    /// `module-info` classes.
+    Module(u16),
+    /// A package exported or opened by a module, stored as the index of its
+    /// internal name, like `java/lang`.
+    Package(u16),
+    /// The entry following a `Long` or `Double`, which is valid but can't be
+    /// referred to.
+    Unusable,
 }
 
-fn constant(input: &[u8]) -> IResult<&[u8], Constant> {
-    let (input, tag) = be_u8(input)?;
-
-    match tag {
-        1 => map(length_data(be_u16), |bytes: &[u8]| unsafe {
-            Constant::Utf8(String::from_utf8_unchecked(bytes.to_vec()))
-        })(input),
-        3 => map(be_i32, |value| Constant::Integer(value))(input),
-        4 => map(be_f32, |value| Constant::Float(value))(input),
-        5 => map(be_i64, |value| Constant::Long(value))(input),
-        6 => map(be_f64, |value| Constant::Double(value))(input),
-        7 => map(be_u16, |name_index| Constant::Class(name_index))(input),
-        8 => map(be_u16, |string_index| Constant::String(string_index))(input),
-        9 => map(tuple((be_u16, be_u16)), |(class_index, nametype_index)| {
-            Constant::Field {
-                class_index,
-                nametype_index,
-            }
-        })(input),
-        10 => map(tuple((be_u16, be_u16)), |(class_index, nametype_index)| {
-            Constant::Method {
-                class_index,
-                nametype_index,
-            }
-        })(input),
-        11 => map(tuple((be_u16, be_u16)), |(class_index, nametype_index)| {
-            Constant::InterfaceMethod {
-                class_index,
-                nametype_index,
-            }
-        })(input),
-        12 => map(tuple((be_u16, be_u16)), |(name_index, descriptor_index)| {
-            Constant::NameAndType {
-                name_index,
-                descriptor_index,
+impl Constant {
+    pub fn kind(&self) -> ConstantKind {
+        match self {
+            Constant::Utf8(_) => ConstantKind::Utf8,
+            Constant::Integer(_) => ConstantKind::Integer,
+            Constant::Float(_) => ConstantKind::Float,
+            Constant::Long(_) => ConstantKind::Long,
+            Constant::Double(_) => ConstantKind::Double,
+            Constant::Class(_) => ConstantKind::Class,
+            Constant::String(_) => ConstantKind::String,
+            Constant::Field { .. } => ConstantKind::Field,
+            Constant::Method { .. } => ConstantKind::Method,
+            Constant::InterfaceMethod { .. } => ConstantKind::InterfaceMethod,
+            Constant::NameAndType { .. } => ConstantKind::NameAndType,
+            Constant::MethodHandle { .. } => ConstantKind::MethodHandle,
+            Constant::MethodType(_) => ConstantKind::MethodType,
+            Constant::Dynamic { .. } => ConstantKind::Dynamic,
+            Constant::InvokeDynamic { .. } => ConstantKind::InvokeDynamic,
+            Constant::Module(_) => ConstantKind::Module,
+            Constant::Package(_) => ConstantKind::Package,
+            Constant::Unusable => ConstantKind::Unusable,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConstantKind {
+    Utf8,
+    Integer,
+    Float,
+    Long,
+    Double,
+    Class,
+    String,
+    Field,
+    Method,
+    InterfaceMethod,
+    NameAndType,
+    MethodHandle,
+    MethodType,
+    Dynamic,
+    InvokeDynamic,
+    Module,
+    Package,
+    Unusable,
+}
+
+/// A constant that breaks a rule the JVM specification sets for the
+/// constant pool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConstantPoolViolation {
+    /// A reference that does not point at a constant of the kind required.
+    WrongKind {
+        /// The index of the constant holding the bad reference.
+        index: u16,
+        /// The index it refers to.
+        target: u16,
+        expected: ConstantKind,
+    },
+    /// A method handle whose reference kind is not one of the nine the
+    /// specification defines.
+    InvalidReferenceKind { index: u16, reference_kind: u8 },
+}
+
+impl fmt::Display for ConstantPoolViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConstantPoolViolation::WrongKind {
+                index,
+                target,
+                expected,
+            } => write!(
+                f,
+                "constant #{index} refers to #{target}, which is not a {expected:?} constant"
+            ),
+            ConstantPoolViolation::InvalidReferenceKind {
+                index,
+                reference_kind,
+            } => write!(
+                f,
+                "method handle #{index} has invalid reference kind {reference_kind}"
+            ),
+        }
+    }
+}
+
+fn constant(index: u16) -> impl Fn(&[u8]) -> IResult<&[u8], Constant, ParsingError> {
+    move |input| {
+        let (input, tag) = be_u8(input)?;
+
+        match tag {
+            1 => {
+                let (remaining, bytes) = length_data(be_u16)(input)?;
+                match mutf8::decode(bytes) {
+                    Ok(string) => Ok((remaining, Constant::Utf8(string))),
+                    Err(err) => Err(nom::Err::Failure(
+                        err.with_kind(ParsingErrorKind::MalformedUtf8 { index }),
+                    )),
+                }
             }
-        })(input),
-        _ => fail(input),
+            3 => map(be_i32, Constant::Integer)(input),
+            4 => map(be_f32, Constant::Float)(input),
+            5 => map(be_i64, Constant::Long)(input),
+            6 => map(be_f64, Constant::Double)(input),
+            7 => map(be_u16, Constant::Class)(input),
+            8 => map(be_u16, Constant::String)(input),
+            9 => map(tuple((be_u16, be_u16)), |(class_index, nametype_index)| {
+                Constant::Field {
+                    class_index,
+                    nametype_index,
+                }
+            })(input),
+            10 => map(tuple((be_u16, be_u16)), |(class_index, nametype_index)| {
+                Constant::Method {
+                    class_index,
+                    nametype_index,
+                }
+            })(input),
+            11 => map(tuple((be_u16, be_u16)), |(class_index, nametype_index)| {
+                Constant::InterfaceMethod {
+                    class_index,
+                    nametype_index,
+                }
+            })(input),
+            12 => map(tuple((be_u16, be_u16)), |(name_index, descriptor_index)| {
+                Constant::NameAndType {
+                    name_index,
+                    descriptor_index,
+                }
+            })(input),
+            15 => map(
+                tuple((be_u8, be_u16)),
+                |(reference_kind, reference_index)| Constant::MethodHandle {
+                    reference_kind,
+                    reference_index,
+                },
+            )(input),
+            16 => map(be_u16, Constant::MethodType)(input),
+            17 => map(
+                tuple((be_u16, be_u16)),
+                |(bootstrap_method_attr_index, nametype_index)| Constant::Dynamic {
+                    bootstrap_method_attr_index,
+                    nametype_index,
+                },
+            )(input),
+            18 => map(
+                tuple((be_u16, be_u16)),
+                |(bootstrap_method_attr_index, nametype_index)| Constant::InvokeDynamic {
+                    bootstrap_method_attr_index,
+                    nametype_index,
+                },
+            )(input),
+            19 => map(be_u16, Constant::Module)(input),
+            20 => map(be_u16, Constant::Package)(input),
+            _ => fail(input),
+        }
     }
 }
 
@@ -97,62 +251,317 @@ pub struct ConstantPool {
 }
 
 impl ConstantPool {
-    pub fn get(&self, index: u16) -> &Constant {
-        &self.items[index as usize - 1]
+    /// Returns the constant at `index`. Indices start at 1, so anything
+    /// outside `1..=len` is an `InvalidIndex` error rather than a panic.
+    pub fn at(&self, index: u16) -> Result<&Constant, ConstantPoolError> {
+        match index {
+            0 => Err(ConstantPoolError::InvalidIndex(index)),
+            _ => self
+                .items
+                .get(index as usize - 1)
+                .ok_or(ConstantPoolError::InvalidIndex(index)),
+        }
+    }
+
+    pub(crate) fn unexpected(&self, index: u16, expected: ConstantKind) -> ConstantPoolError {
+        match self.at(index) {
+            Ok(constant) => ConstantPoolError::UnexpectedKind {
+                index,
+                expected,
+                found: constant.kind(),
+            },
+            Err(err) => err,
+        }
+    }
+
+    pub fn utf8(&self, index: u16) -> Result<&str, ConstantPoolError> {
+        match self.at(index)? {
+            Constant::Utf8(data) => Ok(data.as_str()),
+            _ => Err(self.unexpected(index, ConstantKind::Utf8)),
+        }
+    }
+
+    pub fn integer(&self, index: u16) -> Result<i32, ConstantPoolError> {
+        match *self.at(index)? {
+            Constant::Integer(value) => Ok(value),
+            _ => Err(self.unexpected(index, ConstantKind::Integer)),
+        }
+    }
+
+    pub fn float(&self, index: u16) -> Result<f32, ConstantPoolError> {
+        match *self.at(index)? {
+            Constant::Float(value) => Ok(value),
+            _ => Err(self.unexpected(index, ConstantKind::Float)),
+        }
     }
 
-    pub fn utf8(&self, index: u16) -> &str {
-        match &self.items[index as usize - 1] {
-            Constant::Utf8(data) => data.as_str(),
-            _ => panic!(),
+    pub fn long(&self, index: u16) -> Result<i64, ConstantPoolError> {
+        match *self.at(index)? {
+            Constant::Long(value) => Ok(value),
+            _ => Err(self.unexpected(index, ConstantKind::Long)),
         }
     }
 
-    pub fn name_and_type(&self, index: u16) -> (&str, &str) {
-        let (name_index, descriptor_index) = match self.items[index as usize - 1] {
+    pub fn double(&self, index: u16) -> Result<f64, ConstantPoolError> {
+        match *self.at(index)? {
+            Constant::Double(value) => Ok(value),
+            _ => Err(self.unexpected(index, ConstantKind::Double)),
+        }
+    }
+
+    /// Resolves a `String` constant to its text.
+    pub fn string(&self, index: u16) -> Result<&str, ConstantPoolError> {
+        match self.at(index)? {
+            Constant::String(string_index) => self.utf8(*string_index),
+            _ => Err(self.unexpected(index, ConstantKind::String)),
+        }
+    }
+
+    pub fn name_and_type(&self, index: u16) -> Result<(&str, &str), ConstantPoolError> {
+        match *self.at(index)? {
             Constant::NameAndType {
                 name_index,
                 descriptor_index,
-            } => (name_index, descriptor_index),
-            _ => panic!(),
-        };
-        (self.utf8(name_index), self.utf8(descriptor_index))
+            } => Ok((self.utf8(name_index)?, self.utf8(descriptor_index)?)),
+            _ => Err(self.unexpected(index, ConstantKind::NameAndType)),
+        }
     }
 
-    pub fn class(&self, index: u16) -> &str {
-        let name_index = match self.items[index as usize - 1] {
-            Constant::Class(name_index) => name_index,
-            _ => panic!(),
-        };
-        self.utf8(name_index)
+    /// Resolves a `Class` constant to the binary name of the class.
+    pub fn class(&self, index: u16) -> Result<&str, ConstantPoolError> {
+        match *self.at(index)? {
+            Constant::Class(name_index) => self.utf8(name_index),
+            _ => Err(self.unexpected(index, ConstantKind::Class)),
+        }
     }
 
-    pub fn field(&self, index: u16) -> (u16, u16) {
-        match self.items[index as usize - 1] {
+    /// Resolves a `Field` constant to the class, name and descriptor it
+    /// refers to.
+    pub fn field_ref(&self, index: u16) -> Result<MemberRef<'_>, ConstantPoolError> {
+        match *self.at(index)? {
             Constant::Field {
                 class_index,
                 nametype_index,
-            } => (class_index, nametype_index),
-            _ => panic!(),
+            } => self.member_ref(class_index, nametype_index),
+            _ => Err(self.unexpected(index, ConstantKind::Field)),
         }
     }
 
-    pub fn method(&self, index: u16) -> (u16, u16) {
-        match self.items[index as usize - 1] {
+    /// Resolves a `Method` constant to the class, name and descriptor it
+    /// refers to.
+    pub fn method_ref(&self, index: u16) -> Result<MemberRef<'_>, ConstantPoolError> {
+        match *self.at(index)? {
             Constant::Method {
                 class_index,
                 nametype_index,
-            } => (class_index, nametype_index),
-            _ => panic!(),
+            } => self.member_ref(class_index, nametype_index),
+            _ => Err(self.unexpected(index, ConstantKind::Method)),
+        }
+    }
+
+    /// Resolves an `InterfaceMethod` constant to the interface, name and
+    /// descriptor it refers to.
+    pub fn interface_method_ref(&self, index: u16) -> Result<MemberRef<'_>, ConstantPoolError> {
+        match *self.at(index)? {
+            Constant::InterfaceMethod {
+                class_index,
+                nametype_index,
+            } => self.member_ref(class_index, nametype_index),
+            _ => Err(self.unexpected(index, ConstantKind::InterfaceMethod)),
+        }
+    }
+
+    /// Resolves a `MethodHandle` constant to its kind and the member it
+    /// refers to.
+    pub fn method_handle(&self, index: u16) -> Result<MethodHandle<'_>, ConstantPoolError> {
+        match *self.at(index)? {
+            Constant::MethodHandle {
+                reference_kind,
+                reference_index,
+            } => {
+                let member = match *self.at(reference_index)? {
+                    Constant::Field {
+                        class_index,
+                        nametype_index,
+                    }
+                    | Constant::Method {
+                        class_index,
+                        nametype_index,
+                    }
+                    | Constant::InterfaceMethod {
+                        class_index,
+                        nametype_index,
+                    } => self.member_ref(class_index, nametype_index)?,
+                    _ => return Err(self.unexpected(reference_index, ConstantKind::Method)),
+                };
+                Ok(MethodHandle {
+                    reference_kind,
+                    member,
+                })
+            }
+            _ => Err(self.unexpected(index, ConstantKind::MethodHandle)),
+        }
+    }
+
+    /// Resolves a `MethodType` constant to its method descriptor.
+    pub fn method_type(&self, index: u16) -> Result<&str, ConstantPoolError> {
+        match *self.at(index)? {
+            Constant::MethodType(descriptor_index) => self.utf8(descriptor_index),
+            _ => Err(self.unexpected(index, ConstantKind::MethodType)),
+        }
+    }
+
+    /// Resolves a `Module` constant to the name of the module.
+    pub fn module(&self, index: u16) -> Result<&str, ConstantPoolError> {
+        match *self.at(index)? {
+            Constant::Module(name_index) => self.utf8(name_index),
+            _ => Err(self.unexpected(index, ConstantKind::Module)),
+        }
+    }
+
+    /// Resolves a `Package` constant to the internal name of the package.
+    pub fn package(&self, index: u16) -> Result<&str, ConstantPoolError> {
+        match *self.at(index)? {
+            Constant::Package(name_index) => self.utf8(name_index),
+            _ => Err(self.unexpected(index, ConstantKind::Package)),
+        }
+    }
+
+    fn member_ref(
+        &self,
+        class_index: u16,
+        nametype_index: u16,
+    ) -> Result<MemberRef<'_>, ConstantPoolError> {
+        let (name, descriptor) = self.name_and_type(nametype_index)?;
+        Ok(MemberRef {
+            class: self.class(class_index)?,
+            name,
+            descriptor,
+        })
+    }
+
+    /// Checks that every index stored inside a constant points at a constant
+    /// of the right kind, returning all of the violations found.
+    pub fn validate(&self) -> Vec<ConstantPoolViolation> {
+        let mut violations = Vec::new();
+
+        for (i, constant) in self.items.iter().enumerate() {
+            let index = i as u16 + 1;
+            let references: &[(u16, ConstantKind)] = match *constant {
+                Constant::Class(name_index) => &[(name_index, ConstantKind::Utf8)],
+                Constant::String(string_index) => &[(string_index, ConstantKind::Utf8)],
+                Constant::Field {
+                    class_index,
+                    nametype_index,
+                }
+                | Constant::Method {
+                    class_index,
+                    nametype_index,
+                }
+                | Constant::InterfaceMethod {
+                    class_index,
+                    nametype_index,
+                } => &[
+                    (class_index, ConstantKind::Class),
+                    (nametype_index, ConstantKind::NameAndType),
+                ],
+                Constant::NameAndType {
+                    name_index,
+                    descriptor_index,
+                } => &[
+                    (name_index, ConstantKind::Utf8),
+                    (descriptor_index, ConstantKind::Utf8),
+                ],
+                // invokeSpecial and invokeStatic handles may refer to either
+                // kind of method, so only the other kinds are checked.
+                Constant::MethodHandle {
+                    reference_kind,
+                    reference_index,
+                } => match reference_kind {
+                    1..=4 => &[(reference_index, ConstantKind::Field)],
+                    5 | 8 => &[(reference_index, ConstantKind::Method)],
+                    9 => &[(reference_index, ConstantKind::InterfaceMethod)],
+                    6 | 7 => &[],
+                    _ => {
+                        violations.push(ConstantPoolViolation::InvalidReferenceKind {
+                            index,
+                            reference_kind,
+                        });
+                        &[]
+                    }
+                },
+                Constant::MethodType(descriptor_index) => &[(descriptor_index, ConstantKind::Utf8)],
+                Constant::Module(name_index) | Constant::Package(name_index) => {
+                    &[(name_index, ConstantKind::Utf8)]
+                }
+                Constant::Dynamic { nametype_index, .. }
+                | Constant::InvokeDynamic { nametype_index, .. } => {
+                    &[(nametype_index, ConstantKind::NameAndType)]
+                }
+                _ => &[],
+            };
+
+            for &(target, expected) in references {
+                let found = match target {
+                    0 => None,
+                    _ => self.items.get(target as usize - 1),
+                };
+                if found.map(Constant::kind) != Some(expected) {
+                    violations.push(ConstantPoolViolation::WrongKind {
+                        index,
+                        target,
+                        expected,
+                    });
+                }
+            }
         }
+
+        violations
     }
 }
 
-fn constant_pool(input: &[u8]) -> IResult<&[u8], ConstantPool> {
-    let (input, contant_pool_count) = be_u16(input)?;
-    map(count(constant, contant_pool_count as usize - 1), |items| {
-        ConstantPool { items }
-    })(input)
+/// A field or method reference with its class, name and descriptor resolved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemberRef<'a> {
+    pub class: &'a str,
+    pub name: &'a str,
+    pub descriptor: &'a str,
+}
+
+/// A method handle with the member it refers to resolved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MethodHandle<'a> {
+    pub reference_kind: u8,
+    pub member: MemberRef<'a>,
+}
+
+fn constant_pool(input: &[u8]) -> IResult<&[u8], ConstantPool, ParsingError> {
+    let (mut input, contant_pool_count) = be_u16(input)?;
+    let mut items = Vec::new();
+
+    let mut index = 1;
+    while index < contant_pool_count {
+        let (remaining, item) = constant(index)(input)?;
+        // Longs and doubles take up two entries, the second of which can't
+        // be used.
+        let wide = matches!(item, Constant::Long(_) | Constant::Double(_));
+        if wide && index + 1 >= contant_pool_count {
+            return Err(nom::Err::Failure(ParsingError::new(
+                input,
+                ParsingErrorKind::WideConstantAtEnd { index },
+            )));
+        }
+        items.push(item);
+        input = remaining;
+        index += 1;
+
+        if wide {
+            items.push(Constant::Unusable);
+            index += 1;
+        }
+    }
+
+    Ok((input, ConstantPool { items }))
 }
 
 #[derive(Debug, Clone)]
@@ -163,11 +572,87 @@ pub struct Field {
     pub attributes: Vec<Attribute>,
 }
 
-fn field(pool: ConstantPool) -> impl Fn(&[u8]) -> IResult<&[u8], Field> {
+impl Field {
+    /// Returns every annotation, visible ones first.
+    pub fn annotations(&self) -> Vec<&Annotation> {
+        annotations(&self.attributes)
+    }
+
+    /// Returns the generic signature, if the declaration uses generics.
+    pub fn signature<'a>(&self, pool: &'a ConstantPool) -> Option<&'a str> {
+        signature(&self.attributes, pool)
+    }
+
+    pub fn is_synthetic(&self) -> bool {
+        self.access_flags.contains(FieldAccessFields::SYNTHETIC)
+            || self
+                .attributes
+                .iter()
+                .any(|attr| matches!(attr, Attribute::Synthetic))
+    }
+
+    pub fn is_deprecated(&self) -> bool {
+        self.attributes
+            .iter()
+            .any(|attr| matches!(attr, Attribute::Deprecated))
+    }
+
+    /// Resolves the constant a static final field is initialized to, if it
+    /// has one. The constant must be of the kind the field's type calls for,
+    /// like an Integer for a `boolean` field or a String for a `String` one.
+    pub fn constant_value<'a>(
+        &self,
+        pool: &'a ConstantPool,
+    ) -> Result<Option<ConstantValue<'a>>, ConstantPoolError> {
+        let index = match self.attributes.iter().find_map(|attr| {
+            if let Attribute::ConstantValue(index) = attr {
+                Some(*index)
+            } else {
+                None
+            }
+        }) {
+            Some(index) => index,
+            None => return Ok(None),
+        };
+
+        let expected = match pool.utf8(self.descriptor_index)? {
+            "B" | "C" | "I" | "S" | "Z" => ConstantKind::Integer,
+            "F" => ConstantKind::Float,
+            "J" => ConstantKind::Long,
+            "D" => ConstantKind::Double,
+            _ => ConstantKind::String,
+        };
+        let value = match (expected, pool.at(index)?) {
+            (ConstantKind::Integer, Constant::Integer(value)) => ConstantValue::Int(*value),
+            (ConstantKind::Float, Constant::Float(value)) => ConstantValue::Float(*value),
+            (ConstantKind::Long, Constant::Long(value)) => ConstantValue::Long(*value),
+            (ConstantKind::Double, Constant::Double(value)) => ConstantValue::Double(*value),
+            (ConstantKind::String, Constant::String(_)) => {
+                ConstantValue::String(pool.string(index)?)
+            }
+            _ => return Err(pool.unexpected(index, expected)),
+        };
+
+        Ok(Some(value))
+    }
+}
+
+/// The value a ConstantValue attribute initializes a field to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConstantValue<'a> {
+    /// The value of an `int`, `short`, `char`, `byte`, or `boolean` field.
+    Int(i32),
+    Float(f32),
+    Long(i64),
+    Double(f64),
+    String(&'a str),
+}
+
+fn field(pool: ConstantPool) -> impl Fn(&[u8]) -> IResult<&[u8], Field, ParsingError> {
     move |input| {
         map(
             tuple((
-                map(be_u16, |bits| FieldAccessFields::from_bits_truncate(bits)),
+                map(be_u16, FieldAccessFields::from_bits_truncate),
                 be_u16,
                 be_u16,
                 length_count(be_u16, attribute(pool.clone())),
@@ -191,25 +676,176 @@ pub struct Method {
 }
 
 impl Method {
-    pub fn code(&self) -> &Vec<Instruction> {
+    /// Returns every annotation, visible ones first.
+    pub fn annotations(&self) -> Vec<&Annotation> {
+        annotations(&self.attributes)
+    }
+
+    /// Returns the default value of the element this method declares. Only
+    /// methods of annotation interfaces have one.
+    pub fn annotation_default(&self) -> Option<&ElementValue> {
+        self.attributes.iter().find_map(|attr| {
+            if let Attribute::AnnotationDefault(value) = attr {
+                Some(value)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Returns every annotation on the parameter at `param_index`, visible
+    /// ones first.
+    pub fn parameter_annotations(&self, param_index: usize) -> Vec<&Annotation> {
+        let visible = self.attributes.iter().filter_map(|attr| {
+            if let Attribute::RuntimeVisibleParameterAnnotations(parameters) = attr {
+                parameters.get(param_index)
+            } else {
+                None
+            }
+        });
+        let invisible = self.attributes.iter().filter_map(|attr| {
+            if let Attribute::RuntimeInvisibleParameterAnnotations(parameters) = attr {
+                parameters.get(param_index)
+            } else {
+                None
+            }
+        });
+
+        visible.chain(invisible).flatten().collect()
+    }
+
+    /// Returns the generic signature, if the declaration uses generics.
+    pub fn signature<'a>(&self, pool: &'a ConstantPool) -> Option<&'a str> {
+        signature(&self.attributes, pool)
+    }
+
+    pub fn is_synthetic(&self) -> bool {
+        self.access_flags.contains(MethodAccessFlags::SYNTHETIC)
+            || self
+                .attributes
+                .iter()
+                .any(|attr| matches!(attr, Attribute::Synthetic))
+    }
+
+    pub fn is_deprecated(&self) -> bool {
+        self.attributes
+            .iter()
+            .any(|attr| matches!(attr, Attribute::Deprecated))
+    }
+
+    /// Returns the method's instructions along with their offsets, or `None`
+    /// if it has no code, like abstract and native methods.
+    pub fn code(&self) -> Option<&[(u32, Instruction)]> {
+        self.attributes.iter().find_map(|attr| {
+            if let Attribute::Code { code, .. } = attr {
+                Some(code.as_slice())
+            } else {
+                None
+            }
+        })
+    }
+
+    pub fn has_code(&self) -> bool {
+        self.code().is_some()
+    }
+
+    /// Resolves the names of the exceptions listed in the method's `throws`
+    /// clause.
+    pub fn declared_exceptions<'a>(
+        &self,
+        pool: &'a ConstantPool,
+    ) -> Result<Vec<&'a str>, ConstantPoolError> {
+        self.attributes
+            .iter()
+            .filter_map(|attr| {
+                if let Attribute::Exceptions(classes) = attr {
+                    Some(classes)
+                } else {
+                    None
+                }
+            })
+            .flatten()
+            .map(|&index| pool.class(index))
+            .collect()
+    }
+
+    pub fn exception_table(&self) -> &[ExceptionTableEntry] {
+        self.attributes
+            .iter()
+            .find_map(|attr| {
+                if let Attribute::Code { exceptions, .. } = attr {
+                    Some(exceptions.as_slice())
+                } else {
+                    None
+                }
+            })
+            .unwrap_or(&[])
+    }
+
+    pub fn max_stack(&self) -> u16 {
+        self.attributes
+            .iter()
+            .find_map(|attr| {
+                if let Attribute::Code { max_stack, .. } = attr {
+                    Some(*max_stack)
+                } else {
+                    None
+                }
+            })
+            .unwrap_or(0)
+    }
+
+    /// Looks up the source line the instruction at `pc` was compiled from.
+    pub fn line_number(&self, pc: u32) -> Option<u16> {
+        let attributes = self.attributes.iter().find_map(|attr| {
+            if let Attribute::Code { attributes, .. } = attr {
+                Some(attributes)
+            } else {
+                None
+            }
+        })?;
+
+        attributes
+            .iter()
+            .filter_map(|attr| {
+                if let Attribute::LineNumberTable(entries) = attr {
+                    Some(entries)
+                } else {
+                    None
+                }
+            })
+            .flatten()
+            .filter(|entry| u32::from(entry.start_pc) <= pc)
+            .max_by_key(|entry| entry.start_pc)
+            .map(|entry| entry.line_number)
+    }
+
+    /// Like [`Method::line_number`], for the 16-bit offsets the
+    /// LineNumberTable stores. The entries don't need to be sorted; the one
+    /// with the greatest `start_pc` at or before `pc` wins.
+    pub fn line_number_for_pc(&self, pc: u16) -> Option<u16> {
+        self.line_number(u32::from(pc))
+    }
+
+    pub fn max_locals(&self) -> u16 {
         self.attributes
             .iter()
             .find_map(|attr| {
-                if let Attribute::Code { code, .. } = attr {
-                    Some(code)
+                if let Attribute::Code { max_locals, .. } = attr {
+                    Some(*max_locals)
                 } else {
                     None
                 }
             })
-            .unwrap() // This is fine for now...
+            .unwrap_or(0)
     }
 }
 
-fn method(pool: ConstantPool) -> impl Fn(&[u8]) -> IResult<&[u8], Method> {
+fn method(pool: ConstantPool) -> impl Fn(&[u8]) -> IResult<&[u8], Method, ParsingError> {
     move |input| {
         map(
             tuple((
-                map(be_u16, |bits| MethodAccessFlags::from_bits_truncate(bits)),
+                map(be_u16, MethodAccessFlags::from_bits_truncate),
                 be_u16,
                 be_u16,
                 length_count(be_u16, attribute(pool.clone())),
@@ -230,93 +866,1642 @@ pub enum Attribute {
     Code {
         max_stack: u16,
         max_locals: u16,
-        code: Vec<Instruction>,
+        code: Vec<(u32, Instruction)>,
+        exceptions: Vec<ExceptionTableEntry>,
+        attributes: Vec<Attribute>,
+    },
+    LineNumberTable(Vec<LineNumberTableEntry>),
+    /// The classes of the checked exceptions a method declares it throws.
+    Exceptions(Vec<u16>),
+    InnerClasses(Vec<InnerClassEntry>),
+    Synthetic,
+    Deprecated,
+    /// The generic signature of a class, field, or method.
+    Signature(u16),
+    SourceFile(u16),
+    /// Extended debugging information, like the SMAP that Kotlin and JSP
+    /// compilers use to map generated code back to its source.
+    SourceDebugExtension(String),
+    RuntimeVisibleAnnotations(Vec<Annotation>),
+    /// Annotations with class retention, which is the default. They are
+    /// kept in the class file but not visible through reflection.
+    RuntimeInvisibleAnnotations(Vec<Annotation>),
+    /// The annotations on each of a method's parameters, in declaration
+    /// order.
+    RuntimeVisibleParameterAnnotations(Vec<Vec<Annotation>>),
+    RuntimeInvisibleParameterAnnotations(Vec<Vec<Annotation>>),
+    /// Annotations on uses of types, in signatures or in the method's code.
+    RuntimeVisibleTypeAnnotations(Vec<TypeAnnotation>),
+    RuntimeInvisibleTypeAnnotations(Vec<TypeAnnotation>),
+    /// The bootstrap methods that `invokedynamic` call sites and dynamic
+    /// constants are linked with.
+    BootstrapMethods(Vec<BootstrapMethodEntry>),
+    /// The dependencies and exports of a module, in a `module-info` class.
+    Module(ModuleDescriptor),
+    /// The Package constants of every package in a module, including ones
+    /// it doesn't export.
+    ModulePackages(Vec<u16>),
+    /// The Class constant of a module's main class.
+    ModuleMainClass(u16),
+    /// The Class constant of the nest host, for a class that is a member of
+    /// another class's nest.
+    NestHost(u16),
+    /// The Class constants of the members of the nest this class hosts.
+    NestMembers(Vec<u16>),
+    /// The Class constants of the classes allowed to extend or implement a
+    /// sealed class.
+    PermittedSubclasses(Vec<u16>),
+    /// The components of a record class, in declaration order.
+    Record(Vec<RecordComponent>),
+    /// The default value of an element of an annotation interface.
+    AnnotationDefault(ElementValue),
+    /// An attribute this parser doesn't understand, kept verbatim so it can
+    /// still be inspected or written back out. The name is empty if the name
+    /// index doesn't point at a Utf8 constant.
+    Unknown {
+        name: String,
+        data: Vec<u8>,
     },
-    Unknown(u16),
 }
 
-fn attribute(constant_pool: ConstantPool) -> impl Fn(&[u8]) -> IResult<&[u8], Attribute> {
-    move |input| {
-        let (input, name_index) = be_u16(input)?;
-        let (remaining, attribute_data) = length_data(be_u32)(input)?;
-
-        if let Constant::Utf8(str) = &constant_pool.items[name_index as usize - 1] {
-            let (_, attr) = match str.as_str() {
-                "ConstantValue" => {
-                    map(be_u16, |index| Attribute::ConstantValue(index))(attribute_data)?
-                }
-                "Code" => map(
-                    tuple((be_u16, be_u16, length_value(be_u32, many0(instruction)))),
-                    |(max_stack, max_locals, code)| Attribute::Code {
-                        max_stack,
-                        max_locals,
-                        code,
-                    },
-                )(attribute_data)?,
-                _ => success(Attribute::Unknown(name_index))(attribute_data)?,
-            };
-            Ok((remaining, attr))
-        } else {
-            Ok((remaining, Attribute::Unknown(name_index)))
-        }
-    }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExceptionTableEntry {
+    /// The start (inclusive) of the range of offsets the handler covers.
+    pub start_pc: u16,
+    /// The end (exclusive) of the range of offsets the handler covers.
+    pub end_pc: u16,
+    pub handler_pc: u16,
+    /// The class of exceptions the handler catches, or zero to catch all of
+    /// them.
+    pub catch_type: u16,
 }
 
-#[derive(Debug)]
-pub struct ClassFile {
-    pub version: Version,
-    pub constant_pool: ConstantPool,
-    pub access_flags: ClassAccessFlags,
-    pub this_class: u16,
-    pub super_class: u16,
-    pub interfaces: Vec<u16>,
-    pub fields: Vec<Field>,
-    pub methods: Vec<Method>,
-    pub attributes: Vec<Attribute>,
+fn exception_table_entry(input: &[u8]) -> IResult<&[u8], ExceptionTableEntry, ParsingError> {
+    map(
+        tuple((be_u16, be_u16, be_u16, be_u16)),
+        |(start_pc, end_pc, handler_pc, catch_type)| ExceptionTableEntry {
+            start_pc,
+            end_pc,
+            handler_pc,
+            catch_type,
+        },
+    )(input)
 }
 
-impl ClassFile {
-    pub fn get_method(&self, name: &str, descriptor: &str) -> &Method {
-        self.methods
-            .iter()
-            .find(|method| {
-                let method_name = self.constant_pool.utf8(method.name_index);
-                let method_descriptor = self.constant_pool.utf8(method.descriptor_index);
-                method_name == name && method_descriptor == descriptor
-            })
-            .unwrap() // This is fine for now; this should only be used to get a known method.
-    }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InnerClassEntry {
+    pub inner_class_info_index: u16,
+    /// Zero for local and anonymous classes, which aren't members of
+    /// another class.
+    pub outer_class_info_index: u16,
+    /// Zero for anonymous classes.
+    pub inner_name_index: u16,
+    pub access_flags: InnerClassAccessFlags,
 }
 
-pub fn parse_class(input: &[u8]) -> IResult<&[u8], ClassFile> {
-    let (input, _) = tag([0xCA, 0xFE, 0xBA, 0xBE])(input)?;
-    let (input, version) = version(input)?;
-    let (input, constant_pool) = constant_pool(input)?;
-
-    let mut parser = map(
+fn inner_class_entry(input: &[u8]) -> IResult<&[u8], InnerClassEntry, ParsingError> {
+    map(
         tuple((
-            map(be_u16, |bits| ClassAccessFlags::from_bits_truncate(bits)),
             be_u16,
             be_u16,
-            length_count(be_u16, be_u16),
-            length_count(be_u16, field(constant_pool.clone())),
-            length_count(be_u16, method(constant_pool.clone())),
-            length_count(be_u16, attribute(constant_pool.clone())),
+            be_u16,
+            map(be_u16, InnerClassAccessFlags::from_bits_truncate),
         )),
-        |(access_flags, this_class, super_class, interfaces, fields, methods, attributes)| {
-            ClassFile {
-                version,
-                constant_pool: constant_pool.clone(),
+        |(inner_class_info_index, outer_class_info_index, inner_name_index, access_flags)| {
+            InnerClassEntry {
+                inner_class_info_index,
+                outer_class_info_index,
+                inner_name_index,
                 access_flags,
-                this_class,
-                super_class,
-                interfaces,
-                fields,
-                methods,
-                attributes,
             }
         },
-    );
+    )(input)
+}
+
+/// An [`InnerClassEntry`] with its names resolved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InnerClass<'a> {
+    /// The binary name of the nested class, like `Outer$Inner`.
+    pub class: &'a str,
+    pub outer_class: Option<&'a str>,
+    /// The simple name the class was given in source.
+    pub name: Option<&'a str>,
+    pub access_flags: InnerClassAccessFlags,
+}
 
-    parser(input)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BootstrapMethodEntry {
+    /// The MethodHandle constant of the bootstrap method.
+    pub bootstrap_method_ref: u16,
+    /// The constants passed to the bootstrap method as static arguments.
+    pub bootstrap_arguments: Vec<u16>,
+}
+
+fn bootstrap_method_entry(input: &[u8]) -> IResult<&[u8], BootstrapMethodEntry, ParsingError> {
+    map(
+        tuple((be_u16, length_count(be_u16, be_u16))),
+        |(bootstrap_method_ref, bootstrap_arguments)| BootstrapMethodEntry {
+            bootstrap_method_ref,
+            bootstrap_arguments,
+        },
+    )(input)
+}
+
+/// A [`BootstrapMethodEntry`] with its method handle and arguments resolved.
+#[derive(Debug, Clone)]
+pub struct BootstrapMethod<'a> {
+    pub method: MethodHandle<'a>,
+    pub arguments: Vec<&'a Constant>,
+}
+
+/// A component of a record class, along with its own attributes, like its
+/// Signature and annotations.
+#[derive(Debug, Clone)]
+pub struct RecordComponent {
+    pub name_index: u16,
+    pub descriptor_index: u16,
+    pub attributes: Vec<Attribute>,
+}
+
+impl RecordComponent {
+    /// Returns every annotation, visible ones first.
+    pub fn annotations(&self) -> Vec<&Annotation> {
+        annotations(&self.attributes)
+    }
+
+    /// Returns the generic signature, if the component's type uses generics.
+    pub fn signature<'a>(&self, pool: &'a ConstantPool) -> Option<&'a str> {
+        signature(&self.attributes, pool)
+    }
+}
+
+fn record_component(
+    constant_pool: ConstantPool,
+) -> impl Fn(&[u8]) -> IResult<&[u8], RecordComponent, ParsingError> {
+    move |input| {
+        map(
+            tuple((
+                be_u16,
+                be_u16,
+                length_count(be_u16, nested_attribute(constant_pool.clone(), true)),
+            )),
+            |(name_index, descriptor_index, attributes)| RecordComponent {
+                name_index,
+                descriptor_index,
+                attributes,
+            },
+        )(input)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineNumberTableEntry {
+    /// The offset of the first instruction that belongs to the line.
+    pub start_pc: u16,
+    pub line_number: u16,
+}
+
+fn line_number_table_entry(input: &[u8]) -> IResult<&[u8], LineNumberTableEntry, ParsingError> {
+    map(tuple((be_u16, be_u16)), |(start_pc, line_number)| {
+        LineNumberTableEntry {
+            start_pc,
+            line_number,
+        }
+    })(input)
+}
+
+/// Merges the visible and invisible annotations.
+fn annotations(attributes: &[Attribute]) -> Vec<&Annotation> {
+    let visible = attributes.iter().filter_map(|attr| {
+        if let Attribute::RuntimeVisibleAnnotations(annotations) = attr {
+            Some(annotations)
+        } else {
+            None
+        }
+    });
+    let invisible = attributes.iter().filter_map(|attr| {
+        if let Attribute::RuntimeInvisibleAnnotations(annotations) = attr {
+            Some(annotations)
+        } else {
+            None
+        }
+    });
+
+    visible.chain(invisible).flatten().collect()
+}
+
+fn signature<'a>(attributes: &[Attribute], pool: &'a ConstantPool) -> Option<&'a str> {
+    attributes.iter().find_map(|attr| {
+        if let Attribute::Signature(index) = attr {
+            pool.utf8(*index).ok()
+        } else {
+            None
+        }
+    })
+}
+
+fn attribute(
+    constant_pool: ConstantPool,
+) -> impl Fn(&[u8]) -> IResult<&[u8], Attribute, ParsingError> {
+    nested_attribute(constant_pool, false)
+}
+
+/// Parses an attribute, which is `nested` inside a Code or Record attribute.
+/// Nested attributes can't hold attributes of their own, so a malformed
+/// class can't make parsing recurse without bound.
+fn nested_attribute(
+    constant_pool: ConstantPool,
+    nested: bool,
+) -> impl Fn(&[u8]) -> IResult<&[u8], Attribute, ParsingError> {
+    move |input| {
+        let (input, name_index) = be_u16(input)?;
+        let (remaining, attribute_data) = length_data(be_u32)(input)?;
+
+        if let Ok(Constant::Utf8(str)) = constant_pool.at(name_index) {
+            let (_, attr) = match str.as_str() {
+                "Code" | "Record" if nested => {
+                    return Err(nom::Err::Failure(ParsingError::new(
+                        input,
+                        ParsingErrorKind::NestedAttribute { name_index },
+                    )))
+                }
+                "ConstantValue" => map(be_u16, Attribute::ConstantValue)(attribute_data)?,
+                "Code" => map(
+                    tuple((
+                        be_u16,
+                        be_u16,
+                        length_value(be_u32, code),
+                        length_count(be_u16, exception_table_entry),
+                        length_count(be_u16, nested_attribute(constant_pool.clone(), true)),
+                    )),
+                    |(max_stack, max_locals, code, exceptions, attributes)| Attribute::Code {
+                        max_stack,
+                        max_locals,
+                        code,
+                        exceptions,
+                        attributes,
+                    },
+                )(attribute_data)?,
+                "LineNumberTable" => map(
+                    length_count(be_u16, line_number_table_entry),
+                    Attribute::LineNumberTable,
+                )(attribute_data)?,
+                "Exceptions" => {
+                    map(length_count(be_u16, be_u16), Attribute::Exceptions)(attribute_data)?
+                }
+                "InnerClasses" => map(
+                    length_count(be_u16, inner_class_entry),
+                    Attribute::InnerClasses,
+                )(attribute_data)?,
+                "Synthetic" => map(eof, |_| Attribute::Synthetic)(attribute_data)?,
+                "Deprecated" => map(eof, |_| Attribute::Deprecated)(attribute_data)?,
+                "Signature" => map(be_u16, Attribute::Signature)(attribute_data)?,
+                "SourceFile" => map(be_u16, Attribute::SourceFile)(attribute_data)?,
+                "RuntimeVisibleAnnotations" => map(
+                    length_count(be_u16, annotation),
+                    Attribute::RuntimeVisibleAnnotations,
+                )(attribute_data)?,
+                "RuntimeInvisibleAnnotations" => map(
+                    length_count(be_u16, annotation),
+                    Attribute::RuntimeInvisibleAnnotations,
+                )(attribute_data)?,
+                "RuntimeVisibleParameterAnnotations" => map(
+                    length_count(be_u8, length_count(be_u16, annotation)),
+                    Attribute::RuntimeVisibleParameterAnnotations,
+                )(attribute_data)?,
+                "RuntimeInvisibleParameterAnnotations" => map(
+                    length_count(be_u8, length_count(be_u16, annotation)),
+                    Attribute::RuntimeInvisibleParameterAnnotations,
+                )(attribute_data)?,
+                "RuntimeVisibleTypeAnnotations" => map(
+                    length_count(be_u16, type_annotation),
+                    Attribute::RuntimeVisibleTypeAnnotations,
+                )(attribute_data)?,
+                "RuntimeInvisibleTypeAnnotations" => map(
+                    length_count(be_u16, type_annotation),
+                    Attribute::RuntimeInvisibleTypeAnnotations,
+                )(attribute_data)?,
+                "BootstrapMethods" => map(
+                    length_count(be_u16, bootstrap_method_entry),
+                    Attribute::BootstrapMethods,
+                )(attribute_data)?,
+                "NestHost" => map(be_u16, Attribute::NestHost)(attribute_data)?,
+                "NestMembers" => {
+                    map(length_count(be_u16, be_u16), Attribute::NestMembers)(attribute_data)?
+                }
+                "PermittedSubclasses" => map(
+                    length_count(be_u16, be_u16),
+                    Attribute::PermittedSubclasses,
+                )(attribute_data)?,
+                "Record" => map(
+                    length_count(be_u16, record_component(constant_pool.clone())),
+                    Attribute::Record,
+                )(attribute_data)?,
+                "Module" => map(module, Attribute::Module)(attribute_data)?,
+                "ModulePackages" => {
+                    map(length_count(be_u16, be_u16), Attribute::ModulePackages)(attribute_data)?
+                }
+                "ModuleMainClass" => map(be_u16, Attribute::ModuleMainClass)(attribute_data)?,
+                "AnnotationDefault" => {
+                    map(element_value, Attribute::AnnotationDefault)(attribute_data)?
+                }
+                "SourceDebugExtension" => match mutf8::decode(attribute_data) {
+                    Ok(debug) => (&[][..], Attribute::SourceDebugExtension(debug)),
+                    Err(err) => {
+                        return Err(nom::Err::Failure(err.with_kind(
+                            ParsingErrorKind::MalformedUtf8Attribute { name_index },
+                        )))
+                    }
+                },
+                name => (
+                    &[][..],
+                    Attribute::Unknown {
+                        name: name.to_owned(),
+                        data: attribute_data.to_vec(),
+                    },
+                ),
+            };
+            Ok((remaining, attr))
+        } else {
+            Ok((
+                remaining,
+                Attribute::Unknown {
+                    name: String::new(),
+                    data: attribute_data.to_vec(),
+                },
+            ))
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ClassFile {
+    pub version: Version,
+    pub constant_pool: ConstantPool,
+    pub access_flags: ClassAccessFlags,
+    pub this_class: u16,
+    pub super_class: u16,
+    pub interfaces: Vec<u16>,
+    pub fields: Vec<Field>,
+    pub methods: Vec<Method>,
+    pub attributes: Vec<Attribute>,
+}
+
+impl ClassFile {
+    /// Reads a whole class file from `reader` and parses it.
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<ClassFile, ClassFileError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        parse_class_checked(&bytes)
+    }
+
+    /// Returns the binary name of this class.
+    pub fn name(&self) -> Result<&str, ConstantPoolError> {
+        self.constant_pool.class(self.this_class)
+    }
+
+    /// Returns the binary name of the superclass, or `None` if this class is
+    /// `java/lang/Object`.
+    pub fn super_class_name(&self) -> Result<Option<&str>, ConstantPoolError> {
+        match self.super_class {
+            0 => Ok(None),
+            index => self.constant_pool.class(index).map(Some),
+        }
+    }
+
+    /// Returns the binary names of the interfaces the class directly
+    /// implements, or that the interface directly extends.
+    pub fn interface_names(&self) -> Result<Vec<&str>, ConstantPoolError> {
+        self.interfaces
+            .iter()
+            .map(|&index| self.constant_pool.class(index))
+            .collect()
+    }
+
+    pub fn is_synthetic(&self) -> bool {
+        self.access_flags.contains(ClassAccessFlags::SYNTHETIC)
+            || self
+                .attributes
+                .iter()
+                .any(|attr| matches!(attr, Attribute::Synthetic))
+    }
+
+    pub fn is_deprecated(&self) -> bool {
+        self.attributes
+            .iter()
+            .any(|attr| matches!(attr, Attribute::Deprecated))
+    }
+
+    /// Returns every annotation, visible ones first.
+    pub fn annotations(&self) -> Vec<&Annotation> {
+        annotations(&self.attributes)
+    }
+
+    /// Returns the generic signature of the class, if it has type parameters
+    /// or extends a parameterized type.
+    pub fn signature(&self) -> Option<&str> {
+        signature(&self.attributes, &self.constant_pool)
+    }
+
+    pub fn get_field(&self, name: &str, descriptor: &str) -> Option<&Field> {
+        self.fields.iter().find(|field| {
+            let field_name = self.constant_pool.utf8(field.name_index);
+            let field_descriptor = self.constant_pool.utf8(field.descriptor_index);
+            field_name == Ok(name) && field_descriptor == Ok(descriptor)
+        })
+    }
+
+    pub fn get_method(&self, name: &str, descriptor: &str) -> Option<&Method> {
+        self.methods.iter().find(|method| {
+            let method_name = self.constant_pool.utf8(method.name_index);
+            let method_descriptor = self.constant_pool.utf8(method.descriptor_index);
+            method_name == Ok(name) && method_descriptor == Ok(descriptor)
+        })
+    }
+
+    /// Resolves the entries of the class's InnerClasses attribute, which
+    /// lists every nested class it declares or refers to.
+    pub fn inner_classes(&self) -> Result<Vec<InnerClass<'_>>, ConstantPoolError> {
+        let pool = &self.constant_pool;
+        let optional = |index| match index {
+            0 => Ok(None),
+            index => pool.class(index).map(Some),
+        };
+
+        self.attributes
+            .iter()
+            .filter_map(|attr| {
+                if let Attribute::InnerClasses(entries) = attr {
+                    Some(entries)
+                } else {
+                    None
+                }
+            })
+            .flatten()
+            .map(|entry| {
+                Ok(InnerClass {
+                    class: pool.class(entry.inner_class_info_index)?,
+                    outer_class: optional(entry.outer_class_info_index)?,
+                    name: match entry.inner_name_index {
+                        0 => None,
+                        index => Some(pool.utf8(index)?),
+                    },
+                    access_flags: entry.access_flags,
+                })
+            })
+            .collect()
+    }
+
+    /// Resolves the entry at `index` of the class's BootstrapMethods
+    /// attribute, or returns `None` if there is no such entry.
+    pub fn bootstrap_method(
+        &self,
+        index: u16,
+    ) -> Result<Option<BootstrapMethod<'_>>, ConstantPoolError> {
+        let entry = self
+            .attributes
+            .iter()
+            .find_map(|attr| {
+                if let Attribute::BootstrapMethods(entries) = attr {
+                    Some(entries)
+                } else {
+                    None
+                }
+            })
+            .and_then(|entries| entries.get(index as usize));
+        let entry = match entry {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+
+        let pool = &self.constant_pool;
+        Ok(Some(BootstrapMethod {
+            method: pool.method_handle(entry.bootstrap_method_ref)?,
+            arguments: entry
+                .bootstrap_arguments
+                .iter()
+                .map(|&index| pool.at(index))
+                .collect::<Result<_, _>>()?,
+        }))
+    }
+
+    /// Returns the module declaration, if this is a `module-info` class.
+    pub fn module(&self) -> Option<&ModuleDescriptor> {
+        self.attributes.iter().find_map(|attr| {
+            if let Attribute::Module(module) = attr {
+                Some(module)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Resolves the names of the classes permitted to extend or implement
+    /// this one, in the order they were declared.
+    pub fn permitted_subclasses(&self) -> Result<Vec<&str>, ConstantPoolError> {
+        self.permitted_subclass_indices()
+            .map(|&index| self.constant_pool.class(index))
+            .collect()
+    }
+
+    /// Whether the class is sealed, restricting which classes may extend or
+    /// implement it.
+    pub fn is_sealed(&self) -> bool {
+        self.permitted_subclass_indices().next().is_some()
+    }
+
+    fn permitted_subclass_indices(&self) -> impl Iterator<Item = &u16> {
+        self.attributes
+            .iter()
+            .filter_map(|attr| {
+                if let Attribute::PermittedSubclasses(classes) = attr {
+                    Some(classes)
+                } else {
+                    None
+                }
+            })
+            .flatten()
+    }
+
+    /// Resolves the name and descriptor of each component of a record class.
+    pub fn record_components(&self) -> Result<Vec<(&str, &str)>, ConstantPoolError> {
+        self.attributes
+            .iter()
+            .filter_map(|attr| {
+                if let Attribute::Record(components) = attr {
+                    Some(components)
+                } else {
+                    None
+                }
+            })
+            .flatten()
+            .map(|component| {
+                Ok((
+                    self.constant_pool.utf8(component.name_index)?,
+                    self.constant_pool.utf8(component.descriptor_index)?,
+                ))
+            })
+            .collect()
+    }
+
+    /// Resolves the name of the class hosting the nest this class belongs
+    /// to, if it is a nest member.
+    pub fn nest_host(&self) -> Result<Option<&str>, ConstantPoolError> {
+        self.attributes
+            .iter()
+            .find_map(|attr| {
+                if let Attribute::NestHost(index) = attr {
+                    Some(self.constant_pool.class(*index))
+                } else {
+                    None
+                }
+            })
+            .transpose()
+    }
+
+    /// Resolves the names of the members of the nest this class hosts.
+    pub fn nest_members(&self) -> Result<Vec<&str>, ConstantPoolError> {
+        self.attributes
+            .iter()
+            .filter_map(|attr| {
+                if let Attribute::NestMembers(members) = attr {
+                    Some(members)
+                } else {
+                    None
+                }
+            })
+            .flatten()
+            .map(|&index| self.constant_pool.class(index))
+            .collect()
+    }
+
+    /// Resolves the names of every package in the module, like `demo/api`.
+    pub fn module_packages(&self) -> Result<Vec<&str>, ConstantPoolError> {
+        self.attributes
+            .iter()
+            .filter_map(|attr| {
+                if let Attribute::ModulePackages(packages) = attr {
+                    Some(packages)
+                } else {
+                    None
+                }
+            })
+            .flatten()
+            .map(|&index| self.constant_pool.package(index))
+            .collect()
+    }
+
+    /// Resolves the name of the module's main class, if it has one.
+    pub fn module_main_class(&self) -> Result<Option<&str>, ConstantPoolError> {
+        self.attributes
+            .iter()
+            .find_map(|attr| {
+                if let Attribute::ModuleMainClass(index) = attr {
+                    Some(self.constant_pool.class(*index))
+                } else {
+                    None
+                }
+            })
+            .transpose()
+    }
+
+    /// Returns the name of the source file this class was compiled from, if
+    /// it was compiled with debug information.
+    pub fn source_file(&self) -> Option<&str> {
+        self.attributes.iter().find_map(|attr| {
+            if let Attribute::SourceFile(index) = attr {
+                self.constant_pool.utf8(*index).ok()
+            } else {
+                None
+            }
+        })
+    }
+}
+
+pub fn parse_class(input: &[u8]) -> IResult<&[u8], ClassFile, ParsingError> {
+    let (input, _) = tag([0xCA, 0xFE, 0xBA, 0xBE])(input)?;
+    let (input, version) = version(input)?;
+    let (input, constant_pool) = constant_pool(input)?;
+
+    let mut parser = map(
+        tuple((
+            map(be_u16, ClassAccessFlags::from_bits_truncate),
+            be_u16,
+            be_u16,
+            length_count(be_u16, be_u16),
+            length_count(be_u16, field(constant_pool.clone())),
+            length_count(be_u16, method(constant_pool.clone())),
+            length_count(be_u16, attribute(constant_pool.clone())),
+        )),
+        |(access_flags, this_class, super_class, interfaces, fields, methods, attributes)| {
+            ClassFile {
+                version,
+                constant_pool: constant_pool.clone(),
+                access_flags,
+                this_class,
+                super_class,
+                interfaces,
+                fields,
+                methods,
+                attributes,
+            }
+        },
+    );
+    let (remaining, classfile) = parser(input)?;
+
+    // A class either hosts a nest or belongs to one, never both.
+    let has = |f: fn(&Attribute) -> bool| classfile.attributes.iter().any(f);
+    if has(|attr| matches!(attr, Attribute::NestHost(_)))
+        && has(|attr| matches!(attr, Attribute::NestMembers(_)))
+    {
+        return Err(nom::Err::Failure(ParsingError::new(
+            remaining,
+            ParsingErrorKind::ConflictingAttributes("NestHost", "NestMembers"),
+        )));
+    }
+
+    Ok((remaining, classfile))
+}
+
+/// Parses a whole class file like [`parse_class`], but reports failures with
+/// the offset in the file where they occurred.
+pub fn parse_class_checked(input: &[u8]) -> Result<ClassFile, ClassFileError> {
+    match parse_class(input) {
+        Ok((_, classfile)) => Ok(classfile),
+        Err(nom::Err::Error(err) | nom::Err::Failure(err)) => Err(ClassFileError::Malformed {
+            offset: err.offset(input),
+            kind: err.kind,
+        }),
+        Err(nom::Err::Incomplete(_)) => Err(ClassFileError::Malformed {
+            offset: input.len(),
+            kind: ParsingErrorKind::Nom(nom::error::ErrorKind::Eof),
+        }),
+    }
+}
+
+/// Parses a whole class file like [`parse_class_checked`], then checks the
+/// references between its constants with [`ConstantPool::validate`].
+pub fn parse_class_validated(input: &[u8]) -> Result<ClassFile, ClassFileError> {
+    let classfile = parse_class_checked(input)?;
+    let violations = classfile.constant_pool.validate();
+    if !violations.is_empty() {
+        return Err(ClassFileError::InvalidConstantPool(violations));
+    }
+    Ok(classfile)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        pretty::{self, Names},
+        signature::ClassSignature,
+        ExportsFlags, ModuleFlags, RequiresFlags,
+    };
+    use std::io::Cursor;
+
+    const SAMPLE: &[u8] = include_bytes!("../tests/fixtures/Sample.class");
+    const BOX: &[u8] = include_bytes!("../tests/fixtures/Box.class");
+    const CONSTANTS: &[u8] = include_bytes!("../tests/fixtures/Constants.class");
+    const MEMBERS: &[u8] = include_bytes!("../tests/fixtures/Members.class");
+    const INNER: &[u8] = include_bytes!("../tests/fixtures/Members$Inner.class");
+    const OPT: &[u8] = include_bytes!("../tests/fixtures/Members$Opt.class");
+    const MODULE_INFO: &[u8] = include_bytes!("../tests/fixtures/demo/module-info.class");
+    const POINT: &[u8] = include_bytes!("../tests/fixtures/Point.class");
+    const SHAPE: &[u8] = include_bytes!("../tests/fixtures/Shape.class");
+
+    #[test]
+    fn utf8_constants_are_modified_utf8() {
+        let pool = [
+            0, 3, //
+            1, 0, 4, b'a', 0xc0, 0x80, b'b', //
+            1, 0, 6, 0xed, 0xa0, 0xbd, 0xed, 0xb8, 0x80,
+        ];
+        let (_, pool) = constant_pool(&pool).unwrap();
+        assert_eq!(pool.utf8(1), Ok("a\0b"));
+        assert_eq!(pool.utf8(2), Ok("\u{1f600}"));
+    }
+
+    #[test]
+    fn malformed_utf8_constant_reports_its_index() {
+        let pool = [0, 3, 1, 0, 1, b'a', 1, 0, 1, 0xff];
+        match constant_pool(&pool) {
+            Err(nom::Err::Failure(err)) => {
+                assert_eq!(err.kind, ParsingErrorKind::MalformedUtf8 { index: 2 });
+                assert_eq!(err.offset(&pool), 9);
+            }
+            other => panic!("expected a failure, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn wide_constant_must_fit_in_the_pool() {
+        let mut class = vec![0xca, 0xfe, 0xba, 0xbe, 0, 0, 0, 61, 0xff, 0xff];
+        for _ in 0..65533 {
+            class.extend_from_slice(&[3, 0, 0, 0, 0]);
+        }
+        let long = class.len();
+        class.extend_from_slice(&[5, 0, 0, 0, 0, 0, 0, 0, 1]);
+
+        match parse_class_checked(&class) {
+            Err(ClassFileError::Malformed { offset, kind }) => {
+                assert_eq!(offset, long);
+                assert_eq!(kind, ParsingErrorKind::WideConstantAtEnd { index: 65534 });
+            }
+            other => panic!("expected a malformed class, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn first_instruction_maps_to_its_line() {
+        let class = parse_class_checked(SAMPLE).unwrap();
+        let one = class.get_method("one", "()I").unwrap();
+        assert_eq!(one.line_number(0), Some(6));
+        let sum = class.get_method("sum", "(I)I").unwrap();
+        assert_eq!(sum.line_number(0), Some(18));
+    }
+
+    #[test]
+    fn code_nested_in_code_is_rejected() {
+        let (_, pool) = constant_pool(&[0, 2, 1, 0, 4, b'C', b'o', b'd', b'e']).unwrap();
+        let inner = [0, 1, 0, 0, 0, 12, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        let mut outer = vec![0, 1, 0, 0, 0, 30, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1];
+        outer.extend_from_slice(&inner);
+
+        match attribute(pool.clone())(&inner) {
+            Ok((_, Attribute::Code { code, .. })) => assert!(code.is_empty()),
+            other => panic!("expected a Code attribute, got {other:?}"),
+        }
+        match attribute(pool)(&outer) {
+            Err(nom::Err::Failure(err)) => {
+                assert_eq!(
+                    err.kind,
+                    ParsingErrorKind::NestedAttribute { name_index: 1 }
+                )
+            }
+            other => panic!("expected a failure, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn code_keeps_its_exception_table() {
+        let class = parse_class_checked(SAMPLE).unwrap();
+        let divide = class.get_method("divide", "(II)I").unwrap();
+        match divide.exception_table() {
+            [entry] => assert_eq!(
+                class.constant_pool.class(entry.catch_type),
+                Ok("java/lang/ArithmeticException")
+            ),
+            table => panic!("expected one handler, got {table:?}"),
+        }
+    }
+
+    #[test]
+    fn truncated_constant_pool_reports_its_offset() {
+        // The first constant is a Methodref at offset 10, which is cut off
+        // halfway through its second operand.
+        match parse_class_checked(&SAMPLE[..14]) {
+            Err(ClassFileError::Malformed { offset, kind }) => {
+                assert_eq!(offset, 13);
+                assert_eq!(kind, ParsingErrorKind::Nom(nom::error::ErrorKind::Eof));
+            }
+            other => panic!("expected a malformed class, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn from_reader_parses_a_cursor() {
+        let class = ClassFile::from_reader(Cursor::new(SAMPLE.to_vec())).unwrap();
+        assert_eq!(class.name(), Ok("Sample"));
+
+        let truncated = Cursor::new(SAMPLE[..14].to_vec());
+        assert!(matches!(
+            ClassFile::from_reader(truncated),
+            Err(ClassFileError::Malformed { offset: 13, .. })
+        ));
+    }
+
+    #[test]
+    fn exceptions_and_signatures_round_trip() {
+        let parsed = parse_class_checked(SAMPLE).unwrap();
+        let rewritten = parse_class_checked(&parsed.to_bytes().unwrap()).unwrap();
+        for class in [parsed, rewritten] {
+            let read = class.get_method("read", "()V").unwrap();
+            assert_eq!(
+                read.declared_exceptions(&class.constant_pool),
+                Ok(vec!["java/io/IOException"])
+            );
+            assert_eq!(
+                class.signature(),
+                Some("<T::Ljava/lang/Comparable<TT;>;>Ljava/lang/Object;")
+            );
+            let items = class.get_method("items", "()Ljava/util/List;").unwrap();
+            assert_eq!(
+                items.signature(&class.constant_pool),
+                Some("()Ljava/util/List<TT;>;")
+            );
+        }
+    }
+
+    #[test]
+    fn line_numbers_from_an_unsorted_table() {
+        let entry = |start_pc, line_number| LineNumberTableEntry {
+            start_pc,
+            line_number,
+        };
+        let method = Method {
+            access_flags: MethodAccessFlags::STATIC,
+            name_index: 1,
+            descriptor_index: 2,
+            attributes: vec![Attribute::Code {
+                max_stack: 0,
+                max_locals: 0,
+                code: Vec::new(),
+                exceptions: Vec::new(),
+                attributes: vec![Attribute::LineNumberTable(vec![
+                    entry(10, 30),
+                    entry(2, 10),
+                    entry(6, 20),
+                ])],
+            }],
+        };
+        assert_eq!(method.line_number_for_pc(0), None);
+        assert_eq!(method.line_number_for_pc(1), None);
+        assert_eq!(method.line_number_for_pc(2), Some(10));
+        assert_eq!(method.line_number_for_pc(7), Some(20));
+        assert_eq!(method.line_number_for_pc(12), Some(30));
+    }
+
+    #[test]
+    fn loop_branches_resolve_to_instructions() {
+        let class = parse_class_checked(SAMPLE).unwrap();
+        let code = class.get_method("sum", "(I)I").unwrap().code().unwrap();
+        let at = |offset: u32| &code.iter().find(|(at, _)| *at == offset).unwrap().1;
+        let target = |offset: u32| {
+            let target = (offset as i32 + at(offset).branches()[0]) as u32;
+            (target, at(target))
+        };
+
+        let (exit, inst) = target(6);
+        assert_eq!(exit, 19);
+        assert!(matches!(inst, Instruction::Iload(1)));
+        let (start, inst) = target(16);
+        assert_eq!(start, 4);
+        assert!(matches!(inst, Instruction::Iload(2)));
+    }
+
+    #[test]
+    fn indices_outside_the_pool_are_invalid() {
+        let pool = ConstantPool {
+            items: vec![
+                Constant::Integer(1),
+                Constant::Class(0),
+                Constant::String(4),
+            ],
+        };
+        assert!(matches!(
+            pool.at(0),
+            Err(ConstantPoolError::InvalidIndex(0))
+        ));
+        assert!(matches!(pool.at(1), Ok(Constant::Integer(1))));
+        assert!(matches!(
+            pool.at(4),
+            Err(ConstantPoolError::InvalidIndex(4))
+        ));
+        assert_eq!(pool.integer(4), Err(ConstantPoolError::InvalidIndex(4)));
+        assert_eq!(
+            pool.validate(),
+            [
+                ConstantPoolViolation::WrongKind {
+                    index: 2,
+                    target: 0,
+                    expected: ConstantKind::Utf8,
+                },
+                ConstantPoolViolation::WrongKind {
+                    index: 3,
+                    target: 4,
+                    expected: ConstantKind::Utf8,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn method_handle_reference_kinds_are_validated() {
+        let handle = |reference_kind| Constant::MethodHandle {
+            reference_kind,
+            reference_index: 2,
+        };
+        let pool = ConstantPool {
+            items: vec![handle(0), Constant::Integer(1), handle(6), handle(10)],
+        };
+        assert_eq!(
+            pool.validate(),
+            [
+                ConstantPoolViolation::InvalidReferenceKind {
+                    index: 1,
+                    reference_kind: 0,
+                },
+                ConstantPoolViolation::InvalidReferenceKind {
+                    index: 4,
+                    reference_kind: 10,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn mutated_u16s_never_panic() {
+        let mut class = SAMPLE.to_vec();
+        for offset in 0..class.len() - 1 {
+            for value in [0x0000u16, 0x0001, 0x00ff, 0x7fff, 0xffff] {
+                let original = [class[offset], class[offset + 1]];
+                class[offset..offset + 2].copy_from_slice(&value.to_be_bytes());
+                if let Ok(parsed) = parse_class_checked(&class) {
+                    parsed.constant_pool.validate();
+                    for method in &parsed.methods {
+                        let _ = crate::verify::check_maxs(method, &parsed.constant_pool);
+                        let _ = crate::verify::check_branches(method);
+                        let _ = crate::cfg::basic_blocks(method);
+                    }
+                    parsed.disassemble_full();
+                    let _ = parsed.to_bytes();
+                }
+                class[offset..offset + 2].copy_from_slice(&original);
+            }
+        }
+    }
+
+    #[test]
+    fn truncated_classes_never_panic() {
+        for len in 0..SAMPLE.len() {
+            assert!(parse_class_checked(&SAMPLE[..len]).is_err());
+        }
+    }
+
+    #[test]
+    fn attribute_length_past_the_end_is_rejected() {
+        // The class ends with its SourceFile attribute: a name index, a
+        // length of 2, and the index of the file name.
+        let mut class = SAMPLE.to_vec();
+        let length = class.len() - 6;
+        assert_eq!(class[length..length + 4], [0, 0, 0, 2]);
+        for value in [u32::MAX - 1, u32::MAX] {
+            class[length..length + 4].copy_from_slice(&value.to_be_bytes());
+            assert!(matches!(
+                parse_class_checked(&class),
+                Err(ClassFileError::Malformed { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn validated_parsing_reports_every_bad_reference() {
+        let mut class = parse_class_checked(SAMPLE).unwrap();
+        let pool = &mut class.constant_pool.items;
+        let len = pool.len() as u16;
+        let this_class = class.this_class as usize - 1;
+        let integer = pool.len() as u16 + 1;
+        pool.push(Constant::Integer(7));
+        // One reference dangles past the end, another points at a constant
+        // of the wrong kind.
+        pool.push(Constant::String(len + 10));
+        pool[this_class] = Constant::Class(integer);
+        let bytes = class.to_bytes().unwrap();
+
+        assert!(parse_class_checked(&bytes).is_ok());
+        match parse_class_validated(&bytes) {
+            Err(ClassFileError::InvalidConstantPool(violations)) => assert_eq!(
+                violations,
+                [
+                    ConstantPoolViolation::WrongKind {
+                        index: class.this_class,
+                        target: integer,
+                        expected: ConstantKind::Utf8,
+                    },
+                    ConstantPoolViolation::WrongKind {
+                        index: integer + 1,
+                        target: len + 10,
+                        expected: ConstantKind::Utf8,
+                    },
+                ]
+            ),
+            other => panic!("expected an invalid constant pool, got {other:?}"),
+        }
+        assert!(parse_class_validated(SAMPLE).is_ok());
+    }
+
+    #[test]
+    fn source_file_resolves_through_the_pool() {
+        let mut class = parse_class_checked(SAMPLE).unwrap();
+        assert_eq!(class.source_file(), Some("Sample.java"));
+
+        class
+            .attributes
+            .retain(|attr| !matches!(attr, Attribute::SourceFile(_)));
+        assert_eq!(class.source_file(), None);
+    }
+
+    #[test]
+    fn string_with_a_dangling_inner_index_is_an_error() {
+        let pool = ConstantPool {
+            items: vec![
+                Constant::Utf8("hello".to_owned()),
+                Constant::String(1),
+                Constant::String(0),
+                Constant::String(9),
+                Constant::String(2),
+            ],
+        };
+        assert_eq!(pool.string(2), Ok("hello"));
+        assert_eq!(pool.string(3), Err(ConstantPoolError::InvalidIndex(0)));
+        assert_eq!(pool.string(4), Err(ConstantPoolError::InvalidIndex(9)));
+        assert_eq!(
+            pool.string(5),
+            Err(ConstantPoolError::UnexpectedKind {
+                index: 2,
+                expected: ConstantKind::Utf8,
+                found: ConstantKind::String,
+            })
+        );
+    }
+
+    #[test]
+    fn every_declared_exception_is_listed() {
+        let class = parse_class_checked(MEMBERS).unwrap();
+        let both = class.get_method("both", "()V").unwrap();
+        assert_eq!(
+            both.declared_exceptions(&class.constant_pool),
+            Ok(vec![
+                "java/io/IOException",
+                "java/lang/InterruptedException"
+            ])
+        );
+        let old = class.get_method("old", "()V").unwrap();
+        assert_eq!(old.declared_exceptions(&class.constant_pool), Ok(vec![]));
+    }
+
+    #[test]
+    fn inner_classes_resolve_their_names() {
+        let class = parse_class_checked(MEMBERS).unwrap();
+        let inner = class.inner_classes().unwrap();
+        assert_eq!(inner.len(), 5);
+        assert_eq!(
+            inner[0],
+            InnerClass {
+                class: "Members$Inner",
+                outer_class: Some("Members"),
+                name: Some("Inner"),
+                access_flags: InnerClassAccessFlags::empty(),
+            }
+        );
+        assert_eq!(inner[1].class, "Members$Opt");
+        assert!(inner[1].access_flags.contains(
+            InnerClassAccessFlags::STATIC
+                | InnerClassAccessFlags::INTERFACE
+                | InnerClassAccessFlags::ANNOTATION
+        ));
+        // The lambda brings in the lookup class it is bootstrapped with.
+        assert_eq!(inner[4].class, "java/lang/invoke/MethodHandles$Lookup");
+        assert_eq!(inner[4].outer_class, Some("java/lang/invoke/MethodHandles"));
+    }
+
+    #[test]
+    fn deprecated_and_synthetic_methods() {
+        let class = parse_class_checked(MEMBERS).unwrap();
+        let pool = &class.constant_pool;
+        let old = class.get_method("old", "()V").unwrap();
+        assert!(old.is_deprecated());
+        assert_eq!(
+            pool.utf8(old.annotations()[0].type_index),
+            Ok("Ljava/lang/Deprecated;")
+        );
+        assert!(!class.get_method("both", "()V").unwrap().is_deprecated());
+
+        // javac compiles the lambda's body into a synthetic method.
+        let body = class.get_method("lambda$lambda$0", "()V").unwrap();
+        assert!(body.is_synthetic());
+        assert!(!old.is_synthetic());
+        assert!(!class.is_deprecated() && !class.is_synthetic());
+    }
+
+    #[test]
+    fn source_debug_extension_holds_an_smap() {
+        // No Kotlin compiler is at hand, so the SMAP it would emit is added
+        // to a javac-compiled class instead.
+        let smap =
+            "SMAP\nGröße.kt\nKotlin\n*S Kotlin\n*F\n+ 1 Größe.kt\nGrößeKt\n*L\n1#1,5:1\n*E\n";
+        let mut class = parse_class_checked(SAMPLE).unwrap();
+        let mut pool = crate::ConstantPoolBuilder::from(class.constant_pool.clone());
+        pool.add_utf8("SourceDebugExtension").unwrap();
+        class.constant_pool = pool.build();
+        class
+            .attributes
+            .insert(0, Attribute::SourceDebugExtension(smap.to_owned()));
+        let mut bytes = class.to_bytes().unwrap();
+
+        // The SourceFile attribute after it is still read from the right
+        // place.
+        let parsed = parse_class_checked(&bytes).unwrap();
+        assert!(matches!(
+            &parsed.attributes[0],
+            Attribute::SourceDebugExtension(debug) if debug == smap
+        ));
+        assert_eq!(parsed.source_file(), Some("Sample.java"));
+
+        let start = bytes
+            .windows(4)
+            .position(|window| window == b"SMAP")
+            .unwrap();
+        bytes[start] = 0xff;
+        match parse_class_checked(&bytes) {
+            Err(ClassFileError::Malformed { offset, kind }) => {
+                assert_eq!(offset, start);
+                assert!(matches!(
+                    kind,
+                    ParsingErrorKind::MalformedUtf8Attribute { .. }
+                ));
+            }
+            other => panic!("expected a malformed class, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn class_retention_annotations_are_invisible() {
+        let class = parse_class_checked(MEMBERS).unwrap();
+        let marked = class.get_method("marked", "()V").unwrap();
+        assert!(marked
+            .attributes
+            .iter()
+            .any(|attr| matches!(attr, Attribute::RuntimeInvisibleAnnotations(_))));
+        assert!(!marked
+            .attributes
+            .iter()
+            .any(|attr| matches!(attr, Attribute::RuntimeVisibleAnnotations(_))));
+
+        let annotations = marked.annotations();
+        assert_eq!(annotations.len(), 1);
+        assert_eq!(
+            class.constant_pool.utf8(annotations[0].type_index),
+            Ok("LMembers$Marker;")
+        );
+        assert!(annotations[0].elements.is_empty());
+    }
+
+    #[test]
+    fn parameter_annotations_line_up_with_their_parameters() {
+        let class = parse_class_checked(MEMBERS).unwrap();
+        let pool = &class.constant_pool;
+        let named = class.get_method("named", "(ILjava/lang/String;)V").unwrap();
+        assert!(named.parameter_annotations(0).is_empty());
+
+        let second = named.parameter_annotations(1);
+        assert_eq!(second.len(), 1);
+        assert_eq!(pool.utf8(second[0].type_index), Ok("LMembers$Named;"));
+        match &second[0].elements[..] {
+            [(name, ElementValue::String(value))] => {
+                assert_eq!(pool.utf8(*name), Ok("value"));
+                assert_eq!(pool.utf8(*value), Ok("second"));
+            }
+            elements => panic!("unexpected elements {elements:?}"),
+        }
+        assert!(named.parameter_annotations(2).is_empty());
+    }
+
+    #[test]
+    fn annotation_elements_have_their_defaults() {
+        let class = parse_class_checked(OPT).unwrap();
+        let pool = &class.constant_pool;
+        assert!(class.access_flags.contains(ClassAccessFlags::ANNOTATION));
+
+        let retries = class.get_method("retries", "()I").unwrap();
+        match retries.annotation_default() {
+            Some(&ElementValue::Int(index)) => assert_eq!(pool.integer(index), Ok(3)),
+            other => panic!("unexpected default {other:?}"),
+        }
+        let name = class.get_method("name", "()Ljava/lang/String;").unwrap();
+        match name.annotation_default() {
+            Some(&ElementValue::String(index)) => assert_eq!(pool.utf8(index), Ok("x")),
+            other => panic!("unexpected default {other:?}"),
+        }
+    }
+
+    #[test]
+    fn lambda_is_bootstrapped_by_the_metafactory() {
+        let class = parse_class_checked(MEMBERS).unwrap();
+        let pool = &class.constant_pool;
+        let lambda = class
+            .get_method("lambda", "()Ljava/lang/Runnable;")
+            .unwrap();
+        let call_site = match lambda.code().unwrap()[0] {
+            (_, Instruction::Invokedynamic(index)) => index,
+            (_, ref inst) => panic!("expected invokedynamic, got {inst:?}"),
+        };
+        let bootstrap_index = match *pool.at(call_site).unwrap() {
+            Constant::InvokeDynamic {
+                bootstrap_method_attr_index,
+                ..
+            } => bootstrap_method_attr_index,
+            ref constant => panic!("expected InvokeDynamic, got {constant:?}"),
+        };
+
+        let bootstrap = class.bootstrap_method(bootstrap_index).unwrap().unwrap();
+        // Both handles are REF_invokeStatic.
+        assert_eq!(bootstrap.method.reference_kind, 6);
+        assert_eq!(
+            bootstrap.method.member.class,
+            "java/lang/invoke/LambdaMetafactory"
+        );
+        assert_eq!(bootstrap.method.member.name, "metafactory");
+        match bootstrap.arguments[..] {
+            [&Constant::MethodType(erased), &Constant::MethodHandle {
+                reference_kind: 6,
+                reference_index,
+            }, &Constant::MethodType(instantiated)] => {
+                assert_eq!(pool.utf8(erased), Ok("()V"));
+                assert_eq!(
+                    pool.method_ref(reference_index).unwrap().name,
+                    "lambda$lambda$0"
+                );
+                assert_eq!(pool.utf8(instantiated), Ok("()V"));
+            }
+            ref arguments => panic!("unexpected arguments {arguments:?}"),
+        }
+        assert!(class
+            .bootstrap_method(bootstrap_index + 1)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn module_declaration_is_decoded() {
+        let class = parse_class_checked(MODULE_INFO).unwrap();
+        let pool = &class.constant_pool;
+        assert!(class.access_flags.contains(ClassAccessFlags::MODULE));
+
+        let module = class.module().unwrap();
+        assert_eq!(pool.module(module.name_index), Ok("demo"));
+        assert_eq!(module.flags, ModuleFlags::empty());
+        assert_eq!(module.version_index, 0);
+
+        let requires: Vec<_> = module
+            .requires
+            .iter()
+            .map(|requires| {
+                (
+                    pool.module(requires.requires_index).unwrap(),
+                    requires.flags,
+                )
+            })
+            .collect();
+        assert_eq!(
+            requires,
+            [
+                ("java.base", RequiresFlags::MANDATED),
+                ("java.sql", RequiresFlags::empty()),
+            ]
+        );
+
+        assert_eq!(module.exports.len(), 1);
+        assert_eq!(
+            pool.package(module.exports[0].exports_index),
+            Ok("demo/api")
+        );
+        assert_eq!(module.exports[0].flags, ExportsFlags::empty());
+        assert!(module.exports[0].exports_to.is_empty());
+        assert!(module.opens.is_empty() && module.uses.is_empty() && module.provides.is_empty());
+    }
+
+    #[test]
+    fn module_packages_and_main_class_resolve() {
+        let class = parse_class_checked(MODULE_INFO).unwrap();
+        assert_eq!(class.module_packages(), Ok(vec!["demo", "demo/api"]));
+        assert_eq!(class.module_main_class(), Ok(Some("demo/Main")));
+
+        let class = parse_class_checked(SAMPLE).unwrap();
+        assert_eq!(class.module_packages(), Ok(vec![]));
+        assert_eq!(class.module_main_class(), Ok(None));
+    }
+
+    #[test]
+    fn nest_host_and_members_resolve() {
+        let host = parse_class_checked(MEMBERS).unwrap();
+        assert_eq!(host.nest_host(), Ok(None));
+        assert_eq!(
+            host.nest_members(),
+            Ok(vec![
+                "Members$Inner",
+                "Members$Opt",
+                "Members$Named",
+                "Members$Marker"
+            ])
+        );
+
+        let inner = parse_class_checked(INNER).unwrap();
+        assert_eq!(inner.nest_host(), Ok(Some("Members")));
+        assert_eq!(inner.nest_members(), Ok(vec![]));
+    }
+
+    #[test]
+    fn nest_host_and_members_conflict() {
+        let mut class = parse_class_checked(INNER).unwrap();
+        let mut pool = crate::ConstantPoolBuilder::from(class.constant_pool.clone());
+        pool.add_utf8("NestMembers").unwrap();
+        class.constant_pool = pool.build();
+        class
+            .attributes
+            .push(Attribute::NestMembers(vec![class.this_class]));
+
+        match parse_class_checked(&class.to_bytes().unwrap()) {
+            Err(ClassFileError::Malformed { kind, .. }) => assert_eq!(
+                kind,
+                ParsingErrorKind::ConflictingAttributes("NestHost", "NestMembers")
+            ),
+            other => panic!("expected a malformed class, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn record_components_resolve() {
+        let class = parse_class_checked(POINT).unwrap();
+        assert_eq!(class.super_class_name(), Ok(Some("java/lang/Record")));
+        assert_eq!(
+            class.record_components(),
+            Ok(vec![("x", "I"), ("y", "I"), ("labels", "Ljava/util/List;")])
+        );
+        let components = class
+            .attributes
+            .iter()
+            .find_map(|attr| match attr {
+                Attribute::Record(components) => Some(components),
+                _ => None,
+            })
+            .unwrap();
+        assert!(components[0].attributes.is_empty());
+        assert_eq!(
+            components[2].signature(&class.constant_pool),
+            Some("Ljava/util/List<Ljava/lang/String;>;")
+        );
+        // The attributes after Record are still read from the right place.
+        assert_eq!(class.source_file(), Some("Point.java"));
+        assert!(class.get_method("x", "()I").unwrap().has_code());
+
+        let class = parse_class_checked(SAMPLE).unwrap();
+        assert_eq!(class.record_components(), Ok(vec![]));
+    }
+
+    #[test]
+    fn permitted_subclasses_keep_their_order() {
+        let class = parse_class_checked(SHAPE).unwrap();
+        assert!(class.is_sealed());
+        assert_eq!(class.permitted_subclasses(), Ok(vec!["Circle", "Square"]));
+
+        let class = parse_class_checked(SAMPLE).unwrap();
+        assert!(!class.is_sealed());
+        assert_eq!(class.permitted_subclasses(), Ok(vec![]));
+    }
+
+    #[test]
+    fn unknown_attributes_keep_their_bytes() {
+        let data = vec![0xca, 0xfe, 0, 1, 2, 0xff];
+        let mut class = parse_class_checked(SAMPLE).unwrap();
+        let mut pool = crate::ConstantPoolBuilder::from(class.constant_pool.clone());
+        pool.add_utf8("com.example.Vendor").unwrap();
+        class.constant_pool = pool.build();
+        class.attributes.insert(
+            0,
+            Attribute::Unknown {
+                name: "com.example.Vendor".to_owned(),
+                data: data.clone(),
+            },
+        );
+        let bytes = class.to_bytes().unwrap();
+
+        let parsed = parse_class_checked(&bytes).unwrap();
+        match &parsed.attributes[0] {
+            Attribute::Unknown { name, data: parsed } => {
+                assert_eq!(name, "com.example.Vendor");
+                assert_eq!(*parsed, data);
+            }
+            attr => panic!("expected an unknown attribute, got {attr:?}"),
+        }
+        assert_eq!(parsed.source_file(), Some("Sample.java"));
+        assert_eq!(parsed.to_bytes().unwrap(), bytes);
+        assert!(parsed.disassemble_full().contains(
+            "com.example.Vendor: length = 0x6 (unknown attribute)\n   ca fe 00 01 02 ff\n"
+        ));
+    }
+
+    #[test]
+    fn bounded_type_parameter_signatures() {
+        let class = parse_class_checked(BOX).unwrap();
+        let pool = &class.constant_pool;
+        let signature = class.signature().unwrap();
+        assert_eq!(signature, "<T:Ljava/lang/Number;>Ljava/lang/Object;");
+        let parsed = ClassSignature::parse(signature).unwrap();
+        assert_eq!(parsed.type_parameters[0].name, "T");
+        assert_eq!(
+            parsed.type_parameters[0]
+                .class_bound
+                .as_ref()
+                .map(|bound| pretty::type_signature(bound, Names::Qualified)),
+            Some("java.lang.Number".to_owned())
+        );
+
+        let value = class.get_field("value", "Ljava/lang/Number;").unwrap();
+        assert_eq!(value.signature(pool), Some("TT;"));
+        let get = class
+            .get_method("get", "(Ljava/lang/Number;)Ljava/lang/Number;")
+            .unwrap();
+        assert_eq!(get.signature(pool), Some("<U:TT;>(TU;)TU;"));
+    }
+
+    #[test]
+    fn constant_values_of_every_type() {
+        let class = parse_class_checked(CONSTANTS).unwrap();
+        let pool = &class.constant_pool;
+        let value = |name, descriptor| {
+            class
+                .get_field(name, descriptor)
+                .unwrap()
+                .constant_value(pool)
+        };
+
+        assert_eq!(value("BYTE", "B"), Ok(Some(ConstantValue::Int(-8))));
+        assert_eq!(value("CHAR", "C"), Ok(Some(ConstantValue::Int('c' as i32))));
+        assert_eq!(value("SHORT", "S"), Ok(Some(ConstantValue::Int(1600))));
+        assert_eq!(value("BOOLEAN", "Z"), Ok(Some(ConstantValue::Int(1))));
+        assert_eq!(value("INT", "I"), Ok(Some(ConstantValue::Int(123456))));
+        assert_eq!(
+            value("LONG", "J"),
+            Ok(Some(ConstantValue::Long(-9_000_000_000)))
+        );
+        assert_eq!(value("FLOAT", "F"), Ok(Some(ConstantValue::Float(1.5))));
+        assert_eq!(value("DOUBLE", "D"), Ok(Some(ConstantValue::Double(0.25))));
+        assert_eq!(
+            value("STRING", "Ljava/lang/String;"),
+            Ok(Some(ConstantValue::String("constant")))
+        );
+        // Neither a null nor a non-final field has a constant value.
+        assert_eq!(value("OBJECT", "Ljava/lang/Object;"), Ok(None));
+        assert_eq!(value("counter", "I"), Ok(None));
+    }
+
+    #[test]
+    fn constant_value_of_the_wrong_kind_is_an_error() {
+        let mut class = parse_class_checked(CONSTANTS).unwrap();
+        let float = class.get_field("FLOAT", "F").unwrap().attributes.clone();
+        let int = class
+            .fields
+            .iter_mut()
+            .find(|field| class.constant_pool.utf8(field.name_index) == Ok("INT"))
+            .unwrap();
+        int.attributes = float;
+
+        let float_index = match int.attributes[..] {
+            [Attribute::ConstantValue(index)] => index,
+            ref attributes => panic!("unexpected attributes {attributes:?}"),
+        };
+        assert_eq!(
+            int.constant_value(&class.constant_pool),
+            Err(ConstantPoolError::UnexpectedKind {
+                index: float_index,
+                expected: ConstantKind::Integer,
+                found: ConstantKind::Float,
+            })
+        );
+    }
+
+    #[test]
+    fn interface_and_dynamic_calls_decode_from_javac_output() {
+        let class = parse_class_checked(MEMBERS).unwrap();
+        let pool = &class.constant_pool;
+
+        let size = class.get_method("size", "(Ljava/util/List;)I").unwrap();
+        match size.code().unwrap() {
+            [(0, Instruction::Aload(1)), (1, Instruction::Invokeinterface(index, 1)), (6, Instruction::Ireturn)] =>
+            {
+                let method = pool.interface_method_ref(*index).unwrap();
+                assert_eq!(
+                    (method.class, method.name, method.descriptor),
+                    ("java/util/List", "size", "()I")
+                );
+            }
+            code => panic!("unexpected code {code:?}"),
+        }
+
+        let lambda = class
+            .get_method("lambda", "()Ljava/lang/Runnable;")
+            .unwrap();
+        match lambda.code().unwrap() {
+            [(0, Instruction::Invokedynamic(index)), (5, Instruction::Areturn)] => {
+                let nametype_index = match *pool.at(*index).unwrap() {
+                    Constant::InvokeDynamic { nametype_index, .. } => nametype_index,
+                    ref constant => panic!("expected InvokeDynamic, got {constant:?}"),
+                };
+                assert_eq!(
+                    pool.name_and_type(nametype_index),
+                    Ok(("run", "()Ljava/lang/Runnable;"))
+                );
+            }
+            code => panic!("unexpected code {code:?}"),
+        }
+    }
+
+    #[test]
+    fn multianewarray_decodes_its_dimensions() {
+        let class = parse_class_checked(MEMBERS).unwrap();
+        let pool = &class.constant_pool;
+        let cube = class.get_method("cube", "()[[[I").unwrap();
+        let index = match cube.code().unwrap() {
+            [(0, Instruction::Iconst2), (1, Instruction::Iconst3), (2, Instruction::Iconst4), (3, Instruction::Multianewarray(index, 3)), (7, Instruction::Areturn)] => {
+                *index
+            }
+            code => panic!("unexpected code {code:?}"),
+        };
+        assert_eq!(pool.class(index), Ok("[[[I"));
+        assert!(cube
+            .disassemble(pool)
+            .contains(&format!("3: multianewarray #{index},  3 ")));
+    }
+
+    #[test]
+    fn exception_table_and_nested_attributes_match_javap() {
+        let class = parse_class_checked(SAMPLE).unwrap();
+        let divide = class.get_method("divide", "(II)I").unwrap();
+        let (exceptions, attributes) = match &divide.attributes[..] {
+            [Attribute::Code {
+                exceptions,
+                attributes,
+                ..
+            }] => (exceptions, attributes),
+            attributes => panic!("expected only a Code attribute, got {attributes:?}"),
+        };
+
+        // javap -v: from 0, to 3, target 4, Class java/lang/ArithmeticException
+        match exceptions[..] {
+            [ExceptionTableEntry {
+                start_pc: 0,
+                end_pc: 3,
+                handler_pc: 4,
+                catch_type,
+            }] => assert_eq!(
+                class.constant_pool.class(catch_type),
+                Ok("java/lang/ArithmeticException")
+            ),
+            ref table => panic!("unexpected exception table {table:?}"),
+        }
+
+        match &attributes[..] {
+            [Attribute::LineNumberTable(lines), Attribute::Unknown { name, .. }] => {
+                let lines: Vec<_> = lines
+                    .iter()
+                    .map(|entry| (entry.start_pc, entry.line_number))
+                    .collect();
+                assert_eq!(lines, [(0, 27), (4, 28), (5, 29)]);
+                assert_eq!(name, "StackMapTable");
+            }
+            attributes => panic!("unexpected nested attributes {attributes:?}"),
+        }
+    }
 }
@@ -0,0 +1,181 @@
+//! Rendering of descriptors, signatures, and internal class names the way
+//! they would be written in Java source, like `void (String[])` for
+//! `([Ljava/lang/String;)V`.
+
+use crate::{
+    descriptor::{FieldType, MethodDescriptor},
+    error::DescriptorError,
+    signature::{ClassType, TypeArgument, TypeSignature},
+};
+
+/// How class names are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Names {
+    /// With their package, like `java.lang.String`.
+    Qualified,
+    /// Without their package, like `String`.
+    Simple,
+}
+
+/// Renders an internal class name like `java/util/Map$Entry`. Nested classes
+/// keep the `$` of their binary name, since it can also appear in the name
+/// of a class that isn't nested.
+pub fn class_name(name: &str, names: Names) -> String {
+    match names {
+        Names::Qualified => name.replace('/', "."),
+        Names::Simple => match name.rsplit_once('/') {
+            Some((_, simple)) => simple.to_owned(),
+            None => name.to_owned(),
+        },
+    }
+}
+
+/// Renders a field descriptor or method descriptor, telling them apart by
+/// the parenthesis a method descriptor starts with.
+pub fn descriptor(descriptor: &str, names: Names) -> Result<String, DescriptorError> {
+    if descriptor.starts_with('(') {
+        Ok(method("", &MethodDescriptor::parse(descriptor)?, names))
+    } else {
+        Ok(field_type(&FieldType::parse(descriptor)?, names))
+    }
+}
+
+pub fn field_type(field_type: &FieldType, names: Names) -> String {
+    match field_type {
+        FieldType::Byte => "byte".to_owned(),
+        FieldType::Char => "char".to_owned(),
+        FieldType::Double => "double".to_owned(),
+        FieldType::Float => "float".to_owned(),
+        FieldType::Int => "int".to_owned(),
+        FieldType::Long => "long".to_owned(),
+        FieldType::Short => "short".to_owned(),
+        FieldType::Boolean => "boolean".to_owned(),
+        FieldType::Object(name) => class_name(name, names),
+        FieldType::Array(component) => format!("{}[]", self::field_type(component, names)),
+    }
+}
+
+/// Renders a method declaration like `void main(String[])`. An empty `name`
+/// leaves just the types, as in `void (String[])`.
+pub fn method(name: &str, descriptor: &MethodDescriptor, names: Names) -> String {
+    let return_type = match &descriptor.return_type {
+        Some(return_type) => field_type(return_type, names),
+        None => "void".to_owned(),
+    };
+    let parameters: Vec<_> = descriptor
+        .parameters
+        .iter()
+        .map(|parameter| field_type(parameter, names))
+        .collect();
+    format!("{return_type} {name}({})", parameters.join(", "))
+}
+
+/// Renders a type from a generic signature, like
+/// `java.util.List<? extends T>`.
+pub fn type_signature(signature: &TypeSignature, names: Names) -> String {
+    match signature {
+        TypeSignature::Primitive(primitive) => field_type(primitive, names),
+        TypeSignature::Class(class) => class_type(class, names),
+        TypeSignature::TypeVariable(name) => name.clone(),
+        TypeSignature::Array(component) => format!("{}[]", type_signature(component, names)),
+    }
+}
+
+/// Renders a possibly parameterized class type. Nested classes are
+/// separated by `.`, as in `Map<K, V>.Entry`, since the signature already
+/// tells them apart from their outer class.
+fn class_type(class: &ClassType, names: Names) -> String {
+    let mut out = String::new();
+    if names == Names::Qualified && !class.package.is_empty() {
+        out.push_str(&class.package.replace('/', "."));
+        out.push('.');
+    }
+    for (i, simple) in class.classes.iter().enumerate() {
+        if i > 0 {
+            out.push('.');
+        }
+        out.push_str(&simple.name);
+        if !simple.type_arguments.is_empty() {
+            let arguments: Vec<_> = simple
+                .type_arguments
+                .iter()
+                .map(|argument| match argument {
+                    TypeArgument::Exact(argument) => type_signature(argument, names),
+                    TypeArgument::Extends(bound) => {
+                        format!("? extends {}", type_signature(bound, names))
+                    }
+                    TypeArgument::Super(bound) => {
+                        format!("? super {}", type_signature(bound, names))
+                    }
+                    TypeArgument::Any => "?".to_owned(),
+                })
+                .collect();
+            out.push('<');
+            out.push_str(&arguments.join(", "));
+            out.push('>');
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn class_names() {
+        assert_eq!(
+            class_name("java/lang/Object", Names::Qualified),
+            "java.lang.Object"
+        );
+        assert_eq!(class_name("java/lang/Object", Names::Simple), "Object");
+        assert_eq!(class_name("Hello", Names::Simple), "Hello");
+        assert_eq!(
+            class_name("java/util/Map$Entry", Names::Qualified),
+            "java.util.Map$Entry"
+        );
+        assert_eq!(
+            class_name("java/util/Map$Entry", Names::Simple),
+            "Map$Entry"
+        );
+    }
+
+    #[test]
+    fn descriptors() {
+        let render = |text| descriptor(text, Names::Simple).unwrap();
+        assert_eq!(render("([Ljava/lang/String;)V"), "void (String[])");
+        assert_eq!(render("[[I"), "int[][]");
+        assert_eq!(render("(J[BZ)[D"), "double[] (long, byte[], boolean)");
+        assert_eq!(
+            descriptor(
+                "(Ljava/util/Map$Entry;)Ljava/lang/Object;",
+                Names::Qualified
+            ),
+            Ok("java.lang.Object (java.util.Map$Entry)".to_owned())
+        );
+        assert!(descriptor("(I", Names::Simple).is_err());
+    }
+
+    #[test]
+    fn methods_are_named() {
+        let main = MethodDescriptor::parse("([Ljava/lang/String;)V").unwrap();
+        assert_eq!(
+            method("main", &main, Names::Qualified),
+            "void main(java.lang.String[])"
+        );
+    }
+
+    #[test]
+    fn type_signatures() {
+        let render = |text, names| type_signature(&TypeSignature::parse(text).unwrap(), names);
+        assert_eq!(
+            render("Ljava/util/List<+TT;>;", Names::Qualified),
+            "java.util.List<? extends T>"
+        );
+        assert_eq!(
+            render("Ljava/util/Map<TK;TV;>.Entry<*-[I>;", Names::Simple),
+            "Map<K, V>.Entry<?, ? super int[]>"
+        );
+        assert_eq!(render("[TT;", Names::Simple), "T[]");
+    }
+}
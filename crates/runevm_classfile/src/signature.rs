@@ -0,0 +1,540 @@
+//! Parsing of the generic signatures held by `Signature` attributes, like
+//! `<T:Ljava/lang/Object;>(Ljava/util/List<+TT;>;)TT;`.
+//!
+//! Each type displays as the signature it was parsed from.
+
+use crate::{
+    descriptor::{FieldType, MAX_DIMENSIONS},
+    error::SignatureError,
+};
+use std::fmt;
+
+/// How deeply type arguments can nest inside one another. Real signatures
+/// never come close, and the limit keeps a malformed one from recursing
+/// until the stack overflows.
+const MAX_TYPE_ARGUMENT_DEPTH: usize = 64;
+
+/// The signature of a generic class: its type parameters and the
+/// parameterized types it extends and implements.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ClassSignature {
+    pub type_parameters: Vec<TypeParameter>,
+    pub super_class: ClassType,
+    pub interfaces: Vec<ClassType>,
+}
+
+/// The signature of a generic method, or of one whose parameters, return
+/// type, or thrown exceptions mention a type variable or parameterized type.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MethodSignature {
+    pub type_parameters: Vec<TypeParameter>,
+    pub parameters: Vec<TypeSignature>,
+    /// The type of the returned value, or `None` for `void`.
+    pub return_type: Option<TypeSignature>,
+    /// The exceptions the method declares it throws, which are class types or
+    /// type variables.
+    pub throws: Vec<TypeSignature>,
+}
+
+/// A type parameter of a generic class or method, like `T extends Number`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TypeParameter {
+    pub name: String,
+    /// The bound that is a class or type variable, which may be left out
+    /// when the parameter is only bounded by interfaces.
+    pub class_bound: Option<TypeSignature>,
+    pub interface_bounds: Vec<TypeSignature>,
+}
+
+/// A type as it appears in a signature, which unlike a descriptor can be a
+/// type variable or have type arguments.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TypeSignature {
+    /// One of the eight primitive types.
+    Primitive(FieldType),
+    Class(ClassType),
+    /// A use of the type parameter with the given name.
+    TypeVariable(String),
+    /// An array with the given component type.
+    Array(Box<TypeSignature>),
+}
+
+/// A possibly parameterized class type, like `java/util/Map<TK;TV;>.Entry`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ClassType {
+    /// The package the class is in, with `/` between its parts, which is
+    /// empty for the unnamed package.
+    pub package: String,
+    /// The outermost class first, followed by each class nested in it down
+    /// to the one this type names.
+    pub classes: Vec<SimpleClassType>,
+}
+
+/// A class within a [`ClassType`], along with the type arguments it is
+/// given.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SimpleClassType {
+    pub name: String,
+    pub type_arguments: Vec<TypeArgument>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TypeArgument {
+    /// An exact type, like the `String` in `List<String>`.
+    Exact(TypeSignature),
+    /// `? extends` the type.
+    Extends(TypeSignature),
+    /// `? super` the type.
+    Super(TypeSignature),
+    /// The unbounded wildcard `?`.
+    Any,
+}
+
+impl ClassSignature {
+    /// Parses a class signature, which must make up the whole of
+    /// `signature`.
+    pub fn parse(signature: &str) -> Result<ClassSignature, SignatureError> {
+        let mut parser = Parser::new(signature);
+        let type_parameters = parser.type_parameters()?;
+        let super_class = parser.class_type()?;
+        let mut interfaces = Vec::new();
+        while !parser.at_end() {
+            interfaces.push(parser.class_type()?);
+        }
+
+        Ok(ClassSignature {
+            type_parameters,
+            super_class,
+            interfaces,
+        })
+    }
+}
+
+impl MethodSignature {
+    /// Parses a method signature, which must make up the whole of
+    /// `signature`.
+    pub fn parse(signature: &str) -> Result<MethodSignature, SignatureError> {
+        let mut parser = Parser::new(signature);
+        let type_parameters = parser.type_parameters()?;
+
+        parser.expect('(')?;
+        let mut parameters = Vec::new();
+        while !parser.eat(')') {
+            parameters.push(parser.type_signature()?);
+        }
+        let return_type = if parser.eat('V') {
+            None
+        } else {
+            Some(parser.type_signature()?)
+        };
+
+        let mut throws = Vec::new();
+        while parser.eat('^') {
+            throws.push(match parser.peek()? {
+                'T' => parser.type_signature()?,
+                _ => TypeSignature::Class(parser.class_type()?),
+            });
+        }
+        parser.finish()?;
+
+        Ok(MethodSignature {
+            type_parameters,
+            parameters,
+            return_type,
+            throws,
+        })
+    }
+}
+
+impl TypeSignature {
+    /// Parses a field signature, which must make up the whole of
+    /// `signature`. Fields only have a signature if their type is a
+    /// reference.
+    pub fn parse(signature: &str) -> Result<TypeSignature, SignatureError> {
+        let mut parser = Parser::new(signature);
+        let field_type = parser.type_signature()?;
+        parser.finish()?;
+        Ok(field_type)
+    }
+}
+
+/// Reads a signature from left to right.
+struct Parser<'a> {
+    signature: &'a str,
+    position: usize,
+    /// How many type argument lists the parser is inside.
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(signature: &'a str) -> Parser<'a> {
+        Parser {
+            signature,
+            position: 0,
+            depth: 0,
+        }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.signature[self.position..]
+    }
+
+    fn at_end(&self) -> bool {
+        self.rest().is_empty()
+    }
+
+    fn peek(&self) -> Result<char, SignatureError> {
+        self.rest()
+            .chars()
+            .next()
+            .ok_or(SignatureError::UnexpectedEnd)
+    }
+
+    /// Consumes `c` if it comes next, returning whether it did.
+    fn eat(&mut self, c: char) -> bool {
+        let found = self.rest().starts_with(c);
+        if found {
+            self.position += c.len_utf8();
+        }
+        found
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), SignatureError> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
+    fn unexpected(&self) -> SignatureError {
+        match self.rest().chars().next() {
+            Some(found) => SignatureError::UnexpectedCharacter {
+                position: self.position,
+                found,
+            },
+            None => SignatureError::UnexpectedEnd,
+        }
+    }
+
+    fn finish(&self) -> Result<(), SignatureError> {
+        if self.at_end() {
+            Ok(())
+        } else {
+            Err(SignatureError::TrailingCharacters(self.rest().to_owned()))
+        }
+    }
+
+    /// Reads a name, which runs up to the next character that has a meaning
+    /// of its own in signatures.
+    fn identifier(&mut self) -> Result<String, SignatureError> {
+        let rest = self.rest();
+        let length = rest
+            .find(['.', ';', '[', '/', '<', '>', ':'])
+            .unwrap_or(rest.len());
+        if length == 0 {
+            return Err(self.unexpected());
+        }
+        self.position += length;
+        Ok(rest[..length].to_owned())
+    }
+
+    fn type_parameters(&mut self) -> Result<Vec<TypeParameter>, SignatureError> {
+        let mut parameters = Vec::new();
+        if !self.eat('<') {
+            return Ok(parameters);
+        }
+
+        loop {
+            let name = self.identifier()?;
+            self.expect(':')?;
+            let class_bound = match self.peek()? {
+                ':' => None,
+                _ => Some(self.reference_type()?),
+            };
+            let mut interface_bounds = Vec::new();
+            while self.eat(':') {
+                interface_bounds.push(self.reference_type()?);
+            }
+            parameters.push(TypeParameter {
+                name,
+                class_bound,
+                interface_bounds,
+            });
+
+            if self.eat('>') {
+                return Ok(parameters);
+            }
+        }
+    }
+
+    fn type_signature(&mut self) -> Result<TypeSignature, SignatureError> {
+        let primitive = match self.peek()? {
+            'B' => FieldType::Byte,
+            'C' => FieldType::Char,
+            'D' => FieldType::Double,
+            'F' => FieldType::Float,
+            'I' => FieldType::Int,
+            'J' => FieldType::Long,
+            'S' => FieldType::Short,
+            'Z' => FieldType::Boolean,
+            _ => return self.reference_type(),
+        };
+        self.position += 1;
+        Ok(TypeSignature::Primitive(primitive))
+    }
+
+    fn reference_type(&mut self) -> Result<TypeSignature, SignatureError> {
+        match self.peek()? {
+            'L' => Ok(TypeSignature::Class(self.class_type()?)),
+            'T' => {
+                self.position += 1;
+                let name = self.identifier()?;
+                self.expect(';')?;
+                Ok(TypeSignature::TypeVariable(name))
+            }
+            '[' => {
+                // The dimensions are counted rather than recursed into, so
+                // a long run of them can't overflow the stack.
+                let dimensions = self.rest().bytes().take_while(|&b| b == b'[').count();
+                if dimensions > MAX_DIMENSIONS {
+                    return Err(SignatureError::TooManyDimensions);
+                }
+                self.position += dimensions;
+                let mut signature = self.type_signature()?;
+                for _ in 0..dimensions {
+                    signature = TypeSignature::Array(Box::new(signature));
+                }
+                Ok(signature)
+            }
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    fn class_type(&mut self) -> Result<ClassType, SignatureError> {
+        self.expect('L')?;
+
+        // Every name followed by a `/` is part of the package.
+        let mut package = Vec::new();
+        let mut name = self.identifier()?;
+        while self.eat('/') {
+            package.push(name);
+            name = self.identifier()?;
+        }
+
+        let mut classes = Vec::new();
+        loop {
+            let type_arguments = self.type_arguments()?;
+            classes.push(SimpleClassType {
+                name,
+                type_arguments,
+            });
+            if !self.eat('.') {
+                break;
+            }
+            name = self.identifier()?;
+        }
+        self.expect(';')?;
+
+        Ok(ClassType {
+            package: package.join("/"),
+            classes,
+        })
+    }
+
+    fn type_arguments(&mut self) -> Result<Vec<TypeArgument>, SignatureError> {
+        let mut arguments = Vec::new();
+        if !self.eat('<') {
+            return Ok(arguments);
+        }
+        if self.depth == MAX_TYPE_ARGUMENT_DEPTH {
+            return Err(SignatureError::TooDeep);
+        }
+        self.depth += 1;
+
+        loop {
+            arguments.push(if self.eat('*') {
+                TypeArgument::Any
+            } else if self.eat('+') {
+                TypeArgument::Extends(self.reference_type()?)
+            } else if self.eat('-') {
+                TypeArgument::Super(self.reference_type()?)
+            } else {
+                TypeArgument::Exact(self.reference_type()?)
+            });
+
+            if self.eat('>') {
+                self.depth -= 1;
+                return Ok(arguments);
+            }
+        }
+    }
+}
+
+fn write_type_parameters(f: &mut fmt::Formatter<'_>, parameters: &[TypeParameter]) -> fmt::Result {
+    if parameters.is_empty() {
+        return Ok(());
+    }
+
+    write!(f, "<")?;
+    for parameter in parameters {
+        write!(f, "{}:", parameter.name)?;
+        if let Some(bound) = &parameter.class_bound {
+            write!(f, "{bound}")?;
+        }
+        for bound in &parameter.interface_bounds {
+            write!(f, ":{bound}")?;
+        }
+    }
+    write!(f, ">")
+}
+
+impl fmt::Display for ClassSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_type_parameters(f, &self.type_parameters)?;
+        write!(f, "{}", self.super_class)?;
+        for interface in &self.interfaces {
+            write!(f, "{interface}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for MethodSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_type_parameters(f, &self.type_parameters)?;
+        write!(f, "(")?;
+        for parameter in &self.parameters {
+            write!(f, "{parameter}")?;
+        }
+        match &self.return_type {
+            Some(return_type) => write!(f, "){return_type}")?,
+            None => write!(f, ")V")?,
+        }
+        for thrown in &self.throws {
+            write!(f, "^{thrown}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for TypeSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TypeSignature::Primitive(primitive) => write!(f, "{primitive}"),
+            TypeSignature::Class(class) => write!(f, "{class}"),
+            TypeSignature::TypeVariable(name) => write!(f, "T{name};"),
+            TypeSignature::Array(component) => write!(f, "[{component}"),
+        }
+    }
+}
+
+impl fmt::Display for ClassType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "L")?;
+        if !self.package.is_empty() {
+            write!(f, "{}/", self.package)?;
+        }
+        for (i, class) in self.classes.iter().enumerate() {
+            if i > 0 {
+                write!(f, ".")?;
+            }
+            write!(f, "{}", class.name)?;
+            if !class.type_arguments.is_empty() {
+                write!(f, "<")?;
+                for argument in &class.type_arguments {
+                    match argument {
+                        TypeArgument::Exact(argument) => write!(f, "{argument}")?,
+                        TypeArgument::Extends(bound) => write!(f, "+{bound}")?,
+                        TypeArgument::Super(bound) => write!(f, "-{bound}")?,
+                        TypeArgument::Any => write!(f, "*")?,
+                    }
+                }
+                write!(f, ">")?;
+            }
+        }
+        write!(f, ";")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_jdk_signatures() {
+        for signature in [
+            // java.util.HashMap
+            "<K:Ljava/lang/Object;V:Ljava/lang/Object;>Ljava/util/AbstractMap<TK;TV;>;\
+             Ljava/util/Map<TK;TV;>;Ljava/lang/Cloneable;Ljava/io/Serializable;",
+            // java.lang.Enum
+            "<E:Ljava/lang/Enum<TE;>;>Ljava/lang/Object;Ljava/lang/constant/Constable;\
+             Ljava/lang/Comparable<TE;>;Ljava/io/Serializable;",
+        ] {
+            assert_eq!(
+                ClassSignature::parse(signature).unwrap().to_string(),
+                signature
+            );
+        }
+        for signature in [
+            // java.util.Collections.max
+            "<T:Ljava/lang/Object;:Ljava/lang/Comparable<-TT;>;>(Ljava/util/Collection<+TT;>;)TT;",
+            // java.util.stream.Collectors.groupingBy
+            "<T:Ljava/lang/Object;K:Ljava/lang/Object;>(Ljava/util/function/Function<-TT;+TK;>;)\
+             Ljava/util/stream/Collector<TT;*Ljava/util/Map<TK;Ljava/util/List<TT;>;>;>;",
+            // java.lang.Class.getMethod
+            "(Ljava/lang/String;[Ljava/lang/Class<*>;)Ljava/lang/reflect/Method;\
+             ^Ljava/lang/NoSuchMethodException;",
+        ] {
+            assert_eq!(
+                MethodSignature::parse(signature).unwrap().to_string(),
+                signature
+            );
+        }
+        for signature in ["Ljava/util/Map<TK;TV;>.Entry<TK;TV;>;", "[[TT;"] {
+            assert_eq!(
+                TypeSignature::parse(signature).unwrap().to_string(),
+                signature
+            );
+        }
+    }
+
+    #[test]
+    fn multibyte_characters_are_unexpected_not_a_panic() {
+        assert_eq!(
+            ClassSignature::parse("Ljava/util/List<é>;"),
+            Err(SignatureError::UnexpectedCharacter {
+                position: 16,
+                found: 'é',
+            })
+        );
+    }
+
+    #[test]
+    fn array_dimensions_are_limited() {
+        let nested = |dimensions: usize| format!("{}I", "[".repeat(dimensions));
+        assert!(TypeSignature::parse(&nested(MAX_DIMENSIONS)).is_ok());
+        assert_eq!(
+            TypeSignature::parse(&nested(60000)),
+            Err(SignatureError::TooManyDimensions)
+        );
+    }
+
+    #[test]
+    fn type_argument_nesting_is_limited() {
+        let nested = |depth: usize| {
+            format!(
+                "{}Ljava/lang/Object;{}",
+                "Ljava/util/List<".repeat(depth),
+                ">;".repeat(depth)
+            )
+        };
+        assert!(TypeSignature::parse(&nested(MAX_TYPE_ARGUMENT_DEPTH)).is_ok());
+        for depth in [MAX_TYPE_ARGUMENT_DEPTH + 1, 100_000] {
+            assert_eq!(
+                TypeSignature::parse(&nested(depth)),
+                Err(SignatureError::TooDeep)
+            );
+        }
+    }
+}
@@ -0,0 +1,478 @@
+//! Structural checks run on a method's bytecode before it is executed.
+
+use crate::{
+    error::VerifyError, parser::Attribute, Constant, ConstantKind, ConstantPool, Instruction,
+    Method, MethodAccessFlags,
+};
+use std::collections::{HashMap, HashSet};
+
+/// Checks that no path through `method`'s code can pop more off the operand
+/// stack than is on it, push more than `max_stack` slots onto it, or use a
+/// local variable past `max_locals`. Types aren't checked. Methods without
+/// code, like abstract and native ones, always pass.
+pub fn check_maxs(method: &Method, pool: &ConstantPool) -> Result<(), VerifyError> {
+    let (max_stack, max_locals, code, exceptions) =
+        match method.attributes.iter().find_map(|attr| {
+            if let Attribute::Code {
+                max_stack,
+                max_locals,
+                code,
+                exceptions,
+                ..
+            } = attr
+            {
+                Some((*max_stack, *max_locals, code, exceptions))
+            } else {
+                None
+            }
+        }) {
+            Some(code) => code,
+            None => return Ok(()),
+        };
+
+    // Slots are counted as u32 so that no descriptor can overflow them.
+    let mut parameters = descriptor_slots(pool.utf8(method.descriptor_index)?).0;
+    if !method.access_flags.contains(MethodAccessFlags::STATIC) {
+        parameters += 1;
+    }
+    if parameters > u32::from(max_locals) {
+        return Err(VerifyError::TooManyParameters {
+            parameters,
+            max_locals,
+        });
+    }
+
+    let indices: HashMap<u32, usize> = code
+        .iter()
+        .enumerate()
+        .map(|(index, &(offset, _))| (offset, index))
+        .collect();
+
+    // The stack depth each instruction starts with, once a path to it has
+    // been followed. Handlers start with just the exception on the stack.
+    let mut depths = vec![None; code.len()];
+    let mut pending: Vec<(u32, u16)> = vec![(0, 0)];
+    pending.extend(exceptions.iter().map(|entry| (entry.handler_pc as u32, 1)));
+
+    while let Some((offset, depth)) = pending.pop() {
+        let index = match indices.get(&offset) {
+            Some(&index) => index,
+            None => return Err(VerifyError::InvalidBranchTarget(offset as i64)),
+        };
+        match depths[index] {
+            Some(known) if known == depth => continue,
+            Some(_) => return Err(VerifyError::InconsistentStack { offset }),
+            None => depths[index] = Some(depth),
+        }
+
+        let inst = &code[index].1;
+        let (pops, pushes) = effect(inst, offset, max_locals, pool)?;
+        if pops > u32::from(depth) {
+            return Err(VerifyError::StackUnderflow { offset });
+        }
+        let after = u32::from(depth) - pops + pushes;
+        if after > u32::from(max_stack) {
+            return Err(VerifyError::StackOverflow { offset, max_stack });
+        }
+        // It's no more than max_stack, so it fits.
+        let after = after as u16;
+
+        // Execution can't run past the last instruction.
+        let next = code
+            .get(index + 1)
+            .map(|&(next, _)| next)
+            .ok_or(VerifyError::FallsOffEnd);
+        let branch = |relative: i32| {
+            let target = offset as i64 + relative as i64;
+            u32::try_from(target).map_err(|_| VerifyError::InvalidBranchTarget(target))
+        };
+
+        use Instruction::*;
+        match *inst {
+            Goto(relative) => pending.push((branch(relative as i32)?, after)),
+            Gotow(relative) => pending.push((branch(relative)?, after)),
+            If(_, relative)
+            | Ificmp(_, relative)
+            | Ifacmp(_, relative)
+            | Ifnull(relative)
+            | Ifnonnull(relative) => {
+                pending.push((branch(relative as i32)?, after));
+                pending.push((next?, after));
+            }
+            // The subroutine returns to the next instruction with its return
+            // address popped again.
+            Jsr(relative) => {
+                pending.push((branch(relative as i32)?, after));
+                pending.push((next?, depth));
+            }
+            Jsrw(relative) => {
+                pending.push((branch(relative)?, after));
+                pending.push((next?, depth));
+            }
+            Lookupswitch {
+                default, ref pairs, ..
+            } => {
+                pending.push((branch(default)?, after));
+                for &(_, relative) in pairs {
+                    pending.push((branch(relative)?, after));
+                }
+            }
+            Tableswitch {
+                default,
+                ref offsets,
+                ..
+            } => {
+                pending.push((branch(default)?, after));
+                for &relative in offsets {
+                    pending.push((branch(relative)?, after));
+                }
+            }
+            Areturn | Athrow | Dreturn | Freturn | Ireturn | Lreturn | Ret(_) | Return => {}
+            Wide(0xa9, _) => {}
+            _ => pending.push((next?, after)),
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks that every branch and switch target in `method`'s code, and every
+/// offset in its exception table, lands on the start of an instruction.
+/// A handler's range may also end at the end of the code.
+pub fn check_branches(method: &Method) -> Result<(), VerifyError> {
+    let code = match method.code() {
+        Some(code) => code,
+        None => return Ok(()),
+    };
+    let length = code_length(code);
+    let starts: HashSet<u32> = code.iter().map(|&(offset, _)| offset).collect();
+
+    for (offset, inst) in code {
+        for relative in inst.branches() {
+            let target = *offset as i64 + relative as i64;
+            match u32::try_from(target) {
+                Ok(target) if starts.contains(&target) => {}
+                Ok(target) if target < length => {
+                    return Err(VerifyError::BranchIntoInstruction {
+                        offset: *offset,
+                        target,
+                    })
+                }
+                _ => {
+                    return Err(VerifyError::BranchOutOfRange {
+                        offset: *offset,
+                        target,
+                    })
+                }
+            }
+        }
+    }
+
+    for entry in method.exception_table() {
+        let (start_pc, end_pc) = (entry.start_pc, entry.end_pc);
+        let end_valid = starts.contains(&(end_pc as u32)) || end_pc as u32 == length;
+        if !starts.contains(&(start_pc as u32)) || !end_valid || start_pc >= end_pc {
+            return Err(VerifyError::InvalidExceptionRange { start_pc, end_pc });
+        }
+        if !starts.contains(&(entry.handler_pc as u32)) {
+            return Err(VerifyError::InvalidHandler {
+                handler_pc: entry.handler_pc,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the length in bytes of `code`, which runs up to the end of its
+/// last instruction.
+fn code_length(code: &[(u32, Instruction)]) -> u32 {
+    let (offset, last) = match code.last() {
+        Some(last) => last,
+        None => return 0,
+    };
+
+    // Switches pad their operands to a multiple of four bytes from the start
+    // of the code, so encode the last instruction at the same alignment.
+    let padding = (offset % 4) as usize;
+    let mut out = vec![0; padding];
+    last.encode(&mut out);
+    offset + (out.len() - padding) as u32
+}
+
+/// Returns how many slots `inst` pops off the operand stack and how many it
+/// pushes onto it, checking any local variable it uses against `max_locals`.
+fn effect(
+    inst: &Instruction,
+    offset: u32,
+    max_locals: u16,
+    pool: &ConstantPool,
+) -> Result<(u32, u32), VerifyError> {
+    use Instruction::*;
+
+    let local = |index: u16, slots: u16, effect: (u32, u32)| {
+        if index as u32 + slots as u32 > max_locals as u32 {
+            Err(VerifyError::InvalidLocal { offset, index })
+        } else {
+            Ok(effect)
+        }
+    };
+    let member = |index: u16| -> Result<&str, VerifyError> {
+        let nametype_index = match *pool.at(index)? {
+            Constant::Field { nametype_index, .. }
+            | Constant::Method { nametype_index, .. }
+            | Constant::InterfaceMethod { nametype_index, .. }
+            | Constant::InvokeDynamic { nametype_index, .. } => nametype_index,
+            _ => return Err(pool.unexpected(index, ConstantKind::Method).into()),
+        };
+        Ok(pool.name_and_type(nametype_index)?.1)
+    };
+    let field = |index: u16| member(index).map(|descriptor| descriptor_slots(descriptor).1);
+
+    Ok(match inst.canonical() {
+        Error(opcode) | Local(opcode, _) | Wide(opcode, _) => {
+            return Err(VerifyError::UnknownOpcode { offset, opcode })
+        }
+
+        Nop | Goto(_) | Gotow(_) | Return => (0, 0),
+        Ret(n) => local(n, 1, (0, 0))?,
+        Iinc { index, .. } => local(index, 1, (0, 0))?,
+
+        AconstNull | IconstM1 | Iconst0 | Iconst1 | Iconst2 | Iconst3 | Iconst4 | Iconst5
+        | Fconst0 | Fconst1 | Fconst2 | Bipush(_) | Sipush(_) | Ldc(_) | Ldcw(_) | New(_) => (0, 1),
+        Lconst0 | Lconst1 | Dconst0 | Dconst1 | Ldc2w(_) => (0, 2),
+        Jsr(_) | Jsrw(_) => (0, 1),
+
+        Iload(n) | Fload(n) | Aload(n) => local(n, 1, (0, 1))?,
+        Lload(n) | Dload(n) => local(n, 2, (0, 2))?,
+        Istore(n) | Fstore(n) | Astore(n) => local(n, 1, (1, 0))?,
+        Lstore(n) | Dstore(n) => local(n, 2, (2, 0))?,
+
+        Iaload | Faload | Aaload | Baload | Caload | Saload => (2, 1),
+        Laload | Daload => (2, 2),
+        Iastore | Fastore | Aastore | Bastore | Castore | Sastore => (3, 0),
+        Lastore | Dastore => (4, 0),
+
+        Pop => (1, 0),
+        Pop2 => (2, 0),
+        Dup => (1, 2),
+        DupX1 => (2, 3),
+        DupX2 => (3, 4),
+        Dup2 => (2, 4),
+        Dup2X1 => (3, 5),
+        Dup2X2 => (4, 6),
+        Swap => (2, 2),
+
+        Iadd | Isub | Imul | Idiv | Irem | Iand | Ior | Ixor | Ishl | Ishr | Iushr | Fadd
+        | Fsub | Fmul | Fdiv | Frem | Fcmpl | Fcmpg => (2, 1),
+        Ladd | Lsub | Lmul | Ldiv | Lrem | Land | Lor | Lxor | Dadd | Dsub | Dmul | Ddiv | Drem => {
+            (4, 2)
+        }
+        Lshl | Lshr | Lushr => (3, 2),
+        Lcmp | Dcmpl | Dcmpg => (4, 1),
+        Ineg | Fneg | I2f | I2b | I2c | I2s | F2i => (1, 1),
+        Lneg | Dneg | L2d | D2l => (2, 2),
+        I2l | I2d | F2l | F2d => (1, 2),
+        L2i | L2f | D2i | D2f => (2, 1),
+
+        If(..) | Ifnull(_) | Ifnonnull(_) | Lookupswitch { .. } | Tableswitch { .. } => (1, 0),
+        Ificmp(..) | Ifacmp(..) => (2, 0),
+
+        Ireturn | Freturn | Areturn | Athrow | Monitorenter | Monitorexit => (1, 0),
+        Lreturn | Dreturn => (2, 0),
+
+        Getstatic(index) => (0, field(index)?),
+        Putstatic(index) => (field(index)?, 0),
+        Getfield(index) => (1, field(index)?),
+        Putfield(index) => (1 + field(index)?, 0),
+        Invokevirtual(index) | Invokespecial(index) | Invokeinterface(index, _) => {
+            let (parameters, returned) = descriptor_slots(member(index)?);
+            (parameters + 1, returned)
+        }
+        Invokestatic(index) | Invokedynamic(index) => descriptor_slots(member(index)?),
+
+        Newarray(_) | Anewarray(_) | Arraylength | Checkcast(_) | Instanceof(_) => (1, 1),
+        Multianewarray(_, dimensions) => (u32::from(dimensions), 1),
+    })
+}
+
+/// Counts the slots taken up by a descriptor. For a method descriptor, that's
+/// the slots of its parameters and of its return value; for a field
+/// descriptor, it's none and the slots of the field. Longs and doubles take
+/// up two slots and `void` none.
+fn descriptor_slots(descriptor: &str) -> (u32, u32) {
+    let size = |c: Option<char>| match c {
+        Some('V') => 0,
+        Some('J' | 'D') => 2,
+        _ => 1,
+    };
+
+    let parameters = match descriptor.strip_prefix('(') {
+        Some(parameters) => parameters,
+        None => return (0, size(descriptor.chars().next())),
+    };
+
+    let mut slots = 0;
+    let mut chars = parameters.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            ')' => break,
+            'L' => {
+                chars.by_ref().find(|&c| c == ';');
+                slots += 1;
+            }
+            '[' => {
+                let mut element = chars.next();
+                while element == Some('[') {
+                    element = chars.next();
+                }
+                if element == Some('L') {
+                    chars.by_ref().find(|&c| c == ';');
+                }
+                slots += 1;
+            }
+            c => slots += size(Some(c)),
+        }
+    }
+
+    (slots, size(chars.next()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{instructions::Instruction::*, ConstantPoolBuilder};
+
+    fn method(
+        pool: &mut ConstantPoolBuilder,
+        access_flags: MethodAccessFlags,
+        descriptor: &str,
+        (max_stack, max_locals): (u16, u16),
+        code: Vec<(u32, Instruction)>,
+    ) -> Method {
+        Method {
+            access_flags,
+            name_index: pool.add_utf8("run").unwrap(),
+            descriptor_index: pool.add_utf8(descriptor).unwrap(),
+            attributes: vec![Attribute::Code {
+                max_stack,
+                max_locals,
+                code,
+                exceptions: Vec::new(),
+                attributes: Vec::new(),
+            }],
+        }
+    }
+
+    #[test]
+    fn parameter_slots_beyond_u16() {
+        let descriptor = format!("({})V", "J".repeat(40000));
+        let mut pool = ConstantPool::builder();
+        let method = method(
+            &mut pool,
+            MethodAccessFlags::PUBLIC,
+            &descriptor,
+            (0, u16::MAX),
+            vec![(0, Return)],
+        );
+        assert!(matches!(
+            check_maxs(&method, &pool.build()),
+            Err(VerifyError::TooManyParameters {
+                parameters: 80001,
+                max_locals: u16::MAX,
+            })
+        ));
+    }
+
+    #[test]
+    fn invoking_a_method_with_more_slots_than_u16() {
+        let descriptor = format!("({})V", "D".repeat(40000));
+        let mut pool = ConstantPool::builder();
+        let callee = pool.add_method_ref("Test", "callee", &descriptor).unwrap();
+        let method = method(
+            &mut pool,
+            MethodAccessFlags::STATIC,
+            "()V",
+            (u16::MAX, 0),
+            vec![(0, Invokestatic(callee)), (3, Return)],
+        );
+        assert!(matches!(
+            check_maxs(&method, &pool.build()),
+            Err(VerifyError::StackUnderflow { offset: 0 })
+        ));
+    }
+
+    #[test]
+    fn pushing_past_max_stack_is_rejected() {
+        let code = vec![(0, Iconst1), (1, Iconst2), (2, Iadd), (3, Ireturn)];
+        let mut pool = ConstantPool::builder();
+        let small = method(
+            &mut pool,
+            MethodAccessFlags::STATIC,
+            "()I",
+            (1, 0),
+            code.clone(),
+        );
+        let enough = method(&mut pool, MethodAccessFlags::STATIC, "()I", (2, 0), code);
+        let pool = pool.build();
+
+        assert!(matches!(
+            check_maxs(&small, &pool),
+            Err(VerifyError::StackOverflow {
+                offset: 1,
+                max_stack: 1,
+            })
+        ));
+        assert_eq!(check_maxs(&enough, &pool), Ok(()));
+    }
+
+    #[test]
+    fn using_a_local_past_max_locals_is_rejected() {
+        let mut pool = ConstantPool::builder();
+        let int = method(
+            &mut pool,
+            MethodAccessFlags::STATIC,
+            "()V",
+            (1, 1),
+            vec![(0, Iconst1), (1, Istore(1)), (2, Return)],
+        );
+        // A long in local 0 also takes local 1.
+        let long = method(
+            &mut pool,
+            MethodAccessFlags::STATIC,
+            "()V",
+            (2, 1),
+            vec![(0, Lconst1), (1, Lstore(0)), (2, Return)],
+        );
+        let receiver = method(
+            &mut pool,
+            MethodAccessFlags::PUBLIC,
+            "(I)V",
+            (0, 1),
+            vec![(0, Return)],
+        );
+        let pool = pool.build();
+
+        assert!(matches!(
+            check_maxs(&int, &pool),
+            Err(VerifyError::InvalidLocal {
+                offset: 1,
+                index: 1,
+            })
+        ));
+        assert!(matches!(
+            check_maxs(&long, &pool),
+            Err(VerifyError::InvalidLocal {
+                offset: 1,
+                index: 0,
+            })
+        ));
+        assert!(matches!(
+            check_maxs(&receiver, &pool),
+            Err(VerifyError::TooManyParameters {
+                parameters: 2,
+                max_locals: 1,
+            })
+        ));
+    }
+}
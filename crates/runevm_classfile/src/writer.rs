@@ -0,0 +1,437 @@
+//! Serializes class files back into the bytes `parse_class` reads.
+
+use crate::{
+    annotations::{Annotation, ElementValue, TypeAnnotation, TypeAnnotationTarget},
+    mutf8,
+    parser::{Attribute, RecordComponent},
+    ClassFile, Constant, ConstantPool, ConstantPoolError, ModuleDescriptor,
+};
+
+impl ClassFile {
+    /// Writes the class file out in its binary form, re-encoding the
+    /// constant pool, every attribute, and the instructions of each method.
+    ///
+    /// Attributes don't remember which constant held their name, so it is
+    /// looked up again by value. This fails if the pool has no such Utf8
+    /// constant, which can only happen if the class was modified.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, ConstantPoolError> {
+        let pool = &self.constant_pool;
+        let mut out = vec![0xCA, 0xFE, 0xBA, 0xBE];
+        u16(&mut out, self.version.minor);
+        u16(&mut out, self.version.major);
+
+        u16(&mut out, pool.items.len() as u16 + 1);
+        for constant in &pool.items {
+            write_constant(&mut out, constant);
+        }
+
+        u16(&mut out, self.access_flags.bits());
+        u16(&mut out, self.this_class);
+        u16(&mut out, self.super_class);
+        u16s(&mut out, &self.interfaces);
+
+        u16(&mut out, self.fields.len() as u16);
+        for field in &self.fields {
+            u16(&mut out, field.access_flags.bits());
+            u16(&mut out, field.name_index);
+            u16(&mut out, field.descriptor_index);
+            write_attributes(&mut out, pool, &field.attributes)?;
+        }
+
+        u16(&mut out, self.methods.len() as u16);
+        for method in &self.methods {
+            u16(&mut out, method.access_flags.bits());
+            u16(&mut out, method.name_index);
+            u16(&mut out, method.descriptor_index);
+            write_attributes(&mut out, pool, &method.attributes)?;
+        }
+
+        write_attributes(&mut out, pool, &self.attributes)?;
+
+        Ok(out)
+    }
+}
+
+fn u16(out: &mut Vec<u8>, value: u16) {
+    out.extend(value.to_be_bytes());
+}
+
+/// Writes a u16-length-prefixed list of u16s, like a list of constant pool
+/// indices.
+fn u16s(out: &mut Vec<u8>, values: &[u16]) {
+    u16(out, values.len() as u16);
+    for &value in values {
+        u16(out, value);
+    }
+}
+
+fn write_constant(out: &mut Vec<u8>, constant: &Constant) {
+    let mut pair = |tag: u8, first: u16, second: u16| {
+        out.push(tag);
+        u16(out, first);
+        u16(out, second);
+    };
+
+    match *constant {
+        Constant::Utf8(ref value) => {
+            let bytes = mutf8::encode(value);
+            out.push(1);
+            u16(out, bytes.len() as u16);
+            out.extend(bytes);
+        }
+        Constant::Integer(value) => {
+            out.push(3);
+            out.extend(value.to_be_bytes());
+        }
+        Constant::Float(value) => {
+            out.push(4);
+            out.extend(value.to_be_bytes());
+        }
+        Constant::Long(value) => {
+            out.push(5);
+            out.extend(value.to_be_bytes());
+        }
+        Constant::Double(value) => {
+            out.push(6);
+            out.extend(value.to_be_bytes());
+        }
+        Constant::Class(name_index) => {
+            out.push(7);
+            u16(out, name_index);
+        }
+        Constant::String(string_index) => {
+            out.push(8);
+            u16(out, string_index);
+        }
+        Constant::Field {
+            class_index,
+            nametype_index,
+        } => pair(9, class_index, nametype_index),
+        Constant::Method {
+            class_index,
+            nametype_index,
+        } => pair(10, class_index, nametype_index),
+        Constant::InterfaceMethod {
+            class_index,
+            nametype_index,
+        } => pair(11, class_index, nametype_index),
+        Constant::NameAndType {
+            name_index,
+            descriptor_index,
+        } => pair(12, name_index, descriptor_index),
+        Constant::MethodHandle {
+            reference_kind,
+            reference_index,
+        } => {
+            out.extend([15, reference_kind]);
+            u16(out, reference_index);
+        }
+        Constant::MethodType(descriptor_index) => {
+            out.push(16);
+            u16(out, descriptor_index);
+        }
+        Constant::Dynamic {
+            bootstrap_method_attr_index,
+            nametype_index,
+        } => pair(17, bootstrap_method_attr_index, nametype_index),
+        Constant::InvokeDynamic {
+            bootstrap_method_attr_index,
+            nametype_index,
+        } => pair(18, bootstrap_method_attr_index, nametype_index),
+        Constant::Module(name_index) => {
+            out.push(19);
+            u16(out, name_index);
+        }
+        Constant::Package(name_index) => {
+            out.push(20);
+            u16(out, name_index);
+        }
+        // The second slot of a long or double has no bytes of its own.
+        Constant::Unusable => {}
+    }
+}
+
+fn write_attributes(
+    out: &mut Vec<u8>,
+    pool: &ConstantPool,
+    attributes: &[Attribute],
+) -> Result<(), ConstantPoolError> {
+    u16(out, attributes.len() as u16);
+    for attr in attributes {
+        write_attribute(out, pool, attr)?;
+    }
+    Ok(())
+}
+
+fn write_attribute(
+    out: &mut Vec<u8>,
+    pool: &ConstantPool,
+    attr: &Attribute,
+) -> Result<(), ConstantPoolError> {
+    let name = match attr {
+        Attribute::ConstantValue(_) => "ConstantValue",
+        Attribute::Code { .. } => "Code",
+        Attribute::LineNumberTable(_) => "LineNumberTable",
+        Attribute::Exceptions(_) => "Exceptions",
+        Attribute::InnerClasses(_) => "InnerClasses",
+        Attribute::Synthetic => "Synthetic",
+        Attribute::Deprecated => "Deprecated",
+        Attribute::Signature(_) => "Signature",
+        Attribute::SourceFile(_) => "SourceFile",
+        Attribute::SourceDebugExtension(_) => "SourceDebugExtension",
+        Attribute::RuntimeVisibleAnnotations(_) => "RuntimeVisibleAnnotations",
+        Attribute::RuntimeInvisibleAnnotations(_) => "RuntimeInvisibleAnnotations",
+        Attribute::RuntimeVisibleParameterAnnotations(_) => "RuntimeVisibleParameterAnnotations",
+        Attribute::RuntimeInvisibleParameterAnnotations(_) => {
+            "RuntimeInvisibleParameterAnnotations"
+        }
+        Attribute::RuntimeVisibleTypeAnnotations(_) => "RuntimeVisibleTypeAnnotations",
+        Attribute::RuntimeInvisibleTypeAnnotations(_) => "RuntimeInvisibleTypeAnnotations",
+        Attribute::BootstrapMethods(_) => "BootstrapMethods",
+        Attribute::Module(_) => "Module",
+        Attribute::ModulePackages(_) => "ModulePackages",
+        Attribute::ModuleMainClass(_) => "ModuleMainClass",
+        Attribute::NestHost(_) => "NestHost",
+        Attribute::NestMembers(_) => "NestMembers",
+        Attribute::PermittedSubclasses(_) => "PermittedSubclasses",
+        Attribute::Record(_) => "Record",
+        Attribute::AnnotationDefault(_) => "AnnotationDefault",
+        Attribute::Unknown { name, .. } => name,
+    };
+    let name_index = pool
+        .items
+        .iter()
+        .position(|constant| matches!(constant, Constant::Utf8(value) if value == name))
+        .ok_or(ConstantPoolError::MissingAttributeName)?;
+
+    let mut data = Vec::new();
+    match attr {
+        Attribute::ConstantValue(index)
+        | Attribute::Signature(index)
+        | Attribute::SourceFile(index)
+        | Attribute::ModuleMainClass(index)
+        | Attribute::NestHost(index) => u16(&mut data, *index),
+        Attribute::Code {
+            max_stack,
+            max_locals,
+            code,
+            exceptions,
+            attributes,
+        } => {
+            u16(&mut data, *max_stack);
+            u16(&mut data, *max_locals);
+            let mut bytecode = Vec::new();
+            for (_, inst) in code {
+                inst.encode(&mut bytecode);
+            }
+            data.extend((bytecode.len() as u32).to_be_bytes());
+            data.extend(bytecode);
+            u16(&mut data, exceptions.len() as u16);
+            for entry in exceptions {
+                u16(&mut data, entry.start_pc);
+                u16(&mut data, entry.end_pc);
+                u16(&mut data, entry.handler_pc);
+                u16(&mut data, entry.catch_type);
+            }
+            write_attributes(&mut data, pool, attributes)?;
+        }
+        Attribute::LineNumberTable(entries) => {
+            u16(&mut data, entries.len() as u16);
+            for entry in entries {
+                u16(&mut data, entry.start_pc);
+                u16(&mut data, entry.line_number);
+            }
+        }
+        Attribute::Exceptions(indices)
+        | Attribute::ModulePackages(indices)
+        | Attribute::NestMembers(indices)
+        | Attribute::PermittedSubclasses(indices) => u16s(&mut data, indices),
+        Attribute::InnerClasses(entries) => {
+            u16(&mut data, entries.len() as u16);
+            for entry in entries {
+                u16(&mut data, entry.inner_class_info_index);
+                u16(&mut data, entry.outer_class_info_index);
+                u16(&mut data, entry.inner_name_index);
+                u16(&mut data, entry.access_flags.bits());
+            }
+        }
+        Attribute::Synthetic | Attribute::Deprecated => {}
+        Attribute::SourceDebugExtension(debug) => data.extend(mutf8::encode(debug)),
+        Attribute::RuntimeVisibleAnnotations(annotations)
+        | Attribute::RuntimeInvisibleAnnotations(annotations) => {
+            write_annotations(&mut data, annotations)
+        }
+        Attribute::RuntimeVisibleParameterAnnotations(parameters)
+        | Attribute::RuntimeInvisibleParameterAnnotations(parameters) => {
+            data.push(parameters.len() as u8);
+            for annotations in parameters {
+                write_annotations(&mut data, annotations);
+            }
+        }
+        Attribute::RuntimeVisibleTypeAnnotations(annotations)
+        | Attribute::RuntimeInvisibleTypeAnnotations(annotations) => {
+            u16(&mut data, annotations.len() as u16);
+            for annotation in annotations {
+                write_type_annotation(&mut data, annotation);
+            }
+        }
+        Attribute::BootstrapMethods(entries) => {
+            u16(&mut data, entries.len() as u16);
+            for entry in entries {
+                u16(&mut data, entry.bootstrap_method_ref);
+                u16s(&mut data, &entry.bootstrap_arguments);
+            }
+        }
+        Attribute::Module(module) => write_module(&mut data, module),
+        Attribute::Record(components) => {
+            u16(&mut data, components.len() as u16);
+            for RecordComponent {
+                name_index,
+                descriptor_index,
+                attributes,
+            } in components
+            {
+                u16(&mut data, *name_index);
+                u16(&mut data, *descriptor_index);
+                write_attributes(&mut data, pool, attributes)?;
+            }
+        }
+        Attribute::AnnotationDefault(value) => write_element_value(&mut data, value),
+        Attribute::Unknown { data: raw, .. } => data.extend(raw),
+    }
+
+    u16(out, name_index as u16 + 1);
+    out.extend((data.len() as u32).to_be_bytes());
+    out.extend(data);
+    Ok(())
+}
+
+fn write_annotations(out: &mut Vec<u8>, annotations: &[Annotation]) {
+    u16(out, annotations.len() as u16);
+    for annotation in annotations {
+        write_annotation(out, annotation);
+    }
+}
+
+fn write_annotation(out: &mut Vec<u8>, annotation: &Annotation) {
+    u16(out, annotation.type_index);
+    u16(out, annotation.elements.len() as u16);
+    for (name_index, value) in &annotation.elements {
+        u16(out, *name_index);
+        write_element_value(out, value);
+    }
+}
+
+fn write_element_value(out: &mut Vec<u8>, value: &ElementValue) {
+    let (tag, index) = match *value {
+        ElementValue::Byte(index) => (b'B', index),
+        ElementValue::Char(index) => (b'C', index),
+        ElementValue::Double(index) => (b'D', index),
+        ElementValue::Float(index) => (b'F', index),
+        ElementValue::Int(index) => (b'I', index),
+        ElementValue::Long(index) => (b'J', index),
+        ElementValue::Short(index) => (b'S', index),
+        ElementValue::Boolean(index) => (b'Z', index),
+        ElementValue::String(index) => (b's', index),
+        ElementValue::Class(index) => (b'c', index),
+        ElementValue::Enum {
+            type_name_index,
+            const_name_index,
+        } => {
+            out.push(b'e');
+            u16(out, type_name_index);
+            u16(out, const_name_index);
+            return;
+        }
+        ElementValue::Annotation(ref annotation) => {
+            out.push(b'@');
+            write_annotation(out, annotation);
+            return;
+        }
+        ElementValue::Array(ref values) => {
+            out.push(b'[');
+            u16(out, values.len() as u16);
+            for value in values {
+                write_element_value(out, value);
+            }
+            return;
+        }
+    };
+    out.push(tag);
+    u16(out, index);
+}
+
+fn write_type_annotation(out: &mut Vec<u8>, annotation: &TypeAnnotation) {
+    out.push(annotation.target_type);
+    match annotation.target {
+        TypeAnnotationTarget::TypeParameter { index }
+        | TypeAnnotationTarget::FormalParameter { index } => out.push(index),
+        TypeAnnotationTarget::Supertype { index }
+        | TypeAnnotationTarget::Throws { index }
+        | TypeAnnotationTarget::Catch {
+            exception_table_index: index,
+        }
+        | TypeAnnotationTarget::Offset(index) => u16(out, index),
+        TypeAnnotationTarget::TypeParameterBound {
+            type_parameter_index,
+            bound_index,
+        } => out.extend([type_parameter_index, bound_index]),
+        TypeAnnotationTarget::Empty => {}
+        TypeAnnotationTarget::LocalVariable(ref table) => {
+            u16(out, table.len() as u16);
+            for entry in table {
+                u16(out, entry.start_pc);
+                u16(out, entry.length);
+                u16(out, entry.index);
+            }
+        }
+        TypeAnnotationTarget::TypeArgument {
+            offset,
+            type_argument_index,
+        } => {
+            u16(out, offset);
+            out.push(type_argument_index);
+        }
+    }
+
+    out.push(annotation.type_path.len() as u8);
+    for entry in &annotation.type_path {
+        out.extend([entry.type_path_kind, entry.type_argument_index]);
+    }
+    write_annotation(out, &annotation.annotation);
+}
+
+fn write_module(out: &mut Vec<u8>, module: &ModuleDescriptor) {
+    u16(out, module.name_index);
+    u16(out, module.flags.bits());
+    u16(out, module.version_index);
+
+    u16(out, module.requires.len() as u16);
+    for requires in &module.requires {
+        u16(out, requires.requires_index);
+        u16(out, requires.flags.bits());
+        u16(out, requires.version_index);
+    }
+
+    u16(out, module.exports.len() as u16);
+    for exports in &module.exports {
+        u16(out, exports.exports_index);
+        u16(out, exports.flags.bits());
+        u16s(out, &exports.exports_to);
+    }
+
+    u16(out, module.opens.len() as u16);
+    for opens in &module.opens {
+        u16(out, opens.opens_index);
+        u16(out, opens.flags.bits());
+        u16s(out, &opens.opens_to);
+    }
+
+    u16s(out, &module.uses);
+
+    u16(out, module.provides.len() as u16);
+    for provides in &module.provides {
+        u16(out, provides.provides_index);
+        u16s(out, &provides.provides_with);
+    }
+}
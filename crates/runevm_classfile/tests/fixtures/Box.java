@@ -0,0 +1,7 @@
+public class Box<T extends Number> {
+    T value;
+
+    <U extends T> U get(U fallback) {
+        return fallback;
+    }
+}
@@ -0,0 +1,13 @@
+public class Constants {
+    static final byte BYTE = -8;
+    static final char CHAR = 'c';
+    static final short SHORT = 1600;
+    static final boolean BOOLEAN = true;
+    static final int INT = 123456;
+    static final long LONG = -9_000_000_000L;
+    static final float FLOAT = 1.5f;
+    static final double DOUBLE = 0.25;
+    static final String STRING = "constant";
+    static final Object OBJECT = null;
+    static int counter = 3;
+}
@@ -0,0 +1,44 @@
+import java.io.IOException;
+import java.lang.annotation.Retention;
+import java.lang.annotation.RetentionPolicy;
+import java.util.List;
+
+public class Members {
+    void both() throws IOException, InterruptedException {}
+
+    @Deprecated
+    void old() {}
+
+    @Retention(RetentionPolicy.CLASS)
+    @interface Marker {}
+
+    @Marker
+    void marked() {}
+
+    @Retention(RetentionPolicy.RUNTIME)
+    @interface Named {
+        String value();
+    }
+
+    void named(int first, @Named("second") String second) {}
+
+    @interface Opt {
+        int retries() default 3;
+
+        String name() default "x";
+    }
+
+    Runnable lambda() {
+        return () -> {};
+    }
+
+    int size(List<String> list) {
+        return list.size();
+    }
+
+    int[][][] cube() {
+        return new int[2][3][4];
+    }
+
+    class Inner {}
+}
@@ -0,0 +1,3 @@
+import java.util.List;
+
+public record Point(int x, int y, List<String> labels) {}
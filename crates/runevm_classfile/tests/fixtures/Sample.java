@@ -0,0 +1,40 @@
+import java.io.IOException;
+import java.util.List;
+
+public class Sample<T extends Comparable<T>> {
+    static int one() {
+        return 1;
+    }
+
+    static int sign(int x) {
+        if (x < 0) {
+            return -1;
+        } else {
+            return 1;
+        }
+    }
+
+    static int sum(int n) {
+        int total = 0;
+        for (int i = 0; i < n; i++) {
+            total += i;
+        }
+        return total;
+    }
+
+    static int divide(int a, int b) {
+        try {
+            return a / b;
+        } catch (ArithmeticException e) {
+            return 0;
+        }
+    }
+
+    void read() throws IOException {
+        throw new IOException();
+    }
+
+    List<T> items() {
+        return null;
+    }
+}
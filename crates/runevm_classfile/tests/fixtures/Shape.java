@@ -0,0 +1,5 @@
+public sealed interface Shape permits Circle, Square {}
+
+final class Circle implements Shape {}
+
+final class Square implements Shape {}
@@ -0,0 +1,6 @@
+// module-info.class is javac's output after `jar --create --main-class
+// demo.Main` repackaged it, which adds ModulePackages and ModuleMainClass.
+module demo {
+    requires java.sql;
+    exports demo.api;
+}
@@ -0,0 +1,12 @@
+use runevm_classfile::ClassFile;
+use std::{fs::File, path::Path};
+
+#[test]
+fn reads_a_class_file() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/Sample.class");
+    let class = ClassFile::from_reader(File::open(path).unwrap()).unwrap();
+    assert_eq!(class.name(), Ok("Sample"));
+    assert_eq!(class.super_class_name(), Ok(Some("java/lang/Object")));
+    assert_eq!(class.source_file(), Some("Sample.java"));
+    assert!(class.get_method("divide", "(II)I").is_some());
+}
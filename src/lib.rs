@@ -0,0 +1,6 @@
+//! A Java virtual machine. To embed it, load classes with a
+//! [`ClassLoader`](runtime::classloader::ClassLoader), from a classpath or
+//! from bytes with [`define`](runtime::classloader::ClassLoader::define), and
+//! hand it to a [`Vm`](runtime::vm::Vm) to run a class's `main` method.
+
+pub mod runtime;
@@ -1,33 +1,105 @@
-use crate::runtime::{object::Object, thread::JavaThread};
 use clap::Parser;
-use runevm_classfile::parse_class;
-use std::{fs::File, io::Read, path::PathBuf};
-
-mod runtime;
+use runevm::runtime::{
+    classloader::ClassLoader, frame::FrameError, jar::JarClassSource, trace::StdoutTracer, vm::Vm,
+};
+use std::{
+    env,
+    ffi::OsString,
+    fmt, fs,
+    path::{Path, PathBuf},
+    process,
+};
 
 #[derive(Parser)]
 struct Args {
-    classfile: PathBuf,
+    /// Directories and jars to search for classes, separated by the platform
+    /// path separator. Falls back to the CLASSPATH environment variable.
+    #[arg(long, visible_alias = "cp")]
+    classpath: Option<OsString>,
+    /// Print each instruction as it runs.
+    #[arg(long)]
+    trace: bool,
+    /// A path to a `.class` file, a path to a `.jar` whose manifest names
+    /// its main class, or the binary name of a class to look up on the
+    /// classpath.
+    class: String,
+    /// Arguments passed to the program's `main` method.
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+    args: Vec<String>,
+}
+
+impl Args {
+    fn classpath(&self) -> Vec<PathBuf> {
+        match self.classpath.clone().or_else(|| env::var_os("CLASSPATH")) {
+            Some(paths) => env::split_paths(&paths).collect(),
+            None => vec![PathBuf::from(".")],
+        }
+    }
 }
 
 fn main() {
     let args = Args::parse();
+    let (jars, directories) = args
+        .classpath()
+        .into_iter()
+        .partition(|path| path.extension().is_some_and(|extension| extension == "jar"));
+    let mut class_loader = ClassLoader::new(directories);
+    for path in jars {
+        class_loader.add_jar(open_jar(&path));
+    }
 
-    let mut file = match File::open(args.classfile) {
-        Ok(f) => f,
-        Err(err) => panic!("{err}"),
+    let class = if args.class.ends_with(".class") {
+        let buf = match fs::read(&args.class) {
+            Ok(buf) => buf,
+            Err(err) => fail(format!("could not read {}: {err}", args.class)),
+        };
+        class_loader.define(&buf)
+    } else if args.class.ends_with(".jar") {
+        let mut jar = open_jar(Path::new(&args.class));
+        let name = match jar.main_class() {
+            Ok(Some(name)) => name,
+            Ok(None) => fail(format!("{} has no Main-Class in its manifest", args.class)),
+            Err(err) => fail(format!(
+                "could not read the manifest of {}: {err}",
+                args.class
+            )),
+        };
+        class_loader.add_jar(jar);
+        class_loader.load(&name).map(|_| name)
+    } else {
+        let name = args.class.replace('.', "/");
+        class_loader.load(&name).map(|_| name)
     };
 
-    let mut buf = Vec::new();
-    file.read_to_end(&mut buf)
-        .expect("could not read class file");
-
-    let classfile = match parse_class(buf.as_slice()) {
-        Ok((_, classfile)) => classfile,
-        Err(e) => panic!("{}", e),
+    let class = match class {
+        Ok(class) => class,
+        Err(err) => fail(format!("could not load main class {}: {err}", args.class)),
     };
 
-    let main_method = classfile.get_method("main", "([Ljava/lang/String;)V");
-    let mut thread = JavaThread::new(&classfile.constant_pool, main_method.clone());
-    thread.run();
+    let mut vm = Vm::new(class_loader);
+    if args.trace {
+        vm.tracer = Box::new(StdoutTracer);
+    }
+    match vm.run_main(&class, args.args) {
+        Ok(()) => {}
+        Err(err @ FrameError::UncaughtException { .. }) => {
+            eprintln!("{err}");
+            process::exit(1);
+        }
+        Err(err) => fail(err),
+    }
+}
+
+/// Reports an error that stops the program from running and exits with a
+/// failure status.
+fn fail(message: impl fmt::Display) -> ! {
+    eprintln!("error: {message}");
+    process::exit(1);
+}
+
+fn open_jar(path: &Path) -> JarClassSource {
+    match JarClassSource::open(path) {
+        Ok(jar) => jar,
+        Err(err) => fail(format!("could not open {}: {err}", path.display())),
+    }
 }
@@ -0,0 +1,807 @@
+use super::{frame::OperandItem, heap::Heap, jar::JarClassSource};
+use runevm_classfile::{
+    parse_class_validated, verify, ClassFile, ClassFileError, ConstantPoolError, ConstantValue,
+    FieldAccessFields, MethodAccessFlags, VerifyError,
+};
+use std::{collections::HashMap, fmt, fs, io, path::PathBuf, ptr, rc::Rc};
+use zip::result::ZipError;
+
+/// Loads classes by their binary name from a list of classpath directories
+/// and jar archives, caching them once they have been loaded.
+pub struct ClassLoader {
+    classpath: Vec<PathBuf>,
+    /// Jars searched in order after the classpath directories.
+    jars: Vec<JarClassSource>,
+    /// The loaded classes, which frames share rather than copy.
+    classes: HashMap<String, Rc<ClassFile>>,
+    states: HashMap<String, ClassState>,
+    /// The values of static fields, keyed by declaring class and field name.
+    statics: HashMap<(String, String), OperandItem>,
+}
+
+impl ClassLoader {
+    pub fn new(classpath: Vec<PathBuf>) -> ClassLoader {
+        ClassLoader {
+            classpath,
+            jars: Vec::new(),
+            classes: HashMap::new(),
+            states: HashMap::new(),
+            statics: HashMap::new(),
+        }
+    }
+
+    /// Adds a jar to search for classes in.
+    pub fn add_jar(&mut self, jar: JarClassSource) {
+        self.jars.push(jar);
+    }
+
+    /// Parses a class file and adds it to the loaded classes, returning its
+    /// binary name.
+    pub fn define(&mut self, bytes: &[u8]) -> Result<String, ClassLoadingError> {
+        let classfile = parse_class_validated(bytes).map_err(ClassLoadingError::Parsing)?;
+
+        for method in &classfile.methods {
+            let verified = verify::check_branches(method)
+                .and_then(|_| verify::check_maxs(method, &classfile.constant_pool));
+            if let Err(err) = verified {
+                let pool = &classfile.constant_pool;
+                return Err(ClassLoadingError::Verify {
+                    method: format!(
+                        "{}.{}{}",
+                        classfile.name()?,
+                        pool.utf8(method.name_index)?,
+                        pool.utf8(method.descriptor_index)?
+                    ),
+                    err,
+                });
+            }
+        }
+
+        let name = classfile.name()?.to_owned();
+        self.classes.insert(name.clone(), Rc::new(classfile));
+        self.states.insert(name.clone(), ClassState::Linked);
+        Ok(name)
+    }
+
+    /// Returns the class with the given binary name, loading it from the
+    /// classpath if it hasn't been loaded yet.
+    pub fn load(&mut self, name: &str) -> Result<&Rc<ClassFile>, ClassLoadingError> {
+        if !self.classes.contains_key(name) {
+            let relative = format!("{name}.class");
+            let path = self
+                .classpath
+                .iter()
+                .map(|root| root.join(&relative))
+                .find(|path| path.is_file());
+
+            let bytes = match path {
+                Some(path) => Some(fs::read(path)?),
+                None => self.read_from_jars(name)?,
+            };
+            match bytes {
+                Some(bytes) => self.define(&bytes)?,
+                None => return Err(ClassLoadingError::NotFound(name.to_owned())),
+            };
+        }
+
+        self.classes
+            .get(name)
+            .ok_or_else(|| ClassLoadingError::NotFound(name.to_owned()))
+    }
+
+    fn read_from_jars(&mut self, name: &str) -> Result<Option<Vec<u8>>, ClassLoadingError> {
+        for jar in &mut self.jars {
+            if let Some(bytes) = jar.read_class(name)? {
+                return Ok(Some(bytes));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Finds a method by name and descriptor in a class or its superclasses,
+    /// or failing that, a default method in the interfaces they implement.
+    /// Returns the class that declares it and the method's index in its
+    /// methods.
+    pub fn resolve_method(
+        &mut self,
+        class: &str,
+        name: &str,
+        descriptor: &str,
+    ) -> Result<Option<(Rc<ClassFile>, usize)>, ClassLoadingError> {
+        let mut current = class.to_owned();
+        let mut interfaces = Vec::new();
+
+        loop {
+            // JDK classes aren't on the classpath, so the search ends there.
+            let classfile = match self.load(&current) {
+                Ok(classfile) => classfile,
+                Err(ClassLoadingError::NotFound(_)) => break,
+                Err(err) => return Err(err),
+            };
+            if let Some(method) = method_index(classfile, name, descriptor) {
+                return Ok(Some((Rc::clone(classfile), method)));
+            }
+            interfaces.extend(classfile.interface_names()?.into_iter().map(str::to_owned));
+
+            match classfile.super_class_name()? {
+                Some(super_class) => current = super_class.to_owned(),
+                None => break,
+            }
+        }
+
+        // No class declares the method, so it can only be a default method
+        // inherited from an interface.
+        while let Some(interface) = interfaces.pop() {
+            let classfile = match self.load(&interface) {
+                Ok(classfile) => classfile,
+                Err(ClassLoadingError::NotFound(_)) => continue,
+                Err(err) => return Err(err),
+            };
+            if let Some(method) = method_index(classfile, name, descriptor).filter(|&method| {
+                !classfile.methods[method]
+                    .access_flags
+                    .contains(MethodAccessFlags::ABSTRACT)
+            }) {
+                return Ok(Some((Rc::clone(classfile), method)));
+            }
+            interfaces.extend(classfile.interface_names()?.into_iter().map(str::to_owned));
+        }
+
+        Ok(None)
+    }
+
+    /// Reads a static field of a class or its superclasses, returning `None`
+    /// if there is no such field.
+    pub fn get_static(
+        &mut self,
+        heap: &mut Heap,
+        class: &str,
+        name: &str,
+        descriptor: &str,
+    ) -> Result<Option<OperandItem>, ClassLoadingError> {
+        Ok(self.static_field(heap, class, name, descriptor)?.cloned())
+    }
+
+    /// Writes a static field of a class or its superclasses, returning
+    /// `false` if there is no such field.
+    pub fn put_static(
+        &mut self,
+        heap: &mut Heap,
+        class: &str,
+        name: &str,
+        descriptor: &str,
+        value: OperandItem,
+    ) -> Result<bool, ClassLoadingError> {
+        match self.static_field(heap, class, name, descriptor)? {
+            Some(slot) => {
+                *slot = value;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Finds the storage for a static field, setting it to its initial value
+    /// the first time it is accessed.
+    fn static_field(
+        &mut self,
+        heap: &mut Heap,
+        class: &str,
+        name: &str,
+        descriptor: &str,
+    ) -> Result<Option<&mut OperandItem>, ClassLoadingError> {
+        let mut current = class.to_owned();
+
+        let initial = loop {
+            let classfile = self.load(&current)?;
+            let field = classfile
+                .get_field(name, descriptor)
+                .filter(|field| field.access_flags.contains(FieldAccessFields::STATIC));
+
+            if let Some(field) = field {
+                break match field.constant_value(&classfile.constant_pool)? {
+                    Some(ConstantValue::Int(value)) => OperandItem::Integer(value),
+                    Some(ConstantValue::Float(value)) => OperandItem::Float(value),
+                    Some(ConstantValue::Long(value)) => OperandItem::Long(value),
+                    Some(ConstantValue::Double(value)) => OperandItem::Double(value),
+                    Some(ConstantValue::String(value)) => {
+                        OperandItem::Reference(heap.intern(value))
+                    }
+                    None => OperandItem::zero(descriptor),
+                };
+            }
+
+            match classfile.super_class_name()? {
+                Some(super_class) => current = super_class.to_owned(),
+                None => return Ok(None),
+            }
+        };
+
+        Ok(Some(
+            self.statics
+                .entry((current, name.to_owned()))
+                .or_insert(initial),
+        ))
+    }
+
+    /// Lists the name and descriptor of every instance field declared by a
+    /// class and its superclasses.
+    pub fn instance_fields(
+        &mut self,
+        class: &str,
+    ) -> Result<Vec<(String, String)>, ClassLoadingError> {
+        let mut fields = Vec::new();
+        let mut current = Some(class.to_owned());
+
+        while let Some(class) = current {
+            match self.load(&class) {
+                Ok(classfile) => {
+                    let pool = &classfile.constant_pool;
+                    for field in &classfile.fields {
+                        if !field.access_flags.contains(FieldAccessFields::STATIC) {
+                            fields.push((
+                                pool.utf8(field.name_index)?.to_owned(),
+                                pool.utf8(field.descriptor_index)?.to_owned(),
+                            ));
+                        }
+                    }
+                }
+                Err(ClassLoadingError::NotFound(_)) => fields.extend(
+                    builtin_fields(&class)
+                        .iter()
+                        .map(|&(name, descriptor)| (name.to_owned(), descriptor.to_owned())),
+                ),
+                Err(err) => return Err(err),
+            }
+
+            current = self.super_class(&class)?;
+        }
+
+        Ok(fields)
+    }
+
+    /// Returns the binary name of the superclass of `class`.
+    ///
+    /// Classes that aren't on the classpath fall back to the built-in
+    /// hierarchy of the JDK's exceptions.
+    pub fn super_class(&mut self, class: &str) -> Result<Option<String>, ClassLoadingError> {
+        if class.starts_with('[') {
+            return Ok(Some("java/lang/Object".to_owned()));
+        }
+
+        match self.load(class) {
+            Ok(classfile) => Ok(classfile.super_class_name()?.map(str::to_owned)),
+            Err(ClassLoadingError::NotFound(_)) => {
+                Ok(builtin_super_class(class).map(str::to_owned))
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Checks whether `class` is `ancestor` or one of its subclasses.
+    pub fn is_subclass(&mut self, class: &str, ancestor: &str) -> Result<bool, ClassLoadingError> {
+        let mut current = Some(class.to_owned());
+
+        while let Some(class) = current {
+            if class == ancestor {
+                return Ok(true);
+            }
+            current = self.super_class(&class)?;
+        }
+
+        Ok(false)
+    }
+
+    fn state(&self, class: &str) -> ClassState {
+        self.states
+            .get(class)
+            .copied()
+            .unwrap_or(ClassState::Unloaded)
+    }
+
+    /// Starts initializing a class, superclasses first. Returns the next
+    /// static initializer that needs to run, along with the class it
+    /// belongs to, or `None` once there is nothing left to run.
+    ///
+    /// A class whose initializer is already running counts as initialized,
+    /// which keeps initializers that refer back to their own class from
+    /// recursing forever. A class whose initializer threw can't be used
+    /// again, and is reported as `Erroneous`.
+    pub fn initialize(
+        &mut self,
+        class: &str,
+    ) -> Result<Option<(String, Rc<ClassFile>, usize)>, ClassLoadingError> {
+        let mut chain = Vec::new();
+        let mut current = Some(class.to_owned());
+        while let Some(class) = current {
+            match self.state(&class) {
+                ClassState::Initializing | ClassState::Initialized => break,
+                ClassState::Erroneous => return Err(ClassLoadingError::Erroneous(class)),
+                ClassState::Unloaded | ClassState::Linked => {}
+            }
+
+            // Classes that aren't on the classpath have nothing to run.
+            let classfile = match self.load(&class) {
+                Ok(classfile) => classfile,
+                Err(ClassLoadingError::NotFound(_)) => break,
+                Err(err) => return Err(err),
+            };
+            current = classfile.super_class_name()?.map(str::to_owned);
+            chain.push(class);
+        }
+
+        for class in chain.into_iter().rev() {
+            self.states.insert(class.clone(), ClassState::Initializing);
+            let classfile = self.load(&class)?;
+            if let Some(clinit) = method_index(classfile, "<clinit>", "()V") {
+                let classfile = Rc::clone(classfile);
+                return Ok(Some((class, classfile, clinit)));
+            }
+            self.states.insert(class, ClassState::Initialized);
+        }
+
+        Ok(None)
+    }
+
+    /// Records that the static initializer of a class has returned.
+    pub fn finish_initialization(&mut self, class: &str) {
+        self.states
+            .insert(class.to_owned(), ClassState::Initialized);
+    }
+
+    /// Records that the static initializer of a class threw an exception.
+    pub fn fail_initialization(&mut self, class: &str) {
+        self.states.insert(class.to_owned(), ClassState::Erroneous);
+    }
+}
+
+/// How far along a class is in being made ready for use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClassState {
+    /// The class hasn't been read from the classpath yet.
+    Unloaded,
+    /// The class has been parsed and verified, but its static initializer
+    /// hasn't run.
+    Linked,
+    /// The class's static initializer is running.
+    Initializing,
+    /// The class is ready for use.
+    Initialized,
+    /// The class's static initializer threw an exception, so the class
+    /// can't be used.
+    Erroneous,
+}
+
+/// The index in `classfile.methods` of the method with the given name and
+/// descriptor, which is how frames refer to the method they run.
+fn method_index(classfile: &ClassFile, name: &str, descriptor: &str) -> Option<usize> {
+    let method = classfile.get_method(name, descriptor)?;
+    classfile
+        .methods
+        .iter()
+        .position(|candidate| ptr::eq(candidate, method))
+}
+
+/// The instance fields of JDK classes that natives read and write.
+fn builtin_fields(name: &str) -> &'static [(&'static str, &'static str)] {
+    match name {
+        "java/lang/Throwable" => &[("detailMessage", "Ljava/lang/String;")],
+        _ => &[],
+    }
+}
+
+/// The superclasses of the JDK's throwables, which exception handlers need
+/// even though the classes themselves can't be loaded.
+fn builtin_super_class(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "java/lang/Throwable" | "java/lang/String" | "java/lang/Class" => "java/lang/Object",
+        "java/lang/Exception" | "java/lang/Error" => "java/lang/Throwable",
+        "java/lang/RuntimeException"
+        | "java/lang/ReflectiveOperationException"
+        | "java/lang/InterruptedException"
+        | "java/lang/CloneNotSupportedException"
+        | "java/io/IOException" => "java/lang/Exception",
+        "java/lang/ArithmeticException"
+        | "java/lang/ArrayStoreException"
+        | "java/lang/ClassCastException"
+        | "java/lang/IllegalArgumentException"
+        | "java/lang/IllegalMonitorStateException"
+        | "java/lang/IllegalStateException"
+        | "java/lang/IndexOutOfBoundsException"
+        | "java/lang/NegativeArraySizeException"
+        | "java/lang/NullPointerException"
+        | "java/lang/UnsupportedOperationException"
+        | "java/io/UncheckedIOException" => "java/lang/RuntimeException",
+        "java/lang/ArrayIndexOutOfBoundsException"
+        | "java/lang/StringIndexOutOfBoundsException" => "java/lang/IndexOutOfBoundsException",
+        "java/lang/NumberFormatException" => "java/lang/IllegalArgumentException",
+        "java/lang/ClassNotFoundException"
+        | "java/lang/IllegalAccessException"
+        | "java/lang/InstantiationException"
+        | "java/lang/NoSuchFieldException"
+        | "java/lang/NoSuchMethodException" => "java/lang/ReflectiveOperationException",
+        "java/io/FileNotFoundException" => "java/io/IOException",
+        "java/lang/LinkageError" | "java/lang/VirtualMachineError" | "java/lang/AssertionError" => {
+            "java/lang/Error"
+        }
+        "java/lang/StackOverflowError" | "java/lang/OutOfMemoryError" => {
+            "java/lang/VirtualMachineError"
+        }
+        "java/lang/NoClassDefFoundError"
+        | "java/lang/ExceptionInInitializerError"
+        | "java/lang/IncompatibleClassChangeError" => "java/lang/LinkageError",
+        "java/lang/NoSuchFieldError"
+        | "java/lang/NoSuchMethodError"
+        | "java/lang/AbstractMethodError" => "java/lang/IncompatibleClassChangeError",
+        _ => return None,
+    })
+}
+
+#[derive(Debug)]
+pub enum ClassLoadingError {
+    /// No class file with the given name was found on the classpath.
+    NotFound(String),
+    Io(io::Error),
+    /// A jar on the classpath could not be read.
+    Jar(ZipError),
+    Parsing(ClassFileError),
+    ConstantPool(ConstantPoolError),
+    /// A method's code could overrun its operand stack or local variables.
+    Verify {
+        method: String,
+        err: VerifyError,
+    },
+    /// The static initializer of the class with the given name threw an
+    /// exception when it ran before.
+    Erroneous(String),
+}
+
+impl From<io::Error> for ClassLoadingError {
+    fn from(err: io::Error) -> Self {
+        ClassLoadingError::Io(err)
+    }
+}
+
+impl From<ZipError> for ClassLoadingError {
+    fn from(err: ZipError) -> Self {
+        ClassLoadingError::Jar(err)
+    }
+}
+
+impl From<ConstantPoolError> for ClassLoadingError {
+    fn from(err: ConstantPoolError) -> Self {
+        ClassLoadingError::ConstantPool(err)
+    }
+}
+
+impl fmt::Display for ClassLoadingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClassLoadingError::NotFound(name) => write!(f, "could not find class {name}"),
+            ClassLoadingError::Io(err) => write!(f, "{err}"),
+            ClassLoadingError::Jar(err) => write!(f, "{err}"),
+            ClassLoadingError::Parsing(err) => write!(f, "{err}"),
+            ClassLoadingError::ConstantPool(err) => write!(f, "{err}"),
+            ClassLoadingError::Verify { method, err } => {
+                write!(f, "method {method} failed verification: {err}")
+            }
+            ClassLoadingError::Erroneous(name) => {
+                write!(f, "class {name} failed to initialize")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::{
+        testing::{with_offsets, ClassBuilder},
+        vm::Vm,
+    };
+    use runevm_classfile::{ExceptionTableEntry, Instruction, Instruction::*};
+    use std::{env, io::Write, process};
+    use zip::{write::FileOptions, CompressionMethod, ZipWriter};
+
+    #[test]
+    fn loads_a_called_class_from_the_classpath() {
+        let mut main = ClassBuilder::new("Main");
+        let twice = main.pool.add_method_ref("Util", "twice", "(I)I").unwrap();
+        let result = main.pool.add_field_ref("Main", "result", "I").unwrap();
+        let main = main
+            .field(
+                FieldAccessFields::PUBLIC | FieldAccessFields::STATIC,
+                "result",
+                "I",
+            )
+            .method(
+                "main",
+                "([Ljava/lang/String;)V",
+                (1, 1),
+                &[Bipush(21), Invokestatic(twice), Putstatic(result), Return],
+            )
+            .build();
+        let util = ClassBuilder::new("Util")
+            .method("twice", "(I)I", (2, 1), &[Iload(0), Iconst2, Imul, Ireturn])
+            .build();
+
+        let classpath = env::temp_dir().join(format!("runevm-classpath-{}", process::id()));
+        fs::create_dir_all(&classpath).unwrap();
+        fs::write(classpath.join("Main.class"), main).unwrap();
+        fs::write(classpath.join("Util.class"), util).unwrap();
+
+        let mut vm = Vm::new(ClassLoader::new(vec![classpath.clone()]));
+        let ran = vm.run_main("Main", Vec::new());
+        fs::remove_dir_all(&classpath).unwrap();
+        ran.unwrap();
+        assert_eq!(
+            vm.class_loader
+                .get_static(&mut vm.heap, "Main", "result", "I")
+                .unwrap(),
+            Some(OperandItem::Integer(42))
+        );
+    }
+
+    #[test]
+    fn runs_a_class_from_a_jar() {
+        let mut main = ClassBuilder::new("app/Main");
+        let twice = main
+            .pool
+            .add_method_ref("app/util/Util", "twice", "(I)I")
+            .unwrap();
+        let result = main.pool.add_field_ref("app/Main", "result", "I").unwrap();
+        let main = main
+            .field(
+                FieldAccessFields::PUBLIC | FieldAccessFields::STATIC,
+                "result",
+                "I",
+            )
+            .method(
+                "main",
+                "([Ljava/lang/String;)V",
+                (1, 1),
+                &[Bipush(21), Invokestatic(twice), Putstatic(result), Return],
+            )
+            .build();
+        let util = ClassBuilder::new("app/util/Util")
+            .method("twice", "(I)I", (2, 1), &[Iload(0), Iconst2, Imul, Ireturn])
+            .build();
+
+        let path = env::temp_dir().join(format!("runevm-jar-{}.jar", process::id()));
+        let mut jar = ZipWriter::new(fs::File::create(&path).unwrap());
+        let stored = FileOptions::default().compression_method(CompressionMethod::Stored);
+        for (entry, bytes) in [
+            (
+                "META-INF/MANIFEST.MF",
+                &b"Manifest-Version: 1.0\r\nMain-Class: app.Main\r\n"[..],
+            ),
+            ("app/Main.class", &main),
+            ("app/util/Util.class", &util),
+        ] {
+            jar.start_file(entry, stored).unwrap();
+            jar.write_all(bytes).unwrap();
+        }
+        jar.finish().unwrap();
+
+        let mut source = JarClassSource::open(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(source.main_class().unwrap().as_deref(), Some("app/Main"));
+        assert_eq!(source.read_class("app/Missing").unwrap(), None);
+
+        let mut class_loader = ClassLoader::new(Vec::new());
+        class_loader.add_jar(source);
+        let mut vm = Vm::new(class_loader);
+        vm.run_main("app/Main", Vec::new()).unwrap();
+        assert_eq!(
+            vm.class_loader
+                .get_static(&mut vm.heap, "app/Main", "result", "I")
+                .unwrap(),
+            Some(OperandItem::Integer(42))
+        );
+    }
+
+    #[test]
+    fn static_final_fields_start_at_their_constant_value() {
+        let mut class = ClassBuilder::new("Limits");
+        let max = class.pool.add_integer(100).unwrap();
+        let class = class
+            .constant_field(
+                FieldAccessFields::STATIC | FieldAccessFields::FINAL,
+                "MAX",
+                "I",
+                max,
+            )
+            .build();
+
+        let mut vm = Vm::new(ClassLoader::new(Vec::new()));
+        vm.class_loader.define(&class).unwrap();
+        assert_eq!(
+            vm.class_loader
+                .get_static(&mut vm.heap, "Limits", "MAX", "I")
+                .unwrap(),
+            Some(OperandItem::Integer(100))
+        );
+    }
+
+    /// A class whose static initializer counts how often it runs in `inits`
+    /// and then runs `rest`, which is given the field reference of `value`.
+    /// Its `get()I` method reads `value`.
+    fn counted_initializer(name: &str, rest: fn(u16) -> Vec<Instruction>) -> Vec<u8> {
+        let mut class = ClassBuilder::new(name);
+        let inits = class.pool.add_field_ref(name, "inits", "I").unwrap();
+        let value = class.pool.add_field_ref(name, "value", "I").unwrap();
+        let mut clinit = vec![Getstatic(inits), Iconst1, Iadd, Putstatic(inits)];
+        clinit.extend(rest(value));
+        let statics = FieldAccessFields::PUBLIC | FieldAccessFields::STATIC;
+        class
+            .field(statics, "inits", "I")
+            .field(statics, "value", "I")
+            .method("<clinit>", "()V", (2, 0), &clinit)
+            .method("get", "()I", (1, 0), &[Getstatic(value), Ireturn])
+            .build()
+    }
+
+    fn get_static(vm: &mut Vm, class: &str, name: &str) -> Option<OperandItem> {
+        vm.class_loader
+            .get_static(&mut vm.heap, class, name, "I")
+            .unwrap()
+    }
+
+    #[test]
+    fn static_initializer_runs_once_before_first_use() {
+        let config =
+            counted_initializer("Config", |value| vec![Bipush(21), Putstatic(value), Return]);
+        let mut main = ClassBuilder::new("Main");
+        let value = main.pool.add_field_ref("Config", "value", "I").unwrap();
+        let get = main.pool.add_method_ref("Config", "get", "()I").unwrap();
+        let result = main.pool.add_field_ref("Main", "result", "I").unwrap();
+        let main = main
+            .field(
+                FieldAccessFields::PUBLIC | FieldAccessFields::STATIC,
+                "result",
+                "I",
+            )
+            .method(
+                "main",
+                "([Ljava/lang/String;)V",
+                (2, 1),
+                &[
+                    Getstatic(value),
+                    Invokestatic(get),
+                    Iadd,
+                    Putstatic(result),
+                    Return,
+                ],
+            )
+            .build();
+
+        let mut vm = Vm::new(ClassLoader::new(Vec::new()));
+        vm.class_loader.define(&main).unwrap();
+        vm.class_loader.define(&config).unwrap();
+        assert_eq!(vm.class_loader.state("Config"), ClassState::Linked);
+        vm.run_main("Main", Vec::new()).unwrap();
+        assert_eq!(vm.class_loader.state("Config"), ClassState::Initialized);
+        assert_eq!(
+            get_static(&mut vm, "Config", "inits"),
+            Some(OperandItem::Integer(1))
+        );
+        assert_eq!(
+            get_static(&mut vm, "Main", "result"),
+            Some(OperandItem::Integer(42))
+        );
+    }
+
+    #[test]
+    fn class_whose_initializer_threw_is_unusable() {
+        let bad = counted_initializer("Bad", |_| vec![Iconst1, Iconst0, Idiv, Pop, Return]);
+        let mut main = ClassBuilder::new("Main");
+        let get = main.pool.add_method_ref("Bad", "get", "()I").unwrap();
+        let caught = main.pool.add_field_ref("Main", "caught", "I").unwrap();
+        let no_class_def = main
+            .pool
+            .add_class("java/lang/NoClassDefFoundError")
+            .unwrap();
+        // try { Bad.get(); } catch (Throwable e) {}
+        // try { Bad.get(); } catch (NoClassDefFoundError e) { caught = 1; }
+        let code = [
+            Invokestatic(get),
+            Pop,
+            Goto(4),
+            Pop,
+            Invokestatic(get),
+            Pop,
+            Return,
+            Pop,
+            Iconst1,
+            Putstatic(caught),
+            Return,
+        ];
+        let offsets: Vec<u16> = with_offsets(&code)
+            .iter()
+            .map(|&(offset, _)| offset as u16)
+            .collect();
+        let handlers = vec![
+            ExceptionTableEntry {
+                start_pc: offsets[0],
+                end_pc: offsets[1],
+                handler_pc: offsets[3],
+                catch_type: 0,
+            },
+            ExceptionTableEntry {
+                start_pc: offsets[4],
+                end_pc: offsets[5],
+                handler_pc: offsets[7],
+                catch_type: no_class_def,
+            },
+        ];
+        let main = main
+            .field(
+                FieldAccessFields::PUBLIC | FieldAccessFields::STATIC,
+                "caught",
+                "I",
+            )
+            .method_with_handlers("main", "([Ljava/lang/String;)V", (2, 1), &code, handlers)
+            .build();
+
+        let mut vm = Vm::new(ClassLoader::new(Vec::new()));
+        vm.class_loader.define(&main).unwrap();
+        vm.class_loader.define(&bad).unwrap();
+        vm.run_main("Main", Vec::new()).unwrap();
+        assert_eq!(vm.class_loader.state("Bad"), ClassState::Erroneous);
+        assert_eq!(
+            get_static(&mut vm, "Bad", "inits"),
+            Some(OperandItem::Integer(1))
+        );
+        assert_eq!(
+            get_static(&mut vm, "Main", "caught"),
+            Some(OperandItem::Integer(1))
+        );
+    }
+
+    #[test]
+    fn called_class_is_loaded_and_initialized_once_on_first_call() {
+        let b = counted_initializer("B", |value| vec![Bipush(7), Putstatic(value), Return]);
+        let mut a = ClassBuilder::new("A");
+        let get = a.pool.add_method_ref("B", "get", "()I").unwrap();
+        let result = a.pool.add_field_ref("A", "result", "I").unwrap();
+        let a = a
+            .field(
+                FieldAccessFields::PUBLIC | FieldAccessFields::STATIC,
+                "result",
+                "I",
+            )
+            .method(
+                "main",
+                "([Ljava/lang/String;)V",
+                (2, 1),
+                &[
+                    Invokestatic(get),
+                    Invokestatic(get),
+                    Iadd,
+                    Putstatic(result),
+                    Return,
+                ],
+            )
+            .build();
+
+        let classpath = env::temp_dir().join(format!("runevm-lazy-{}", process::id()));
+        fs::create_dir_all(&classpath).unwrap();
+        fs::write(classpath.join("B.class"), b).unwrap();
+        let mut vm = Vm::new(ClassLoader::new(vec![classpath.clone()]));
+        vm.class_loader.define(&a).unwrap();
+        assert_eq!(vm.class_loader.state("B"), ClassState::Unloaded);
+        let ran = vm.run_main("A", Vec::new());
+        fs::remove_dir_all(&classpath).unwrap();
+        ran.unwrap();
+
+        assert_eq!(vm.class_loader.state("B"), ClassState::Initialized);
+        assert_eq!(
+            get_static(&mut vm, "B", "inits"),
+            Some(OperandItem::Integer(1))
+        );
+        assert_eq!(
+            get_static(&mut vm, "A", "result"),
+            Some(OperandItem::Integer(14))
+        );
+    }
+}
@@ -1,73 +1,2927 @@
-use super::object::Object;
-use runevm_classfile::{Constant, ConstantPool, Instruction, Method};
-
-macro_rules! unwrap_constant {
-    ($cp:expr, $method:ident, $idx:expr) => {{
-        let constant = $cp.$method($idx);
-        ($cp.class(constant.0), $cp.name_and_type(constant.1))
-    }};
-}
+use super::{
+    classloader::{ClassLoader, ClassLoadingError},
+    heap::{Heap, Reference},
+    natives::{format_double, format_float},
+    object::{Array, Elements, Object},
+    vm::Vm,
+};
+use runevm_classfile::{
+    descriptor::{FieldType, MethodDescriptor},
+    pretty::{self, Names},
+    ClassFile, ComparisonKind, Constant, ConstantPoolError, DescriptorError, Instruction, Method,
+    MethodAccessFlags,
+};
+use std::{fmt, rc::Rc};
 
 pub struct Frame {
-    constant_pool: ConstantPool,
-    method: Method,
+    /// The class declaring the method, which is shared with the class loader
+    /// and every other frame running one of its methods.
+    class: Rc<ClassFile>,
+    /// The index of the method in the class's methods.
+    method: usize,
     pc: usize,
+    /// The offset of the instruction being executed, which stays put while a
+    /// method it invoked is running.
+    current_pc: usize,
+    locals: Vec<OperandItem>,
     operand_stack: Vec<OperandItem>,
+    max_stack: usize,
+    /// The class this frame is running the static initializer of, if any.
+    initializing: Option<String>,
+    /// The receiver of a synchronized method, or the mirror of its class if
+    /// it's static, whose monitor it holds while it runs.
+    locked: Option<Reference>,
+    /// The monitors `monitorenter` has entered in this frame and
+    /// `monitorexit` hasn't exited yet, innermost last.
+    monitors: Vec<Reference>,
 }
 
 impl Frame {
-    pub fn new(constant_pool: &ConstantPool, method: Method) -> Frame {
-        Frame {
-            constant_pool: constant_pool.clone(),
+    /// Creates a frame for `method`, with `arguments` copied into the first
+    /// local variable slots. There must be no more argument slots than the
+    /// method has locals.
+    pub fn new(
+        class: Rc<ClassFile>,
+        method: usize,
+        arguments: Vec<OperandItem>,
+    ) -> Result<Frame, FrameError> {
+        let max_locals = class.methods[method].max_locals();
+        if arguments.len() > max_locals as usize {
+            let pool = &class.constant_pool;
+            return Err(FrameError::TooManyArguments {
+                class: class.name()?.to_owned(),
+                name: pool.utf8(class.methods[method].name_index)?.to_owned(),
+                descriptor: pool
+                    .utf8(class.methods[method].descriptor_index)?
+                    .to_owned(),
+                arguments: arguments.len(),
+                max_locals,
+            });
+        }
+        let mut locals = arguments;
+        locals.resize(max_locals as usize, OperandItem::Padding);
+
+        Ok(Frame {
+            max_stack: class.methods[method].max_stack() as usize,
+            class,
             method,
             pc: 0,
+            current_pc: 0,
+            locals,
             operand_stack: Vec::new(),
+            initializing: None,
+            locked: None,
+            monitors: Vec::new(),
+        })
+    }
+
+    /// Creates a frame for the static initializer of `class`, which marks the
+    /// class as initialized when it returns.
+    pub fn initializer(
+        name: String,
+        class: Rc<ClassFile>,
+        clinit: usize,
+    ) -> Result<Frame, FrameError> {
+        let mut frame = Frame::new(class, clinit, Vec::new())?;
+        frame.initializing = Some(name);
+        Ok(frame)
+    }
+
+    /// Exits every monitor this frame still holds, for when its method
+    /// completes, whether by returning or by throwing.
+    pub fn release_monitors(&mut self, heap: &mut Heap) {
+        self.exit_entered_monitors(heap);
+        if let Some(receiver) = self.locked.take() {
+            heap.exit_monitor(receiver);
+        }
+    }
+
+    /// Exits the monitors `monitorenter` entered in this frame, returning
+    /// whether there were any.
+    fn exit_entered_monitors(&mut self, heap: &mut Heap) -> bool {
+        let held = !self.monitors.is_empty();
+        for monitor in self.monitors.drain(..).rev() {
+            heap.exit_monitor(monitor);
         }
+        held
+    }
+
+    /// The class declaring the method this frame is running.
+    pub fn class(&self) -> &ClassFile {
+        &self.class
     }
 
-    pub fn execute(&mut self) -> FrameResult {
-        let code = self.method.code();
+    pub fn method(&self) -> &Method {
+        &self.class.methods[self.method]
+    }
 
-        while self.pc < code.len() {
-            let inst = code[self.pc];
-            print!("{:?} ", code[self.pc]);
+    /// Describes where this frame is in its method, for a stack trace.
+    pub fn stack_trace_element(&self) -> Result<StackTraceElement, FrameError> {
+        let pool = &self.class.constant_pool;
+        Ok(StackTraceElement {
+            class: self.class.name()?.to_owned(),
+            method: pool.utf8(self.method().name_index)?.to_owned(),
+            source_file: self.class.source_file().map(str::to_owned),
+            line: self.method().line_number(self.current_pc as u32),
+            pc: self.current_pc,
+        })
+    }
+
+    pub fn execute(&mut self, vm: &mut Vm) -> Result<FrameResult, FrameError> {
+        loop {
+            let code = match self.method().code() {
+                Some(code) => code,
+                None => {
+                    let pool = &self.class.constant_pool;
+                    return Err(FrameError::NoCode {
+                        class: self.class.name()?.to_owned(),
+                        name: pool.utf8(self.method().name_index)?.to_owned(),
+                        descriptor: pool.utf8(self.method().descriptor_index)?.to_owned(),
+                    });
+                }
+            };
+            // Methods have to return or throw, so the pc can only run off
+            // the end of code that wasn't verified.
+            let index = match instruction_index(code, self.pc) {
+                Some(index) => index,
+                None => {
+                    let pool = &self.class.constant_pool;
+                    return Err(FrameError::FellOffEnd {
+                        class: self.class.name()?.to_owned(),
+                        name: pool.utf8(self.method().name_index)?.to_owned(),
+                        descriptor: pool.utf8(self.method().descriptor_index)?.to_owned(),
+                    });
+                }
+            };
+            let inst = code[index].1.canonical();
+            // Falling off the end leaves the pc past the last instruction.
+            let next_pc = code
+                .get(index + 1)
+                .map_or(usize::MAX, |(offset, _)| *offset as usize);
+            self.current_pc = self.pc;
+            vm.tracer.on_instruction(self, self.current_pc, &inst);
 
             match inst {
-                Instruction::Getstatic(index) => {
-                    let (class, name_and_type) = unwrap_constant!(self.constant_pool, field, index);
-                    print!("{} {} {}", class, name_and_type.0, name_and_type.1);
+                Instruction::Getstatic(index) | Instruction::Putstatic(index) => {
+                    let field = self.class.constant_pool.field_ref(index)?;
+                    let (class, name, descriptor) = (
+                        field.class.to_owned(),
+                        field.name.to_owned(),
+                        field.descriptor.to_owned(),
+                    );
+
+                    let system_out = (class.as_str(), name.as_str()) == ("java/lang/System", "out");
+                    if system_out && matches!(inst, Instruction::Getstatic(_)) {
+                        self.push(OperandItem::SystemOut)?;
+                    } else {
+                        match self.initialize(vm, &class)? {
+                            Initialization::Ready => {}
+                            Initialization::Pending(next) => return Ok(next),
+                            Initialization::Caught => continue,
+                        }
+
+                        if let Instruction::Putstatic(_) = inst {
+                            let value = self.pop_value()?;
+                            if !vm.class_loader.put_static(
+                                &mut vm.heap,
+                                &class,
+                                &name,
+                                &descriptor,
+                                value,
+                            )? {
+                                return Err(no_such_field(&class, (name, descriptor)));
+                            }
+                        } else {
+                            let value = vm
+                                .class_loader
+                                .get_static(&mut vm.heap, &class, &name, &descriptor)?
+                                .ok_or_else(|| no_such_field(&class, (name, descriptor)))?;
+                            self.push_value(value)?;
+                        }
+                    }
                 }
-                Instruction::Ldc(index) => match self.constant_pool.get(index as u16) {
-                    Constant::String(string_index) => {
-                        print!("\"{}\"", self.constant_pool.utf8(*string_index));
+                Instruction::Ldc(index) => self.load_constant(index as u16, &mut vm.heap)?,
+                Instruction::Ldcw(index) => self.load_constant(index, &mut vm.heap)?,
+                Instruction::Ldc2w(index) => match self.class.constant_pool.at(index)? {
+                    Constant::Long(_) => self.push_long(self.class.constant_pool.long(index)?)?,
+                    Constant::Double(_) => {
+                        let value = self.class.constant_pool.double(index)?;
+                        self.push_double(value)?;
                     }
-                    _ => todo!(),
+                    _ => return Err(FrameError::TypeMismatch),
                 },
-                Instruction::Invokevirtual(index) => {
-                    let (class, name_and_type) = unwrap_constant!(self.constant_pool, method, index);
-                    print!("{} {} {}", class, name_and_type.0, name_and_type.1);
+                Instruction::Invokevirtual(index)
+                | Instruction::Invokespecial(index)
+                | Instruction::Invokestatic(index)
+                | Instruction::Invokeinterface(index, _) => {
+                    let is_static = matches!(inst, Instruction::Invokestatic(_));
+                    let method = match self.class.constant_pool.at(index)? {
+                        Constant::InterfaceMethod { .. } => {
+                            self.class.constant_pool.interface_method_ref(index)?
+                        }
+                        _ => self.class.constant_pool.method_ref(index)?,
+                    };
+                    let (mut class, name, descriptor) = (
+                        method.class.to_owned(),
+                        method.name.to_owned(),
+                        method.descriptor.to_owned(),
+                    );
+
+                    // Virtual calls are dispatched on the receiver's runtime
+                    // class, so overriding methods are found first. Special
+                    // calls keep the resolved class but still need a receiver.
+                    if !is_static {
+                        match self.peek(parameter_slots(&descriptor)?)? {
+                            OperandItem::Reference(receiver) => {
+                                if let Instruction::Invokevirtual(_)
+                                | Instruction::Invokeinterface(..) = inst
+                                {
+                                    class = vm.heap.get(*receiver).class_name().to_owned();
+                                }
+                            }
+                            OperandItem::Null => {
+                                match self.throw("java/lang/NullPointerException", None, vm)? {
+                                    Some(thrown) => return Ok(thrown),
+                                    None => continue,
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+
+                    if is_static {
+                        match self.initialize(vm, &class)? {
+                            Initialization::Ready => {}
+                            Initialization::Pending(next) => return Ok(next),
+                            Initialization::Caught => continue,
+                        }
+                    }
+
+                    let next = self.invoke(vm, (&class, &name, &descriptor), is_static, next_pc)?;
+                    if let Some(next) = next {
+                        return Ok(next);
+                    }
+                }
+                Instruction::Invokedynamic(index) => {
+                    let class = Rc::clone(&self.class);
+                    let pool = &class.constant_pool;
+                    let (bootstrap_index, descriptor) = match *pool.at(index)? {
+                        Constant::InvokeDynamic {
+                            bootstrap_method_attr_index,
+                            nametype_index,
+                        } => (
+                            bootstrap_method_attr_index,
+                            pool.name_and_type(nametype_index)?.1,
+                        ),
+                        _ => return Err(FrameError::TypeMismatch),
+                    };
+                    let bootstrap = class
+                        .bootstrap_method(bootstrap_index)?
+                        .ok_or(FrameError::TypeMismatch)?;
+                    let factory = &bootstrap.method.member;
+
+                    // Only string concatenation is supported, which is what
+                    // javac compiles `+` on strings to.
+                    let types = MethodDescriptor::parse(descriptor)?.parameters;
+                    let (recipe, constants) = match (factory.class, factory.name) {
+                        ("java/lang/invoke/StringConcatFactory", "makeConcat") => {
+                            ("\u{1}".repeat(types.len()), &[][..])
+                        }
+                        ("java/lang/invoke/StringConcatFactory", "makeConcatWithConstants") => {
+                            match bootstrap.arguments.split_first() {
+                                Some((Constant::String(recipe), constants)) => {
+                                    (pool.utf8(*recipe)?.to_owned(), constants)
+                                }
+                                _ => return Err(FrameError::TypeMismatch),
+                            }
+                        }
+                        (class, name) => {
+                            return Err(FrameError::UnsupportedBootstrapMethod {
+                                class: class.to_owned(),
+                                name: name.to_owned(),
+                            });
+                        }
+                    };
+                    let constants = constants
+                        .iter()
+                        .map(|constant| match **constant {
+                            Constant::String(index) => Ok(pool.utf8(index)?.to_owned()),
+                            Constant::Integer(value) => Ok(value.to_string()),
+                            Constant::Long(value) => Ok(value.to_string()),
+                            Constant::Float(value) => Ok(format_float(value)),
+                            Constant::Double(value) => Ok(format_double(value)),
+                            _ => Err(FrameError::TypeMismatch),
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
+
+                    let args = self.pop_arguments(parameter_slots(descriptor)?)?;
+                    let value = concat(&recipe, &types, &args, &constants, &vm.heap)?;
+                    let string = vm.heap.allocate_string(value);
+                    self.push(OperandItem::Reference(string))?;
+                }
+                Instruction::Return
+                | Instruction::Ireturn
+                | Instruction::Lreturn
+                | Instruction::Freturn
+                | Instruction::Dreturn
+                | Instruction::Areturn => {
+                    // A method must exit every monitor it enters before
+                    // returning.
+                    if self.exit_entered_monitors(&mut vm.heap) {
+                        match self.throw("java/lang/IllegalMonitorStateException", None, vm)? {
+                            Some(thrown) => return Ok(thrown),
+                            None => continue,
+                        }
+                    }
+
+                    let value = match inst {
+                        Instruction::Ireturn => OperandItem::Integer(self.pop_int()?),
+                        Instruction::Lreturn => OperandItem::Long(self.pop_long()?),
+                        Instruction::Freturn => OperandItem::Float(self.pop_float()?),
+                        Instruction::Dreturn => OperandItem::Double(self.pop_double()?),
+                        Instruction::Areturn => match self.pop()? {
+                            value @ (OperandItem::Reference(_) | OperandItem::Null) => value,
+                            _ => return Err(FrameError::TypeMismatch),
+                        },
+                        _ => {
+                            if let Some(class) = &self.initializing {
+                                vm.class_loader.finish_initialization(class);
+                            }
+                            return Ok(FrameResult::Finished);
+                        }
+                    };
+                    return Ok(FrameResult::Returned(value));
+                }
+                Instruction::New(index) => {
+                    let class = self.class.constant_pool.class(index)?.to_owned();
+                    match self.initialize(vm, &class)? {
+                        Initialization::Ready => {}
+                        Initialization::Pending(next) => return Ok(next),
+                        Initialization::Caught => continue,
+                    }
+
+                    let fields = vm.class_loader.instance_fields(&class)?;
+                    let object = vm.heap.allocate(Object::new(&class, fields));
+                    self.push(OperandItem::Reference(object))?;
+                }
+                Instruction::Getfield(index) => {
+                    let field = self.class.constant_pool.field_ref(index)?;
+                    let key = (field.name.to_owned(), field.descriptor.to_owned());
+
+                    let object = match self.pop()? {
+                        OperandItem::Reference(object) => object,
+                        OperandItem::Null => {
+                            match self.throw("java/lang/NullPointerException", None, vm)? {
+                                Some(thrown) => return Ok(thrown),
+                                None => continue,
+                            }
+                        }
+                        _ => return Err(FrameError::TypeMismatch),
+                    };
+                    let object = vm
+                        .heap
+                        .get(object)
+                        .as_instance()
+                        .ok_or(FrameError::TypeMismatch)?;
+                    let value = object
+                        .fields
+                        .get(&key)
+                        .cloned()
+                        .ok_or_else(|| no_such_field(&object.name, key.clone()))?;
+                    self.push_value(value)?;
+                }
+                Instruction::Putfield(index) => {
+                    let field = self.class.constant_pool.field_ref(index)?;
+                    let key = (field.name.to_owned(), field.descriptor.to_owned());
+
+                    let value = self.pop_value()?;
+                    let object = match self.pop()? {
+                        OperandItem::Reference(object) => object,
+                        OperandItem::Null => {
+                            match self.throw("java/lang/NullPointerException", None, vm)? {
+                                Some(thrown) => return Ok(thrown),
+                                None => continue,
+                            }
+                        }
+                        _ => return Err(FrameError::TypeMismatch),
+                    };
+                    let object = vm
+                        .heap
+                        .get_mut(object)
+                        .as_instance_mut()
+                        .ok_or(FrameError::TypeMismatch)?;
+                    match object.fields.get_mut(&key) {
+                        Some(slot) => *slot = value,
+                        None => return Err(no_such_field(&object.name, key)),
+                    }
+                }
+                Instruction::Anewarray(index) => {
+                    let component = self.class.constant_pool.class(index)?;
+                    let name = if component.starts_with('[') {
+                        format!("[{component}")
+                    } else {
+                        format!("[L{component};")
+                    };
+
+                    let length = self.pop_int()?;
+                    if length < 0 {
+                        match self.throw("java/lang/NegativeArraySizeException", None, vm)? {
+                            Some(thrown) => return Ok(thrown),
+                            None => continue,
+                        }
+                    }
+                    let array = vm.heap.allocate_array(Array::new(name, length as usize));
+                    self.push(OperandItem::Reference(array))?;
+                }
+                Instruction::Newarray(atype) => {
+                    let length = self.pop_int()?;
+                    if length < 0 {
+                        match self.throw("java/lang/NegativeArraySizeException", None, vm)? {
+                            Some(thrown) => return Ok(thrown),
+                            None => continue,
+                        }
+                    }
+                    let array = vm.heap.allocate_array(Array::new(
+                        format!("[{}", atype.descriptor()),
+                        length as usize,
+                    ));
+                    self.push(OperandItem::Reference(array))?;
+                }
+                Instruction::Multianewarray(index, dimensions) => {
+                    let name = self.class.constant_pool.class(index)?.to_owned();
+                    if name.bytes().take_while(|&b| b == b'[').count() < dimensions as usize {
+                        return Err(FrameError::TypeMismatch);
+                    }
+
+                    // The outermost dimension's length was pushed first.
+                    let mut lengths = Vec::with_capacity(dimensions as usize);
+                    for _ in 0..dimensions {
+                        lengths.push(self.pop_int()?);
+                    }
+                    lengths.reverse();
+                    if lengths.iter().any(|&length| length < 0) {
+                        match self.throw("java/lang/NegativeArraySizeException", None, vm)? {
+                            Some(thrown) => return Ok(thrown),
+                            None => continue,
+                        }
+                    }
+                    let array = allocate_multi_array(&mut vm.heap, &name, &lengths);
+                    self.push(OperandItem::Reference(array))?;
+                }
+                Instruction::Arraylength => {
+                    let array = match self.pop()? {
+                        OperandItem::Reference(array) => array,
+                        OperandItem::Null => {
+                            match self.throw("java/lang/NullPointerException", None, vm)? {
+                                Some(thrown) => return Ok(thrown),
+                                None => continue,
+                            }
+                        }
+                        _ => return Err(FrameError::TypeMismatch),
+                    };
+                    let array = vm
+                        .heap
+                        .get(array)
+                        .as_array()
+                        .ok_or(FrameError::TypeMismatch)?;
+                    self.push(OperandItem::Integer(array.len() as i32))?;
+                }
+                Instruction::Iaload
+                | Instruction::Laload
+                | Instruction::Faload
+                | Instruction::Daload
+                | Instruction::Aaload
+                | Instruction::Baload
+                | Instruction::Caload
+                | Instruction::Saload
+                | Instruction::Iastore
+                | Instruction::Lastore
+                | Instruction::Fastore
+                | Instruction::Dastore
+                | Instruction::Aastore
+                | Instruction::Bastore
+                | Instruction::Castore
+                | Instruction::Sastore => {
+                    let value = match inst {
+                        Instruction::Lastore | Instruction::Dastore => Some(self.pop_value()?),
+                        Instruction::Iastore
+                        | Instruction::Fastore
+                        | Instruction::Aastore
+                        | Instruction::Bastore
+                        | Instruction::Castore
+                        | Instruction::Sastore => Some(self.pop()?),
+                        _ => None,
+                    };
+                    let index = self.pop_int()?;
+                    let array = match self.pop()? {
+                        OperandItem::Reference(array) => array,
+                        OperandItem::Null => {
+                            match self.throw("java/lang/NullPointerException", None, vm)? {
+                                Some(thrown) => return Ok(thrown),
+                                None => continue,
+                            }
+                        }
+                        _ => return Err(FrameError::TypeMismatch),
+                    };
+
+                    let array = vm
+                        .heap
+                        .get_mut(array)
+                        .as_array_mut()
+                        .ok_or(FrameError::TypeMismatch)?;
+                    if !accepts(&inst, &array.elements) {
+                        return Err(FrameError::TypeMismatch);
+                    }
+                    let in_bounds = match (usize::try_from(index), value) {
+                        (Ok(index), Some(value)) => array.store(index, value)?,
+                        (Ok(index), None) => match array.load(index) {
+                            Some(value) => {
+                                self.push_value(value)?;
+                                true
+                            }
+                            None => false,
+                        },
+                        (Err(_), _) => false,
+                    };
+                    if !in_bounds {
+                        match self.throw("java/lang/ArrayIndexOutOfBoundsException", None, vm)? {
+                            Some(thrown) => return Ok(thrown),
+                            None => continue,
+                        }
+                    }
+                }
+                Instruction::Athrow => {
+                    let exception = match self.pop()? {
+                        OperandItem::Reference(exception) => exception,
+                        OperandItem::Null => {
+                            match self.throw("java/lang/NullPointerException", None, vm)? {
+                                Some(thrown) => return Ok(thrown),
+                                None => continue,
+                            }
+                        }
+                        _ => return Err(FrameError::TypeMismatch),
+                    };
+
+                    if self.catch(exception, vm)? {
+                        continue;
+                    }
+                    return Ok(FrameResult::Thrown(exception));
+                }
+                Instruction::Goto(offset) => {
+                    self.jump(offset as i32);
+                    continue;
+                }
+                Instruction::Gotow(offset) => {
+                    self.jump(offset);
+                    continue;
+                }
+                Instruction::If(kind, offset) => {
+                    let value = self.pop_int()?;
+                    if compare(kind, value, 0) {
+                        self.jump(offset as i32);
+                        continue;
+                    }
+                }
+                Instruction::Ificmp(kind, offset) => {
+                    let right = self.pop_int()?;
+                    let left = self.pop_int()?;
+                    if compare(kind, left, right) {
+                        self.jump(offset as i32);
+                        continue;
+                    }
+                }
+                Instruction::Ifacmp(kind, offset) => {
+                    let right = self.pop()?;
+                    let left = self.pop()?;
+                    if !left.is_reference() || !right.is_reference() {
+                        return Err(FrameError::TypeMismatch);
+                    }
+                    let taken = match kind {
+                        ComparisonKind::Eq => left == right,
+                        ComparisonKind::Ne => left != right,
+                        _ => return Err(FrameError::TypeMismatch),
+                    };
+                    if taken {
+                        self.jump(offset as i32);
+                        continue;
+                    }
+                }
+                Instruction::Ifnull(offset) | Instruction::Ifnonnull(offset) => {
+                    let value = self.pop()?;
+                    if !value.is_reference() {
+                        return Err(FrameError::TypeMismatch);
+                    }
+                    let is_null = value == OperandItem::Null;
+                    if is_null == matches!(inst, Instruction::Ifnull(_)) {
+                        self.jump(offset as i32);
+                        continue;
+                    }
+                }
+                Instruction::Tableswitch {
+                    default,
+                    low,
+                    high,
+                    ref offsets,
+                } => {
+                    let index = self.pop_int()?;
+                    let offset = if (low..=high).contains(&index) {
+                        *offsets
+                            .get((index as i64 - low as i64) as usize)
+                            .ok_or(FrameError::TypeMismatch)?
+                    } else {
+                        default
+                    };
+                    self.jump(offset);
+                    continue;
+                }
+                Instruction::Lookupswitch { default, ref pairs } => {
+                    let key = self.pop_int()?;
+                    let offset = pairs
+                        .iter()
+                        .find(|&&(candidate, _)| candidate == key)
+                        .map_or(default, |&(_, offset)| offset);
+                    self.jump(offset);
+                    continue;
+                }
+                Instruction::Aload(index) => {
+                    let value = self.load_reference(index)?;
+                    self.push(value)?;
+                }
+                Instruction::Astore(index) => {
+                    let value = self.pop()?;
+                    if !value.is_reference() {
+                        return Err(FrameError::TypeMismatch);
+                    }
+                    self.store(index, value)?;
+                }
+                Instruction::AconstNull => self.push(OperandItem::Null)?,
+                Instruction::IconstM1 => self.push(OperandItem::Integer(-1))?,
+                Instruction::Iconst0 => self.push(OperandItem::Integer(0))?,
+                Instruction::Iconst1 => self.push(OperandItem::Integer(1))?,
+                Instruction::Iconst2 => self.push(OperandItem::Integer(2))?,
+                Instruction::Iconst3 => self.push(OperandItem::Integer(3))?,
+                Instruction::Iconst4 => self.push(OperandItem::Integer(4))?,
+                Instruction::Iconst5 => self.push(OperandItem::Integer(5))?,
+                Instruction::Bipush(value) => self.push(OperandItem::Integer(value as i32))?,
+                Instruction::Sipush(value) => self.push(OperandItem::Integer(value as i32))?,
+                Instruction::Iload(index) => {
+                    let value = self.load_int(index)?;
+                    self.push(OperandItem::Integer(value))?;
+                }
+                Instruction::Istore(index) => {
+                    let value = self.pop_int()?;
+                    self.store(index, OperandItem::Integer(value))?;
+                }
+                Instruction::Iinc {
+                    index, constant, ..
+                } => {
+                    let value = self.load_int(index)?;
+                    self.store(
+                        index,
+                        OperandItem::Integer(value.wrapping_add(constant as i32)),
+                    )?;
+                }
+                Instruction::Lconst0 => self.push_long(0)?,
+                Instruction::Lconst1 => self.push_long(1)?,
+                Instruction::Lload(index) => {
+                    let value = self.load_long(index)?;
+                    self.push_long(value)?;
+                }
+                Instruction::Lstore(index) => {
+                    let value = self.pop_long()?;
+                    self.store(index, OperandItem::Long(value))?;
+                    self.store(index + 1, OperandItem::Padding)?;
+                }
+                Instruction::Iadd
+                | Instruction::Isub
+                | Instruction::Imul
+                | Instruction::Iand
+                | Instruction::Ior
+                | Instruction::Ixor => {
+                    let value2 = self.pop_int()?;
+                    let value1 = self.pop_int()?;
+                    self.push(OperandItem::Integer(match inst {
+                        Instruction::Iadd => value1.wrapping_add(value2),
+                        Instruction::Isub => value1.wrapping_sub(value2),
+                        Instruction::Imul => value1.wrapping_mul(value2),
+                        Instruction::Iand => value1 & value2,
+                        Instruction::Ior => value1 | value2,
+                        _ => value1 ^ value2,
+                    }))?;
+                }
+                Instruction::Idiv | Instruction::Irem => {
+                    let value2 = self.pop_int()?;
+                    let value1 = self.pop_int()?;
+                    if value2 == 0 {
+                        match self.throw("java/lang/ArithmeticException", Some("/ by zero"), vm)? {
+                            Some(thrown) => return Ok(thrown),
+                            None => continue,
+                        }
+                    }
+                    // Wrapping covers Integer.MIN_VALUE / -1, which overflows.
+                    self.push(OperandItem::Integer(match inst {
+                        Instruction::Idiv => value1.wrapping_div(value2),
+                        _ => value1.wrapping_rem(value2),
+                    }))?;
+                }
+                Instruction::Ineg => {
+                    let value = self.pop_int()?;
+                    self.push(OperandItem::Integer(value.wrapping_neg()))?;
+                }
+                Instruction::Ishl | Instruction::Ishr | Instruction::Iushr => {
+                    // Only the low five bits of the shift distance are used.
+                    let distance = self.pop_int()? as u32 & 0x1f;
+                    let value = self.pop_int()?;
+                    self.push(OperandItem::Integer(match inst {
+                        Instruction::Ishl => value << distance,
+                        Instruction::Ishr => value >> distance,
+                        _ => ((value as u32) >> distance) as i32,
+                    }))?;
+                }
+                Instruction::Ladd
+                | Instruction::Lsub
+                | Instruction::Lmul
+                | Instruction::Land
+                | Instruction::Lor
+                | Instruction::Lxor => {
+                    let value2 = self.pop_long()?;
+                    let value1 = self.pop_long()?;
+                    self.push_long(match inst {
+                        Instruction::Ladd => value1.wrapping_add(value2),
+                        Instruction::Lsub => value1.wrapping_sub(value2),
+                        Instruction::Lmul => value1.wrapping_mul(value2),
+                        Instruction::Land => value1 & value2,
+                        Instruction::Lor => value1 | value2,
+                        _ => value1 ^ value2,
+                    })?;
+                }
+                Instruction::Ldiv | Instruction::Lrem => {
+                    let value2 = self.pop_long()?;
+                    let value1 = self.pop_long()?;
+                    if value2 == 0 {
+                        match self.throw("java/lang/ArithmeticException", Some("/ by zero"), vm)? {
+                            Some(thrown) => return Ok(thrown),
+                            None => continue,
+                        }
+                    }
+                    // Wrapping covers Long.MIN_VALUE / -1, which overflows.
+                    self.push_long(match inst {
+                        Instruction::Ldiv => value1.wrapping_div(value2),
+                        _ => value1.wrapping_rem(value2),
+                    })?;
+                }
+                Instruction::Lneg => {
+                    let value = self.pop_long()?;
+                    self.push_long(value.wrapping_neg())?;
+                }
+                Instruction::Lshl | Instruction::Lshr | Instruction::Lushr => {
+                    // Only the low six bits of the shift distance are used.
+                    let distance = self.pop_int()? as u32 & 0x3f;
+                    let value = self.pop_long()?;
+                    self.push_long(match inst {
+                        Instruction::Lshl => value << distance,
+                        Instruction::Lshr => value >> distance,
+                        _ => ((value as u64) >> distance) as i64,
+                    })?;
+                }
+                Instruction::Fconst0 => self.push(OperandItem::Float(0.0))?,
+                Instruction::Fconst1 => self.push(OperandItem::Float(1.0))?,
+                Instruction::Fconst2 => self.push(OperandItem::Float(2.0))?,
+                Instruction::Fload(index) => {
+                    let value = self.load_float(index)?;
+                    self.push(OperandItem::Float(value))?;
+                }
+                Instruction::Fstore(index) => {
+                    let value = self.pop_float()?;
+                    self.store(index, OperandItem::Float(value))?;
+                }
+                Instruction::Fadd
+                | Instruction::Fsub
+                | Instruction::Fmul
+                | Instruction::Fdiv
+                | Instruction::Frem => {
+                    let value2 = self.pop_float()?;
+                    let value1 = self.pop_float()?;
+                    // Rust's `%` on floats truncates like C's fmod, which is
+                    // what Java's remainder does too.
+                    self.push(OperandItem::Float(match inst {
+                        Instruction::Fadd => value1 + value2,
+                        Instruction::Fsub => value1 - value2,
+                        Instruction::Fmul => value1 * value2,
+                        Instruction::Fdiv => value1 / value2,
+                        _ => value1 % value2,
+                    }))?;
+                }
+                Instruction::Fneg => {
+                    let value = self.pop_float()?;
+                    self.push(OperandItem::Float(-value))?;
+                }
+                Instruction::Fcmpl | Instruction::Fcmpg => {
+                    let value2 = self.pop_float()?;
+                    let value1 = self.pop_float()?;
+                    let nan = if let Instruction::Fcmpl = inst { -1 } else { 1 };
+                    self.push(OperandItem::Integer(
+                        value1
+                            .partial_cmp(&value2)
+                            .map_or(nan, |order| order as i32),
+                    ))?;
+                }
+                Instruction::Dconst0 => self.push_double(0.0)?,
+                Instruction::Dconst1 => self.push_double(1.0)?,
+                Instruction::Dload(index) => {
+                    let value = self.load_double(index)?;
+                    self.push_double(value)?;
+                }
+                Instruction::Dstore(index) => {
+                    let value = self.pop_double()?;
+                    self.store(index, OperandItem::Double(value))?;
+                    self.store(index + 1, OperandItem::Padding)?;
+                }
+                Instruction::Dadd
+                | Instruction::Dsub
+                | Instruction::Dmul
+                | Instruction::Ddiv
+                | Instruction::Drem => {
+                    let value2 = self.pop_double()?;
+                    let value1 = self.pop_double()?;
+                    self.push_double(match inst {
+                        Instruction::Dadd => value1 + value2,
+                        Instruction::Dsub => value1 - value2,
+                        Instruction::Dmul => value1 * value2,
+                        Instruction::Ddiv => value1 / value2,
+                        _ => value1 % value2,
+                    })?;
+                }
+                Instruction::Dneg => {
+                    let value = self.pop_double()?;
+                    self.push_double(-value)?;
+                }
+                Instruction::Dcmpl | Instruction::Dcmpg => {
+                    let value2 = self.pop_double()?;
+                    let value1 = self.pop_double()?;
+                    let nan = if let Instruction::Dcmpl = inst { -1 } else { 1 };
+                    self.push(OperandItem::Integer(
+                        value1
+                            .partial_cmp(&value2)
+                            .map_or(nan, |order| order as i32),
+                    ))?;
+                }
+                Instruction::Lcmp => {
+                    let value2 = self.pop_long()?;
+                    let value1 = self.pop_long()?;
+                    self.push(OperandItem::Integer(value1.cmp(&value2) as i32))?;
+                }
+                // Rust's float to integer casts saturate and turn NaN into
+                // zero, which is exactly what Java specifies.
+                Instruction::I2l => {
+                    let value = self.pop_int()?;
+                    self.push_long(value as i64)?;
+                }
+                Instruction::I2f => {
+                    let value = self.pop_int()?;
+                    self.push(OperandItem::Float(value as f32))?;
                 }
-                _ => {}
+                Instruction::I2d => {
+                    let value = self.pop_int()?;
+                    self.push_double(value as f64)?;
+                }
+                Instruction::L2i => {
+                    let value = self.pop_long()?;
+                    self.push(OperandItem::Integer(value as i32))?;
+                }
+                Instruction::L2f => {
+                    let value = self.pop_long()?;
+                    self.push(OperandItem::Float(value as f32))?;
+                }
+                Instruction::L2d => {
+                    let value = self.pop_long()?;
+                    self.push_double(value as f64)?;
+                }
+                Instruction::F2i => {
+                    let value = self.pop_float()?;
+                    self.push(OperandItem::Integer(value as i32))?;
+                }
+                Instruction::F2l => {
+                    let value = self.pop_float()?;
+                    self.push_long(value as i64)?;
+                }
+                Instruction::F2d => {
+                    let value = self.pop_float()?;
+                    self.push_double(value as f64)?;
+                }
+                Instruction::D2i => {
+                    let value = self.pop_double()?;
+                    self.push(OperandItem::Integer(value as i32))?;
+                }
+                Instruction::D2l => {
+                    let value = self.pop_double()?;
+                    self.push_long(value as i64)?;
+                }
+                Instruction::D2f => {
+                    let value = self.pop_double()?;
+                    self.push(OperandItem::Float(value as f32))?;
+                }
+                Instruction::I2b => {
+                    let value = self.pop_int()?;
+                    self.push(OperandItem::Integer(value as i8 as i32))?;
+                }
+                Instruction::I2c => {
+                    let value = self.pop_int()?;
+                    self.push(OperandItem::Integer(value as u16 as i32))?;
+                }
+                Instruction::I2s => {
+                    let value = self.pop_int()?;
+                    self.push(OperandItem::Integer(value as i16 as i32))?;
+                }
+                Instruction::Pop => {
+                    self.pop_slots(1)?;
+                }
+                Instruction::Pop2 => {
+                    self.pop_slots(2)?;
+                }
+                Instruction::Dup => self.dup_slots(1, 1)?,
+                Instruction::DupX1 => self.dup_slots(1, 2)?,
+                Instruction::DupX2 => self.dup_slots(1, 3)?,
+                Instruction::Dup2 => self.dup_slots(2, 2)?,
+                Instruction::Monitorenter | Instruction::Monitorexit => {
+                    let object = match self.pop()? {
+                        OperandItem::Reference(object) => object,
+                        OperandItem::Null => {
+                            match self.throw("java/lang/NullPointerException", None, vm)? {
+                                Some(thrown) => return Ok(thrown),
+                                None => continue,
+                            }
+                        }
+                        _ => return Err(FrameError::TypeMismatch),
+                    };
+
+                    if let Instruction::Monitorenter = inst {
+                        vm.heap.enter_monitor(object);
+                        self.monitors.push(object);
+                    } else {
+                        // Locking is structured: a frame can only exit the
+                        // monitors it entered itself.
+                        match self.monitors.iter().rposition(|&held| held == object) {
+                            Some(position) => {
+                                self.monitors.remove(position);
+                                vm.heap.exit_monitor(object);
+                            }
+                            None => {
+                                match self.throw(
+                                    "java/lang/IllegalMonitorStateException",
+                                    None,
+                                    vm,
+                                )? {
+                                    Some(thrown) => return Ok(thrown),
+                                    None => continue,
+                                }
+                            }
+                        }
+                    }
+                }
+                Instruction::Dup2X1 => self.dup_slots(2, 3)?,
+                Instruction::Dup2X2 => self.dup_slots(2, 4)?,
+                Instruction::Swap => {
+                    self.check_value_boundary(1)?;
+                    self.check_value_boundary(2)?;
+                    let len = self.operand_stack.len();
+                    self.operand_stack.swap(len - 1, len - 2);
+                }
+                Instruction::Nop => {}
+                _ => return Err(FrameError::UnsupportedInstruction(inst.opcode())),
+            }
+
+            self.pc = next_pc;
+        }
+    }
+
+    /// Continues at `offset` bytes from the current instruction. A target
+    /// outside the code is left for the next instruction fetch to report.
+    fn jump(&mut self, offset: i32) {
+        self.pc = (self.current_pc as isize + offset as isize) as usize;
+    }
+
+    fn push(&mut self, item: OperandItem) -> Result<(), FrameError> {
+        if self.operand_stack.len() >= self.max_stack {
+            return Err(FrameError::StackOverflow);
+        }
+
+        self.operand_stack.push(item);
+        Ok(())
+    }
+
+    /// Pushes the value of the one-slot constant at `index`, for `ldc` and
+    /// `ldc_w`. Class constants load the class's mirror.
+    fn load_constant(&mut self, index: u16, heap: &mut Heap) -> Result<(), FrameError> {
+        let pool = &self.class.constant_pool;
+        let value = match pool.at(index)? {
+            Constant::String(_) => OperandItem::Reference(heap.intern(pool.string(index)?)),
+            Constant::Integer(_) => OperandItem::Integer(pool.integer(index)?),
+            Constant::Float(_) => OperandItem::Float(pool.float(index)?),
+            Constant::Class(_) => OperandItem::Reference(heap.mirror(pool.class(index)?)),
+            _ => return Err(FrameError::UnsupportedConstant(index)),
+        };
+        self.push(value)
+    }
+
+    /// Looks for a handler in this method that covers the current instruction
+    /// and catches `exception`. If there is one, the operand stack is
+    /// replaced with just the exception and execution continues at the
+    /// handler.
+    pub fn catch(&mut self, exception: Reference, vm: &mut Vm) -> Result<bool, FrameError> {
+        for entry in self.method().exception_table() {
+            let range = entry.start_pc as usize..entry.end_pc as usize;
+            if !range.contains(&self.current_pc) {
+                continue;
+            }
+
+            // A catch type of zero catches everything, as `finally` blocks do.
+            if entry.catch_type != 0 {
+                let catch_type = self.class.constant_pool.class(entry.catch_type)?;
+                if !vm
+                    .class_loader
+                    .is_subclass(vm.heap.get(exception).class_name(), catch_type)?
+                {
+                    continue;
+                }
+            }
+
+            self.pc = entry.handler_pc as usize;
+            self.operand_stack.clear();
+            self.push(OperandItem::Reference(exception))?;
+            return Ok(true);
+        }
+
+        Ok(false)
+    }
+
+    /// Throws a new exception of the given class from the current
+    /// instruction, with `message` as its detail message. Returns the result
+    /// to hand back to the thread if this method has no handler for it.
+    fn throw(
+        &mut self,
+        class: &str,
+        message: Option<&str>,
+        vm: &mut Vm,
+    ) -> Result<Option<FrameResult>, FrameError> {
+        let fields = vm.class_loader.instance_fields(class)?;
+        let mut object = Object::new(class, fields);
+        if let Some(message) = message {
+            let message = vm.heap.allocate_string(message.to_owned());
+            if let Some(field) = object.field_mut("detailMessage", "Ljava/lang/String;") {
+                *field = OperandItem::Reference(message);
+            }
+        }
+        let exception = vm.heap.allocate(object);
+
+        if self.catch(exception, vm)? {
+            Ok(None)
+        } else {
+            Ok(Some(FrameResult::Thrown(exception)))
+        }
+    }
+
+    /// Initializes `class` if that hasn't happened yet, returning a frame for
+    /// its static initializer if it has one.
+    ///
+    /// The pc is left on the current instruction, so it runs again once the
+    /// initializer returns. A class whose initializer threw before throws a
+    /// `NoClassDefFoundError` instead.
+    fn initialize(&mut self, vm: &mut Vm, class: &str) -> Result<Initialization, FrameError> {
+        match vm.class_loader.initialize(class) {
+            Ok(Some((name, class, clinit))) => Ok(Initialization::Pending(FrameResult::NextFrame(
+                Frame::initializer(name, class, clinit)?,
+            ))),
+            Ok(None) | Err(ClassLoadingError::NotFound(_)) => Ok(Initialization::Ready),
+            Err(ClassLoadingError::Erroneous(name)) => {
+                let message = format!("Could not initialize class {}", name.replace('/', "."));
+                match self.throw("java/lang/NoClassDefFoundError", Some(&message), vm)? {
+                    Some(thrown) => Ok(Initialization::Pending(thrown)),
+                    None => Ok(Initialization::Caught),
+                }
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Marks the class this frame is running the static initializer of as
+    /// unusable, for when the initializer throws.
+    pub fn fail_initialization(&self, class_loader: &mut ClassLoader) {
+        if let Some(class) = &self.initializing {
+            class_loader.fail_initialization(class);
+        }
+    }
+
+    /// Invokes a method. Methods with bytecode get a new frame, which is
+    /// returned for the thread to run, while native methods are called
+    /// directly.
+    ///
+    /// The registry is consulted for methods flagged `NATIVE`, and for
+    /// methods whose class isn't on the classpath, like the JDK's. Natives
+    /// registered on a superclass are inherited, so `Object.<init>` covers
+    /// every constructor that chains up to it.
+    fn invoke(
+        &mut self,
+        vm: &mut Vm,
+        (class, name, descriptor): (&str, &str, &str),
+        is_static: bool,
+        next_pc: usize,
+    ) -> Result<Option<FrameResult>, FrameError> {
+        let slots = MethodDescriptor::parse(descriptor)?.slot_count(is_static) as usize;
+
+        let resolved = match vm.class_loader.resolve_method(class, name, descriptor) {
+            Ok(resolved) => resolved,
+            Err(ClassLoadingError::NotFound(_)) => None,
+            Err(err) => return Err(err.into()),
+        };
+
+        match resolved {
+            Some((callee, method))
+                if !callee.methods[method]
+                    .access_flags
+                    .intersects(MethodAccessFlags::NATIVE | MethodAccessFlags::ABSTRACT) =>
+            {
+                let synchronized = callee.methods[method]
+                    .access_flags
+                    .contains(MethodAccessFlags::SYNCHRONIZED);
+                let args = self.pop_slots(slots)?;
+                self.pc = next_pc;
+                let mut frame = Frame::new(callee, method, args)?;
+
+                // Synchronized static methods lock their class's mirror.
+                if synchronized {
+                    let locked = match frame.locals.first() {
+                        _ if is_static => Some(vm.heap.mirror(frame.class.name()?)),
+                        Some(&OperandItem::Reference(receiver)) => Some(receiver),
+                        _ => None,
+                    };
+                    if let Some(locked) = locked {
+                        vm.heap.enter_monitor(locked);
+                        frame.locked = Some(locked);
+                    }
+                }
+                Ok(Some(FrameResult::NextFrame(frame)))
+            }
+            _ => {
+                let mut current = Some(class.to_owned());
+                let native = loop {
+                    let owner = current.ok_or_else(|| FrameError::NoSuchMethod {
+                        class: class.to_owned(),
+                        name: name.to_owned(),
+                        descriptor: descriptor.to_owned(),
+                    })?;
+                    if let Some(native) = vm.natives.get(&owner, name, descriptor) {
+                        break native;
+                    }
+                    current = vm.class_loader.super_class(&owner)?;
+                };
+
+                let args = self.pop_arguments(slots)?;
+                if let Some(result) = native(self, &mut vm.heap, args)? {
+                    self.push_value(result)?;
+                }
+                Ok(None)
             }
+        }
+    }
+
+    /// Pushes a value of any type, adding a padding slot for longs and
+    /// doubles.
+    pub fn push_value(&mut self, item: OperandItem) -> Result<(), FrameError> {
+        let wide = matches!(item, OperandItem::Long(_) | OperandItem::Double(_));
+        self.push(item)?;
+        if wide {
+            self.push(OperandItem::Padding)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the slot `depth` slots below the top of the operand stack.
+    fn peek(&self, depth: usize) -> Result<&OperandItem, FrameError> {
+        let index = self
+            .operand_stack
+            .len()
+            .checked_sub(depth + 1)
+            .ok_or(FrameError::StackUnderflow)?;
+        Ok(&self.operand_stack[index])
+    }
+
+    fn pop(&mut self) -> Result<OperandItem, FrameError> {
+        self.operand_stack.pop().ok_or(FrameError::StackUnderflow)
+    }
+
+    /// Pops a value of any type, along with its padding slot if it is a long
+    /// or double.
+    fn pop_value(&mut self) -> Result<OperandItem, FrameError> {
+        match self.pop()? {
+            OperandItem::Padding => match self.pop()? {
+                value @ (OperandItem::Long(_) | OperandItem::Double(_)) => Ok(value),
+                _ => Err(FrameError::TypeMismatch),
+            },
+            value => Ok(value),
+        }
+    }
+
+    fn pop_int(&mut self) -> Result<i32, FrameError> {
+        match self.pop()? {
+            OperandItem::Integer(value) => Ok(value),
+            _ => Err(FrameError::TypeMismatch),
+        }
+    }
+
+    /// Pushes a long, which takes up two slots on the operand stack.
+    fn push_long(&mut self, value: i64) -> Result<(), FrameError> {
+        self.push(OperandItem::Long(value))?;
+        self.push(OperandItem::Padding)
+    }
+
+    fn pop_long(&mut self) -> Result<i64, FrameError> {
+        match (self.pop()?, self.pop()?) {
+            (OperandItem::Padding, OperandItem::Long(value)) => Ok(value),
+            _ => Err(FrameError::TypeMismatch),
+        }
+    }
+
+    fn pop_float(&mut self) -> Result<f32, FrameError> {
+        match self.pop()? {
+            OperandItem::Float(value) => Ok(value),
+            _ => Err(FrameError::TypeMismatch),
+        }
+    }
+
+    /// Pushes a double, which takes up two slots on the operand stack.
+    fn push_double(&mut self, value: f64) -> Result<(), FrameError> {
+        self.push(OperandItem::Double(value))?;
+        self.push(OperandItem::Padding)
+    }
+
+    fn pop_double(&mut self) -> Result<f64, FrameError> {
+        match (self.pop()?, self.pop()?) {
+            (OperandItem::Padding, OperandItem::Double(value)) => Ok(value),
+            _ => Err(FrameError::TypeMismatch),
+        }
+    }
+
+    /// Checks that the boundary `depth` slots below the top of the operand
+    /// stack doesn't fall between the two slots of a long or double.
+    ///
+    /// The padding slot always sits directly above its value, so a boundary
+    /// splits a value exactly when the slot right above it is padding.
+    fn check_value_boundary(&self, depth: usize) -> Result<(), FrameError> {
+        let index = self
+            .operand_stack
+            .len()
+            .checked_sub(depth)
+            .ok_or(FrameError::StackUnderflow)?;
 
-            println!();
-            self.pc += 1;
+        match self.operand_stack.get(index) {
+            Some(OperandItem::Padding) => Err(FrameError::TypeMismatch),
+            _ => Ok(()),
         }
+    }
+
+    /// Duplicates the top `count` slots of the operand stack and inserts the
+    /// copies `depth` slots down, which covers every `dup` variant.
+    fn dup_slots(&mut self, count: usize, depth: usize) -> Result<(), FrameError> {
+        self.check_value_boundary(count)?;
+        self.check_value_boundary(depth)?;
 
-        FrameResult::Finished
+        let len = self.operand_stack.len();
+        if len + count > self.max_stack {
+            return Err(FrameError::StackOverflow);
+        }
+
+        let values = self.operand_stack[len - count..].to_vec();
+        self.operand_stack.splice(len - depth..len - depth, values);
+        Ok(())
+    }
+
+    /// Pops the top `count` slots of the operand stack, in the order they
+    /// were pushed.
+    fn pop_slots(&mut self, count: usize) -> Result<Vec<OperandItem>, FrameError> {
+        self.check_value_boundary(count)?;
+        let len = self.operand_stack.len();
+        Ok(self.operand_stack.split_off(len - count))
+    }
+
+    /// Pops the top `slots` slots of the operand stack as method arguments,
+    /// in the order they were pushed and without their padding.
+    fn pop_arguments(&mut self, slots: usize) -> Result<Vec<OperandItem>, FrameError> {
+        let mut args = self.pop_slots(slots)?;
+        args.retain(|item| !matches!(item, OperandItem::Padding));
+        Ok(args)
+    }
+
+    fn load_int(&self, index: u16) -> Result<i32, FrameError> {
+        match self.locals.get(index as usize) {
+            Some(OperandItem::Integer(value)) => Ok(*value),
+            Some(_) => Err(FrameError::TypeMismatch),
+            None => Err(FrameError::InvalidLocal(index)),
+        }
+    }
+
+    fn load_long(&self, index: u16) -> Result<i64, FrameError> {
+        match self.locals.get(index as usize) {
+            Some(OperandItem::Long(value)) => Ok(*value),
+            Some(_) => Err(FrameError::TypeMismatch),
+            None => Err(FrameError::InvalidLocal(index)),
+        }
+    }
+
+    fn load_float(&self, index: u16) -> Result<f32, FrameError> {
+        match self.locals.get(index as usize) {
+            Some(OperandItem::Float(value)) => Ok(*value),
+            Some(_) => Err(FrameError::TypeMismatch),
+            None => Err(FrameError::InvalidLocal(index)),
+        }
+    }
+
+    fn load_double(&self, index: u16) -> Result<f64, FrameError> {
+        match self.locals.get(index as usize) {
+            Some(OperandItem::Double(value)) => Ok(*value),
+            Some(_) => Err(FrameError::TypeMismatch),
+            None => Err(FrameError::InvalidLocal(index)),
+        }
+    }
+
+    fn load_reference(&self, index: u16) -> Result<OperandItem, FrameError> {
+        match self.locals.get(index as usize) {
+            Some(value) if value.is_reference() => Ok(value.clone()),
+            Some(_) => Err(FrameError::TypeMismatch),
+            None => Err(FrameError::InvalidLocal(index)),
+        }
+    }
+
+    fn store(&mut self, index: u16, item: OperandItem) -> Result<(), FrameError> {
+        match self.locals.get_mut(index as usize) {
+            Some(local) => {
+                *local = item;
+                Ok(())
+            }
+            None => Err(FrameError::InvalidLocal(index)),
+        }
+    }
+}
+
+/// Whether an array load or store instruction can operate on `elements`.
+/// `baload` and `bastore` are shared by byte and boolean arrays.
+fn accepts(inst: &Instruction, elements: &Elements) -> bool {
+    matches!(
+        (inst, elements),
+        (Instruction::Iaload | Instruction::Iastore, Elements::Int(_))
+            | (
+                Instruction::Laload | Instruction::Lastore,
+                Elements::Long(_)
+            )
+            | (
+                Instruction::Faload | Instruction::Fastore,
+                Elements::Float(_)
+            )
+            | (
+                Instruction::Daload | Instruction::Dastore,
+                Elements::Double(_)
+            )
+            | (
+                Instruction::Aaload | Instruction::Aastore,
+                Elements::Reference(_)
+            )
+            | (
+                Instruction::Baload | Instruction::Bastore,
+                Elements::Byte(_) | Elements::Boolean(_)
+            )
+            | (
+                Instruction::Caload | Instruction::Castore,
+                Elements::Char(_)
+            )
+            | (
+                Instruction::Saload | Instruction::Sastore,
+                Elements::Short(_)
+            )
+    )
+}
+
+/// Whether `left` and `right` compare as a conditional branch requires.
+fn compare(kind: ComparisonKind, left: i32, right: i32) -> bool {
+    match kind {
+        ComparisonKind::Eq => left == right,
+        ComparisonKind::Ne => left != right,
+        ComparisonKind::Lt => left < right,
+        ComparisonKind::Ge => left >= right,
+        ComparisonKind::Gt => left > right,
+        ComparisonKind::Le => left <= right,
+    }
+}
+
+/// Finds the index of the instruction starting at byte offset `pc`.
+fn instruction_index(code: &[(u32, Instruction)], pc: usize) -> Option<usize> {
+    code.binary_search_by_key(&pc, |(offset, _)| *offset as usize)
+        .ok()
+}
+
+fn no_such_field(class: &str, (name, descriptor): (String, String)) -> FrameError {
+    FrameError::NoSuchField {
+        class: class.to_owned(),
+        name,
+        descriptor,
     }
 }
 
+/// Counts the operand stack slots taken up by the parameters of a method
+/// descriptor. Longs and doubles take up two slots, everything else one.
+fn parameter_slots(descriptor: &str) -> Result<usize, FrameError> {
+    Ok(MethodDescriptor::parse(descriptor)?.parameter_slots())
+}
+
+/// Allocates an array of the class `name` for `multianewarray`, with one
+/// length for each dimension to create. Every element of an outer dimension
+/// is a new array of the next, and dimensions past the last length are left
+/// null, as in `new int[2][]`.
+fn allocate_multi_array(heap: &mut Heap, name: &str, lengths: &[i32]) -> Reference {
+    let mut array = Array::new(name.to_owned(), lengths[0] as usize);
+    if lengths.len() > 1 {
+        if let Elements::Reference(elements) = &mut array.elements {
+            for element in elements {
+                *element =
+                    OperandItem::Reference(allocate_multi_array(heap, &name[1..], &lengths[1..]));
+            }
+        }
+    }
+    heap.allocate_array(array)
+}
+
+/// Builds the string a `StringConcatFactory` call site produces. In the
+/// recipe, `\u{1}` stands for the next argument and `\u{2}` for the next
+/// constant.
+fn concat(
+    recipe: &str,
+    types: &[FieldType],
+    args: &[OperandItem],
+    constants: &[String],
+    heap: &Heap,
+) -> Result<String, FrameError> {
+    let mut args = types.iter().zip(args);
+    let mut constants = constants.iter();
+    let mut out = String::new();
+
+    for c in recipe.chars() {
+        match c {
+            '\u{1}' => match args.next().ok_or(FrameError::TypeMismatch)? {
+                (FieldType::Boolean, OperandItem::Integer(value)) => {
+                    out.push_str(&(*value != 0).to_string())
+                }
+                (FieldType::Char, OperandItem::Integer(value)) => {
+                    out.push(char::from_u32(*value as u16 as u32).unwrap_or('\u{fffd}'))
+                }
+                (_, OperandItem::Integer(value)) => out.push_str(&value.to_string()),
+                (_, OperandItem::Long(value)) => out.push_str(&value.to_string()),
+                (_, OperandItem::Float(value)) => out.push_str(&format_float(*value)),
+                (_, OperandItem::Double(value)) => out.push_str(&format_double(*value)),
+                (_, OperandItem::Null) => out.push_str("null"),
+                // Other objects would need their toString() called.
+                (_, OperandItem::Reference(value)) => out.push_str(
+                    heap.get(*value)
+                        .as_string()
+                        .ok_or(FrameError::TypeMismatch)?,
+                ),
+                _ => return Err(FrameError::TypeMismatch),
+            },
+            '\u{2}' => out.push_str(constants.next().ok_or(FrameError::TypeMismatch)?),
+            c => out.push(c),
+        }
+    }
+
+    Ok(out)
+}
+
+/// What an instruction that needs a class initialized does next.
+enum Initialization {
+    /// The class is ready for use, so the instruction carries on.
+    Ready,
+    /// The thread has to run a static initializer first, or the class
+    /// couldn't be initialized and this method has no handler for the
+    /// error.
+    Pending(FrameResult),
+    /// The class couldn't be initialized and a handler in this method caught
+    /// the error, so execution continues there.
+    Caught,
+}
+
 pub enum FrameResult {
-    NextFrame(Method),
+    /// A method was invoked and its frame should run before this one
+    /// continues.
+    NextFrame(Frame),
+    /// The method returned without a value.
     Finished,
+    /// The method returned a value to be pushed onto the caller's stack.
+    Returned(OperandItem),
+    /// The method threw an exception it had no handler for.
+    Thrown(Reference),
+}
+
+/// A frame on the stack of a thread an exception was thrown on.
+#[derive(Debug)]
+pub struct StackTraceElement {
+    pub class: String,
+    pub method: String,
+    pub source_file: Option<String>,
+    /// The source line being executed, if the method has a
+    /// `LineNumberTable`.
+    pub line: Option<u16>,
+    /// The offset of the instruction being executed.
+    pub pc: usize,
+}
+
+impl fmt::Display for StackTraceElement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let file = self.source_file.as_deref().unwrap_or("Unknown Source");
+        write!(
+            f,
+            "{}.{}(",
+            pretty::class_name(&self.class, Names::Qualified),
+            self.method
+        )?;
+        match self.line {
+            Some(line) => write!(f, "{file}:{line})"),
+            None => write!(f, "{file}, pc {})", self.pc),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum FrameError {
+    /// An instruction popped a value off an empty operand stack.
+    StackUnderflow,
+    /// A value was pushed beyond the method's `max_stack`, or the thread ran
+    /// out of room for another frame.
+    StackOverflow,
+    /// An operand or local variable did not hold the type of value the
+    /// instruction expected.
+    TypeMismatch,
+    /// A local variable index was outside of the method's `max_locals`.
+    InvalidLocal(u16),
+    ConstantPool(ConstantPoolError),
+    ClassLoading(ClassLoadingError),
+    /// A method or field descriptor is malformed.
+    InvalidDescriptor(DescriptorError),
+    NoSuchMethod {
+        class: String,
+        name: String,
+        descriptor: String,
+    },
+    NoSuchField {
+        class: String,
+        name: String,
+        descriptor: String,
+    },
+    /// An `invokedynamic` call site's bootstrap method is not one the
+    /// interpreter knows how to link.
+    UnsupportedBootstrapMethod {
+        class: String,
+        name: String,
+    },
+    /// The interpreter doesn't implement the instruction with the given
+    /// opcode yet.
+    UnsupportedInstruction(u8),
+    /// The constant at the given index can't be loaded by `ldc`, either
+    /// because it takes two slots or because the interpreter doesn't support
+    /// its kind yet.
+    UnsupportedConstant(u16),
+    /// A frame was made for a method without code, like an abstract or
+    /// native one.
+    NoCode {
+        class: String,
+        name: String,
+        descriptor: String,
+    },
+    /// A frame was made with more argument slots than its method has local
+    /// variables.
+    TooManyArguments {
+        class: String,
+        name: String,
+        descriptor: String,
+        arguments: usize,
+        max_locals: u16,
+    },
+    /// Execution ran past the last instruction of a method, without
+    /// returning or throwing.
+    FellOffEnd {
+        class: String,
+        name: String,
+        descriptor: String,
+    },
+    /// An exception was thrown and no frame on the thread had a handler for
+    /// it.
+    UncaughtException {
+        class: String,
+        message: Option<String>,
+        /// The frames the exception unwound, innermost first.
+        stack_trace: Vec<StackTraceElement>,
+    },
+}
+
+impl From<ConstantPoolError> for FrameError {
+    fn from(err: ConstantPoolError) -> Self {
+        FrameError::ConstantPool(err)
+    }
+}
+
+impl From<DescriptorError> for FrameError {
+    fn from(err: DescriptorError) -> Self {
+        FrameError::InvalidDescriptor(err)
+    }
+}
+
+impl From<ClassLoadingError> for FrameError {
+    fn from(err: ClassLoadingError) -> Self {
+        FrameError::ClassLoading(err)
+    }
+}
+
+impl fmt::Display for FrameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FrameError::StackUnderflow => write!(f, "operand stack underflow"),
+            FrameError::StackOverflow => write!(f, "stack overflow"),
+            FrameError::TypeMismatch => write!(f, "operand has an unexpected type"),
+            FrameError::InvalidLocal(index) => write!(f, "invalid local variable {index}"),
+            FrameError::ConstantPool(err) => write!(f, "{err}"),
+            FrameError::InvalidDescriptor(err) => write!(f, "{err}"),
+            FrameError::ClassLoading(err) => write!(f, "{err}"),
+            FrameError::NoSuchMethod {
+                class,
+                name,
+                descriptor,
+            } => write!(f, "no such method {class}.{name}{descriptor}"),
+            FrameError::NoSuchField {
+                class,
+                name,
+                descriptor,
+            } => write!(f, "no such field {class}.{name}:{descriptor}"),
+            FrameError::UnsupportedBootstrapMethod { class, name } => {
+                write!(f, "unsupported bootstrap method {class}.{name}")
+            }
+            FrameError::UnsupportedInstruction(opcode) => {
+                write!(f, "unsupported instruction with opcode 0x{opcode:02x}")
+            }
+            FrameError::UnsupportedConstant(index) => {
+                write!(f, "constant #{index} can't be loaded by ldc")
+            }
+            FrameError::NoCode {
+                class,
+                name,
+                descriptor,
+            } => write!(f, "method {class}.{name}{descriptor} has no code"),
+            FrameError::TooManyArguments {
+                class,
+                name,
+                descriptor,
+                arguments,
+                max_locals,
+            } => write!(
+                f,
+                "{arguments} argument slots don't fit in the {max_locals} locals of {class}.{name}{descriptor}"
+            ),
+            FrameError::FellOffEnd {
+                class,
+                name,
+                descriptor,
+            } => write!(
+                f,
+                "execution fell off the end of {class}.{name}{descriptor}"
+            ),
+            FrameError::UncaughtException {
+                class,
+                message,
+                stack_trace,
+            } => {
+                write!(
+                    f,
+                    "Exception in thread \"main\" {}",
+                    pretty::class_name(class, Names::Qualified)
+                )?;
+                if let Some(message) = message {
+                    write!(f, ": {message}")?;
+                }
+                for element in stack_trace {
+                    write!(f, "\n\tat {element}")?;
+                }
+                Ok(())
+            }
+        }
+    }
 }
 
+#[derive(Debug, Clone, PartialEq)]
+#[allow(dead_code)]
 pub enum OperandItem {
     Integer(i32),
     Float(f32),
     Long(i64),
     Double(f64),
-    Reference(Object),
+    Reference(Reference),
+    Null,
+    /// The `java.lang.System.out` print stream.
+    SystemOut,
     Padding,
 }
+
+impl OperandItem {
+    /// The default value of a field with the given descriptor.
+    pub fn zero(descriptor: &str) -> OperandItem {
+        match descriptor.as_bytes().first() {
+            Some(b'J') => OperandItem::Long(0),
+            Some(b'F') => OperandItem::Float(0.0),
+            Some(b'D') => OperandItem::Double(0.0),
+            Some(b'L' | b'[') => OperandItem::Null,
+            _ => OperandItem::Integer(0),
+        }
+    }
+
+    /// Whether the item can be stored by `astore`.
+    pub fn is_reference(&self) -> bool {
+        matches!(
+            self,
+            OperandItem::Reference(_) | OperandItem::Null | OperandItem::SystemOut
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::testing::{frame, main_result, run, vm, with_offsets, ClassBuilder};
+    use runevm_classfile::{
+        parse_class, ArrayType, ExceptionTableEntry, FieldAccessFields, VerifyError,
+    };
+    use Instruction::*;
+
+    #[test]
+    fn iinc_adds_a_signed_constant() {
+        let decrement = Iinc {
+            index: 0,
+            constant: -2,
+            wide: false,
+        };
+        let class = ClassBuilder::new("Test")
+            .method(
+                "run",
+                "()I",
+                (1, 1),
+                &[
+                    Iconst0,
+                    Istore(0),
+                    decrement.clone(),
+                    decrement.clone(),
+                    decrement,
+                    Iload(0),
+                    Ireturn,
+                ],
+            )
+            .build();
+        let mut vm = vm(&[&class]);
+        assert_eq!(run(&mut vm, &class, "run", "()I"), OperandItem::Integer(-6));
+    }
+
+    #[test]
+    fn lcmp_compares_signed_longs() {
+        let mut class = ClassBuilder::new("Test");
+        let min = class.pool.add_long(i64::MIN).unwrap();
+        let max = class.pool.add_long(i64::MAX).unwrap();
+        let class = class
+            .method(
+                "less",
+                "()I",
+                (4, 0),
+                &[Ldc2w(min), Ldc2w(max), Lcmp, Ireturn],
+            )
+            .method(
+                "equal",
+                "()I",
+                (4, 0),
+                &[Ldc2w(max), Ldc2w(max), Lcmp, Ireturn],
+            )
+            .method(
+                "greater",
+                "()I",
+                (4, 0),
+                &[Ldc2w(max), Ldc2w(min), Lcmp, Ireturn],
+            )
+            .build();
+        let mut vm = vm(&[&class]);
+        assert_eq!(
+            run(&mut vm, &class, "less", "()I"),
+            OperandItem::Integer(-1)
+        );
+        assert_eq!(
+            run(&mut vm, &class, "equal", "()I"),
+            OperandItem::Integer(0)
+        );
+        assert_eq!(
+            run(&mut vm, &class, "greater", "()I"),
+            OperandItem::Integer(1)
+        );
+    }
+
+    #[test]
+    fn dup_x2_below_two_ints() {
+        let class = ClassBuilder::new("Test")
+            .method(
+                "run",
+                "()V",
+                (4, 0),
+                &[Iconst1, Iconst2, Iconst3, DupX2, Return],
+            )
+            .build();
+        let mut vm = vm(&[&class]);
+        let mut frame = frame(&class, "run", "()V", Vec::new());
+        assert!(matches!(frame.execute(&mut vm), Ok(FrameResult::Finished)));
+        assert_eq!(frame.operand_stack, [3, 1, 2, 3].map(OperandItem::Integer));
+    }
+
+    #[test]
+    fn dup_x2_below_a_long() {
+        let class = ClassBuilder::new("Test")
+            .method("run", "()V", (4, 0), &[Lconst1, Iconst2, DupX2, Return])
+            .build();
+        let mut vm = vm(&[&class]);
+        let mut frame = frame(&class, "run", "()V", Vec::new());
+        assert!(matches!(frame.execute(&mut vm), Ok(FrameResult::Finished)));
+        assert_eq!(
+            frame.operand_stack,
+            [
+                OperandItem::Integer(2),
+                OperandItem::Long(1),
+                OperandItem::Padding,
+                OperandItem::Integer(2),
+            ]
+        );
+    }
+
+    #[test]
+    fn pushing_past_max_stack_overflows() {
+        let class = ClassBuilder::new("Test")
+            .method("run", "()V", (2, 0), &[Iconst1, Iconst2, Iconst3, Return])
+            .build();
+        // The class isn't defined, since verification would reject it.
+        let mut vm = vm(&[]);
+        let mut frame = frame(&class, "run", "()V", Vec::new());
+        assert!(matches!(
+            frame.execute(&mut vm),
+            Err(FrameError::StackOverflow)
+        ));
+        assert_eq!(frame.operand_stack.len(), 2);
+    }
+
+    #[test]
+    fn ldc_loads_a_float() {
+        let mut class = ClassBuilder::new("Test");
+        let half = class.pool.add_float(0.5).unwrap();
+        let class = class
+            .method("run", "()F", (1, 0), &[Ldc(half as u8), Freturn])
+            .build();
+        let mut vm = vm(&[&class]);
+        assert_eq!(run(&mut vm, &class, "run", "()F"), OperandItem::Float(0.5));
+    }
+
+    #[test]
+    fn ldc_of_a_long_is_unsupported() {
+        let mut class = ClassBuilder::new("Test");
+        let long = class.pool.add_long(1).unwrap();
+        let class = class
+            .method("run", "()V", (2, 0), &[Ldc(long as u8), Return])
+            .build();
+        let mut vm = vm(&[]);
+        let mut frame = frame(&class, "run", "()V", Vec::new());
+        assert!(matches!(
+            frame.execute(&mut vm),
+            Err(FrameError::UnsupportedConstant(index)) if index == long
+        ));
+    }
+
+    #[test]
+    fn ldc2_w_pushes_a_long_and_a_double_as_two_slots_each() {
+        let mut class = ClassBuilder::new("Test");
+        let long = class.pool.add_long(-7).unwrap();
+        let double = class.pool.add_double(2.5).unwrap();
+        let class = class
+            .method("run", "()V", (4, 0), &[Ldc2w(long), Ldc2w(double), Return])
+            .build();
+        let mut vm = vm(&[&class]);
+        let mut frame = frame(&class, "run", "()V", Vec::new());
+        assert!(matches!(frame.execute(&mut vm), Ok(FrameResult::Finished)));
+        assert_eq!(
+            frame.operand_stack,
+            [
+                OperandItem::Long(-7),
+                OperandItem::Padding,
+                OperandItem::Double(2.5),
+                OperandItem::Padding,
+            ]
+        );
+    }
+
+    #[test]
+    fn athrow_is_caught_in_the_same_method() {
+        let boom = ClassBuilder::new("Boom").build();
+        let mut class = ClassBuilder::new("Test");
+        let boom_class = class.pool.add_class("Boom").unwrap();
+        let class = class
+            .method_with_handlers(
+                "run",
+                "()I",
+                (1, 0),
+                &[New(boom_class), Athrow, Pop, Bipush(42), Ireturn],
+                vec![ExceptionTableEntry {
+                    start_pc: 0,
+                    end_pc: 4,
+                    handler_pc: 4,
+                    catch_type: boom_class,
+                }],
+            )
+            .build();
+        let mut vm = vm(&[&boom, &class]);
+        assert_eq!(run(&mut vm, &class, "run", "()I"), OperandItem::Integer(42));
+    }
+
+    #[test]
+    fn new_allocates_an_object() {
+        let mut class = ClassBuilder::new("Test");
+        let this_class = class.pool.add_class("Test").unwrap();
+        let class = class
+            .method("run", "()LTest;", (1, 0), &[New(this_class), Areturn])
+            .build();
+        let mut vm = vm(&[&class]);
+        let before = vm.heap.len();
+        let object = match run(&mut vm, &class, "run", "()LTest;") {
+            OperandItem::Reference(object) => object,
+            other => panic!("expected a reference, got {other:?}"),
+        };
+        assert_eq!(vm.heap.len(), before + 1);
+        assert_eq!(vm.heap.get(object).class_name(), "Test");
+    }
+
+    #[test]
+    fn putfield_then_getfield_round_trips() {
+        let mut class = ClassBuilder::new("Test");
+        let this_class = class.pool.add_class("Test").unwrap();
+        let value = class.pool.add_field_ref("Test", "value", "I").unwrap();
+        let class = class
+            .field(FieldAccessFields::PUBLIC, "value", "I")
+            .method(
+                "run",
+                "()I",
+                (3, 0),
+                &[
+                    New(this_class),
+                    Dup,
+                    Bipush(99),
+                    Putfield(value),
+                    Getfield(value),
+                    Ireturn,
+                ],
+            )
+            .build();
+        let mut vm = vm(&[&class]);
+        assert_eq!(run(&mut vm, &class, "run", "()I"), OperandItem::Integer(99));
+    }
+
+    #[test]
+    fn constructor_sets_a_field_after_calling_super() {
+        let result = main_result(
+            &[
+                include_bytes!("../../tests/fixtures/Constructors.class"),
+                include_bytes!("../../tests/fixtures/Constructors$Base.class"),
+                include_bytes!("../../tests/fixtures/Constructors$Derived.class"),
+            ],
+            "Constructors",
+            "I",
+        );
+        assert_eq!(result, OperandItem::Integer(11));
+    }
+
+    #[test]
+    fn overrides_are_called_through_the_base_type() {
+        // Bird's describe() is inherited, and calls legs() on the Bird.
+        let result = main_result(
+            &[
+                include_bytes!("../../tests/fixtures/Dispatch.class"),
+                include_bytes!("../../tests/fixtures/Dispatch$Animal.class"),
+                include_bytes!("../../tests/fixtures/Dispatch$Bird.class"),
+                include_bytes!("../../tests/fixtures/Dispatch$Dog.class"),
+            ],
+            "Dispatch",
+            "I",
+        );
+        assert_eq!(result, OperandItem::Integer(2 * 10 + 4 * 100));
+    }
+
+    #[test]
+    fn interface_methods_are_called_through_the_interface() {
+        let result = main_result(
+            &[
+                include_bytes!("../../tests/fixtures/Interfaces.class"),
+                include_bytes!("../../tests/fixtures/Interfaces$Shape.class"),
+                include_bytes!("../../tests/fixtures/Interfaces$Triangle.class"),
+                include_bytes!("../../tests/fixtures/Interfaces$Square.class"),
+            ],
+            "Interfaces",
+            "I",
+        );
+        assert_eq!(result, OperandItem::Integer(34));
+    }
+
+    #[test]
+    fn synchronized_blocks_run() {
+        // bumpTwice is a synchronized method too, so it enters the monitor
+        // it already holds.
+        let result = main_result(
+            &[include_bytes!("../../tests/fixtures/Synchronized.class")],
+            "Synchronized",
+            "I",
+        );
+        assert_eq!(result, OperandItem::Integer(1 + 3 * 10));
+    }
+
+    #[test]
+    fn putstatic_then_getstatic_counts_calls() {
+        let mut class = ClassBuilder::new("Test");
+        let count = class.pool.add_field_ref("Test", "count", "I").unwrap();
+        let class = class
+            .field(FieldAccessFields::STATIC, "count", "I")
+            .method(
+                "next",
+                "()I",
+                (2, 0),
+                &[
+                    Getstatic(count),
+                    Iconst1,
+                    Iadd,
+                    Putstatic(count),
+                    Getstatic(count),
+                    Ireturn,
+                ],
+            )
+            .build();
+        let mut vm = vm(&[&class]);
+        for expected in 1..=3 {
+            assert_eq!(
+                run(&mut vm, &class, "next", "()I"),
+                OperandItem::Integer(expected)
+            );
+        }
+    }
+
+    #[test]
+    fn putstatic_to_system_out_pops_its_value() {
+        let mut class = ClassBuilder::new("Test");
+        let out = class
+            .pool
+            .add_field_ref("java/lang/System", "out", "Ljava/io/PrintStream;")
+            .unwrap();
+        let class = class
+            .method("run", "()V", (1, 0), &[AconstNull, Putstatic(out), Return])
+            .build();
+        let mut vm = vm(&[&class]);
+        let mut frame = frame(&class, "run", "()V", Vec::new());
+        // There's no System class to store to, but the value must be popped
+        // rather than System.out pushed.
+        assert!(matches!(
+            frame.execute(&mut vm),
+            Err(FrameError::ClassLoading(ClassLoadingError::NotFound(class)))
+                if class == "java/lang/System"
+        ));
+        assert!(frame.operand_stack.is_empty());
+    }
+
+    #[test]
+    fn sums_an_int_array() {
+        let class = ClassBuilder::new("Test")
+            .method(
+                "run",
+                "()I",
+                (4, 1),
+                &[
+                    Iconst3,
+                    Newarray(ArrayType::Int),
+                    Dup,
+                    Iconst0,
+                    Iconst1,
+                    Iastore,
+                    Dup,
+                    Iconst1,
+                    Iconst2,
+                    Iastore,
+                    Dup,
+                    Iconst2,
+                    Iconst3,
+                    Iastore,
+                    Astore(0),
+                    Iconst0,
+                    Aload(0),
+                    Iconst0,
+                    Iaload,
+                    Iadd,
+                    Aload(0),
+                    Iconst1,
+                    Iaload,
+                    Iadd,
+                    Aload(0),
+                    Iconst2,
+                    Iaload,
+                    Iadd,
+                    Ireturn,
+                ],
+            )
+            .build();
+        let mut vm = vm(&[&class]);
+        assert_eq!(run(&mut vm, &class, "run", "()I"), OperandItem::Integer(6));
+    }
+
+    #[test]
+    fn each_return_kind_hands_back_its_value() {
+        let mut class = ClassBuilder::new("Test");
+        let long = class.pool.add_long(-3).unwrap();
+        let this_class = class.pool.add_class("Test").unwrap();
+        let class = class
+            .method("int", "()I", (1, 0), &[Bipush(-7), Ireturn])
+            .method("long", "()J", (2, 0), &[Ldc2w(long), Lreturn])
+            .method("float", "()F", (1, 0), &[Fconst2, Freturn])
+            .method("double", "()D", (2, 0), &[Dconst1, Dreturn])
+            .method("null", "()LTest;", (1, 0), &[AconstNull, Areturn])
+            .method("object", "()LTest;", (1, 0), &[New(this_class), Areturn])
+            .method("void", "()V", (1, 0), &[Iconst1, Return])
+            .build();
+        let mut vm = vm(&[&class]);
+
+        assert_eq!(run(&mut vm, &class, "int", "()I"), OperandItem::Integer(-7));
+        assert_eq!(run(&mut vm, &class, "long", "()J"), OperandItem::Long(-3));
+        assert_eq!(
+            run(&mut vm, &class, "float", "()F"),
+            OperandItem::Float(2.0)
+        );
+        assert_eq!(
+            run(&mut vm, &class, "double", "()D"),
+            OperandItem::Double(1.0)
+        );
+        assert_eq!(run(&mut vm, &class, "null", "()LTest;"), OperandItem::Null);
+        match run(&mut vm, &class, "object", "()LTest;") {
+            OperandItem::Reference(object) => assert_eq!(vm.heap.get(object).class_name(), "Test"),
+            other => panic!("expected a reference, got {other:?}"),
+        }
+        let mut frame = frame(&class, "void", "()V", Vec::new());
+        assert!(matches!(frame.execute(&mut vm), Ok(FrameResult::Finished)));
+    }
+
+    #[test]
+    fn falling_off_the_end_is_an_error() {
+        let class = ClassBuilder::new("Test")
+            .method("run", "()V", (1, 0), &[Iconst1, Pop])
+            .build();
+        assert!(matches!(
+            ClassLoader::new(Vec::new()).define(&class),
+            Err(ClassLoadingError::Verify {
+                err: VerifyError::FallsOffEnd,
+                ..
+            })
+        ));
+
+        let mut vm = vm(&[]);
+        let mut frame = frame(&class, "run", "()V", Vec::new());
+        assert!(matches!(
+            frame.execute(&mut vm),
+            Err(FrameError::FellOffEnd { name, .. }) if name == "run"
+        ));
+    }
+
+    #[test]
+    fn lmul_wraps_on_overflow() {
+        let mut class = ClassBuilder::new("Test");
+        let max = class.pool.add_long(i64::MAX).unwrap();
+        let three = class.pool.add_long(3).unwrap();
+        let class = class
+            .method(
+                "run",
+                "()J",
+                (4, 0),
+                &[Ldc2w(max), Ldc2w(three), Lmul, Lreturn],
+            )
+            .build();
+        let mut vm = vm(&[&class]);
+        assert_eq!(
+            run(&mut vm, &class, "run", "()J"),
+            OperandItem::Long(i64::MAX.wrapping_mul(3))
+        );
+    }
+
+    #[test]
+    fn zero_divided_by_zero_is_nan() {
+        let class = ClassBuilder::new("Test")
+            .method("run", "()F", (2, 0), &[Fconst0, Fconst0, Fdiv, Freturn])
+            .build();
+        let mut vm = vm(&[&class]);
+        match run(&mut vm, &class, "run", "()F") {
+            OperandItem::Float(value) => assert!(value.is_nan()),
+            other => panic!("expected a float, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn fcmpl_and_fcmpg_differ_on_nan() {
+        let nan = [Fconst0, Fconst0, Fdiv, Fconst1];
+        let class = ClassBuilder::new("Test")
+            .method(
+                "less",
+                "()I",
+                (3, 0),
+                &[&nan[..], &[Fcmpl, Ireturn]].concat(),
+            )
+            .method(
+                "greater",
+                "()I",
+                (3, 0),
+                &[&nan[..], &[Fcmpg, Ireturn]].concat(),
+            )
+            .build();
+        let mut vm = vm(&[&class]);
+        assert_eq!(
+            run(&mut vm, &class, "less", "()I"),
+            OperandItem::Integer(-1)
+        );
+        assert_eq!(
+            run(&mut vm, &class, "greater", "()I"),
+            OperandItem::Integer(1)
+        );
+    }
+
+    #[test]
+    fn i2d_then_d2i_round_trips() {
+        let class = ClassBuilder::new("Test")
+            .method("run", "()I", (2, 0), &[Bipush(7), I2d, D2i, Ireturn])
+            .build();
+        let mut vm = vm(&[&class]);
+        assert_eq!(run(&mut vm, &class, "run", "()I"), OperandItem::Integer(7));
+    }
+
+    #[test]
+    fn f2i_of_nan_is_zero() {
+        let class = ClassBuilder::new("Test")
+            .method(
+                "run",
+                "()I",
+                (2, 0),
+                &[Fconst0, Fconst0, Fdiv, F2i, Ireturn],
+            )
+            .build();
+        let mut vm = vm(&[&class]);
+        assert_eq!(run(&mut vm, &class, "run", "()I"), OperandItem::Integer(0));
+    }
+
+    #[test]
+    fn string_literals_are_interned() {
+        let mut class = ClassBuilder::new("Test");
+        let hello = class.pool.add_string("hello").unwrap() as u8;
+        let class = class
+            .method("run", "()V", (2, 0), &[Ldc(hello), Ldc(hello), Return])
+            .build();
+        let mut vm = vm(&[&class]);
+        let mut frame = frame(&class, "run", "()V", Vec::new());
+        assert!(matches!(frame.execute(&mut vm), Ok(FrameResult::Finished)));
+        match frame.operand_stack.as_slice() {
+            [OperandItem::Reference(first), OperandItem::Reference(second)] => {
+                assert_eq!(first, second);
+                assert_eq!(vm.heap.get(*first).as_string(), Some("hello"));
+            }
+            stack => panic!("expected two references, got {stack:?}"),
+        }
+    }
+
+    #[test]
+    fn frames_of_one_class_share_it() {
+        let mut class = ClassBuilder::new("Test");
+        let callee = class.pool.add_method_ref("Test", "callee", "()I").unwrap();
+        let class = class
+            .method("caller", "()I", (1, 0), &[Invokestatic(callee), Ireturn])
+            .method("callee", "()I", (1, 0), &[Iconst1, Ireturn])
+            .build();
+        let mut vm = vm(&[&class]);
+        let (class, method) = vm
+            .class_loader
+            .resolve_method("Test", "caller", "()I")
+            .unwrap()
+            .unwrap();
+        let mut caller = Frame::new(class, method, Vec::new()).unwrap();
+
+        match caller.execute(&mut vm) {
+            Ok(FrameResult::NextFrame(callee)) => {
+                assert!(Rc::ptr_eq(&caller.class, &callee.class));
+                assert!(Rc::ptr_eq(
+                    &caller.class,
+                    vm.class_loader.load("Test").unwrap()
+                ));
+            }
+            _ => panic!("expected a frame for the callee"),
+        }
+    }
+
+    #[test]
+    fn division_by_zero_reaches_its_handler() {
+        let mut class = ClassBuilder::new("Test");
+        let arithmetic = class
+            .pool
+            .add_class("java/lang/ArithmeticException")
+            .unwrap();
+        let class = class
+            .method_with_handlers(
+                "run",
+                "()I",
+                (2, 0),
+                &[Iconst1, Iconst0, Idiv, Ireturn, Pop, IconstM1, Ireturn],
+                vec![ExceptionTableEntry {
+                    start_pc: 0,
+                    end_pc: 4,
+                    handler_pc: 4,
+                    catch_type: arithmetic,
+                }],
+            )
+            .build();
+        let mut vm = vm(&[&class]);
+        assert_eq!(run(&mut vm, &class, "run", "()I"), OperandItem::Integer(-1));
+    }
+
+    #[test]
+    fn dereferencing_null_reaches_its_handler() {
+        let mut class = ClassBuilder::new("Test");
+        let npe = class
+            .pool
+            .add_class("java/lang/NullPointerException")
+            .unwrap();
+        let class = class
+            .method_with_handlers(
+                "run",
+                "()I",
+                (1, 0),
+                &[AconstNull, Arraylength, Ireturn, Pop, Iconst5, Ireturn],
+                vec![ExceptionTableEntry {
+                    start_pc: 0,
+                    end_pc: 3,
+                    handler_pc: 3,
+                    catch_type: npe,
+                }],
+            )
+            .build();
+        let mut vm = vm(&[&class]);
+        assert_eq!(run(&mut vm, &class, "run", "()I"), OperandItem::Integer(5));
+    }
+
+    #[test]
+    fn reference_array_stores_and_loads() {
+        let mut class = ClassBuilder::new("Test");
+        let string = class.pool.add_class("java/lang/String").unwrap();
+        let hello = class.pool.add_string("hello").unwrap();
+        let store = [
+            Iconst3,
+            Anewarray(string),
+            Astore(0),
+            Aload(0),
+            Iconst2,
+            Ldc(hello as u8),
+            Aastore,
+        ];
+        let read = |index| [&store[..], &[Aload(0), index, Aaload, Areturn]].concat();
+        let class = class
+            .method("last", "()Ljava/lang/Object;", (3, 1), &read(Iconst2))
+            .method("first", "()Ljava/lang/Object;", (3, 1), &read(Iconst0))
+            .method(
+                "length",
+                "()I",
+                (3, 1),
+                &[&store[..], &[Aload(0), Arraylength, Ireturn]].concat(),
+            )
+            .build();
+        let mut vm = vm(&[&class]);
+
+        match run(&mut vm, &class, "last", "()Ljava/lang/Object;") {
+            OperandItem::Reference(string) => {
+                assert_eq!(vm.heap.get(string).as_string(), Some("hello"))
+            }
+            other => panic!("expected a reference, got {other:?}"),
+        }
+        assert_eq!(
+            run(&mut vm, &class, "first", "()Ljava/lang/Object;"),
+            OperandItem::Null
+        );
+        assert_eq!(
+            run(&mut vm, &class, "length", "()I"),
+            OperandItem::Integer(3)
+        );
+    }
+
+    #[test]
+    fn reference_array_index_is_bounds_checked() {
+        let mut class = ClassBuilder::new("Test");
+        let object = class.pool.add_class("java/lang/Object").unwrap();
+        let class = class
+            .method(
+                "run",
+                "()V",
+                (3, 0),
+                &[Iconst3, Anewarray(object), Iconst3, Aaload, Pop, Return],
+            )
+            .build();
+        let mut vm = vm(&[&class]);
+        let mut frame = frame(&class, "run", "()V", Vec::new());
+        match frame.execute(&mut vm) {
+            Ok(FrameResult::Thrown(exception)) => assert_eq!(
+                vm.heap.get(exception).class_name(),
+                "java/lang/ArrayIndexOutOfBoundsException"
+            ),
+            Ok(_) => panic!("expected an exception"),
+            Err(err) => panic!("failed: {err}"),
+        }
+    }
+
+    #[test]
+    fn concatenates_with_invokedynamic() {
+        const CONCAT: &[u8] = include_bytes!("../../tests/fixtures/Concat.class");
+        let mut vm = vm(&[CONCAT]);
+        let mut call =
+            |name, descriptor, arguments| match frame(CONCAT, name, descriptor, arguments)
+                .execute(&mut vm)
+            {
+                Ok(FrameResult::Returned(OperandItem::Reference(string))) => {
+                    vm.heap.get(string).as_string().unwrap().to_owned()
+                }
+                _ => panic!("{name} did not return a string"),
+            };
+
+        assert_eq!(
+            call(
+                "literalAndInt",
+                "(I)Ljava/lang/String;",
+                vec![OperandItem::Integer(5)]
+            ),
+            "x=5"
+        );
+        assert_eq!(
+            call(
+                "floats",
+                "(FD)Ljava/lang/String;",
+                vec![
+                    OperandItem::Float(1.0e10),
+                    OperandItem::Double(0.5),
+                    OperandItem::Padding
+                ]
+            ),
+            "1.0E10 0.5"
+        );
+    }
+
+    #[test]
+    fn class_literal_names_its_class() {
+        let mut class = ClassBuilder::new("Test");
+        let some_class = class.pool.add_class("pkg/SomeClass").unwrap();
+        let get_name = class
+            .pool
+            .add_method_ref("java/lang/Class", "getName", "()Ljava/lang/String;")
+            .unwrap();
+        let class = class
+            .method(
+                "run",
+                "()Ljava/lang/String;",
+                (1, 0),
+                &[Ldc(some_class as u8), Invokevirtual(get_name), Areturn],
+            )
+            .build();
+        let mut vm = vm(&[&class]);
+        match run(&mut vm, &class, "run", "()Ljava/lang/String;") {
+            OperandItem::Reference(name) => {
+                assert_eq!(vm.heap.get(name).as_string(), Some("pkg.SomeClass"));
+            }
+            value => panic!("expected a string, got {value:?}"),
+        }
+    }
+
+    #[test]
+    fn ldc_and_ldc_w_share_a_class_mirror() {
+        let mut class = ClassBuilder::new("Test");
+        let some_class = class.pool.add_class("SomeClass").unwrap();
+        let class = class
+            .method(
+                "run",
+                "()V",
+                (2, 0),
+                &[Ldc(some_class as u8), Ldcw(some_class), Return],
+            )
+            .build();
+        let mut vm = vm(&[&class]);
+        let mut frame = frame(&class, "run", "()V", Vec::new());
+        assert!(matches!(frame.execute(&mut vm), Ok(FrameResult::Finished)));
+        match frame.operand_stack.as_slice() {
+            [OperandItem::Reference(first), OperandItem::Reference(second)] => {
+                assert_eq!(first, second);
+                assert_eq!(vm.heap.get(*first).as_class(), Some("SomeClass"));
+            }
+            stack => panic!("expected two references, got {stack:?}"),
+        }
+    }
+
+    #[test]
+    fn long_arguments_take_two_local_slots() {
+        let mut class = ClassBuilder::new("Test");
+        let callee = class
+            .pool
+            .add_method_ref("Test", "callee", "(IJI)I")
+            .unwrap();
+        let long = class.pool.add_long(5_000_000_000).unwrap();
+        let class = class
+            .method(
+                "caller",
+                "()I",
+                (4, 0),
+                &[
+                    Iconst1,
+                    Ldc2w(long),
+                    Bipush(7),
+                    Invokestatic(callee),
+                    Ireturn,
+                ],
+            )
+            .method(
+                "callee",
+                "(IJI)I",
+                (2, 4),
+                &[Lload(1), L2i, Iload(3), Iadd, Ireturn],
+            )
+            .build();
+        let mut vm = vm(&[&class]);
+        let mut callee = match frame(&class, "caller", "()I", Vec::new()).execute(&mut vm) {
+            Ok(FrameResult::NextFrame(callee)) => callee,
+            Ok(_) => panic!("expected a call"),
+            Err(err) => panic!("caller failed: {err}"),
+        };
+        assert_eq!(
+            callee.locals,
+            [
+                OperandItem::Integer(1),
+                OperandItem::Long(5_000_000_000),
+                OperandItem::Padding,
+                OperandItem::Integer(7),
+            ]
+        );
+        assert!(matches!(
+            callee.execute(&mut vm),
+            Ok(FrameResult::Returned(OperandItem::Integer(705_032_711)))
+        ));
+    }
+
+    #[test]
+    fn unimplemented_instructions_are_reported() {
+        let mut class = ClassBuilder::new("Test");
+        let object = class.pool.add_class("java/lang/Object").unwrap();
+        let class = class
+            .method(
+                "checkcast",
+                "()V",
+                (1, 0),
+                &[AconstNull, Checkcast(object), Return],
+            )
+            .method("jsr", "()V", (1, 0), &[Jsr(3), Return])
+            .build();
+        let mut vm = vm(&[]);
+        for (name, opcode) in [("checkcast", 0xc0), ("jsr", 0xa8)] {
+            assert!(matches!(
+                frame(&class, name, "()V", Vec::new()).execute(&mut vm),
+                Err(FrameError::UnsupportedInstruction(found)) if found == opcode
+            ));
+        }
+    }
+
+    #[test]
+    fn if_icmplt_loops_backward() {
+        // int sum = 0; for (int i = 0; i < 5; i++) sum += i; return sum;
+        let code = |back| {
+            vec![
+                Iconst0,
+                Istore(0),
+                Iconst0,
+                Istore(1),
+                Iload(1),
+                Iload(0),
+                Iadd,
+                Istore(1),
+                Iinc {
+                    index: 0,
+                    constant: 1,
+                    wide: false,
+                },
+                Iload(0),
+                Iconst5,
+                Ificmp(ComparisonKind::Lt, back),
+                Iload(1),
+                Ireturn,
+            ]
+        };
+        let offsets = with_offsets(&code(0));
+        let back = offsets[4].0 as i16 - offsets[11].0 as i16;
+        let class = ClassBuilder::new("Test")
+            .method("run", "()I", (2, 2), &code(back))
+            .build();
+        let mut vm = vm(&[&class]);
+        assert_eq!(run(&mut vm, &class, "run", "()I"), OperandItem::Integer(10));
+    }
+
+    #[test]
+    fn conditional_branches_follow_their_offsets() {
+        // Each method returns 1 if its branch is taken and 0 otherwise.
+        let branch = |setup: &[Instruction], branch: Instruction| {
+            let mut code = setup.to_vec();
+            code.extend([branch, Iconst0, Ireturn, Iconst1, Ireturn]);
+            code
+        };
+        let taken = |setup: &[Instruction], kind: fn(i16) -> Instruction| {
+            let skip = with_offsets(&branch(setup, kind(0)));
+            let offset = skip[setup.len() + 3].0 - skip[setup.len()].0;
+            branch(setup, kind(offset as i16))
+        };
+        let class = ClassBuilder::new("Test")
+            .method(
+                "ifle",
+                "()I",
+                (1, 0),
+                &taken(&[IconstM1], |offset| If(ComparisonKind::Le, offset)),
+            )
+            .method(
+                "ifne",
+                "()I",
+                (1, 0),
+                &taken(&[Iconst0], |offset| If(ComparisonKind::Ne, offset)),
+            )
+            .method(
+                "if_acmpeq",
+                "()I",
+                (2, 0),
+                &taken(&[AconstNull, AconstNull], |offset| {
+                    Ifacmp(ComparisonKind::Eq, offset)
+                }),
+            )
+            .method("ifnull", "()I", (1, 0), &taken(&[AconstNull], Ifnull))
+            .method("ifnonnull", "()I", (1, 0), &taken(&[AconstNull], Ifnonnull))
+            .build();
+        let mut vm = vm(&[&class]);
+        for (name, expected) in [
+            ("ifle", 1),
+            ("ifne", 0),
+            ("if_acmpeq", 1),
+            ("ifnull", 1),
+            ("ifnonnull", 0),
+        ] {
+            assert_eq!(
+                run(&mut vm, &class, name, "()I"),
+                OperandItem::Integer(expected),
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn switches_jump_to_the_matching_case() {
+        // switch (n) { case 1: return 10; case 2: return 20; default: return -1; }
+        let cases = |switch: Instruction| {
+            vec![
+                Iload(0),
+                switch,
+                Bipush(10),
+                Ireturn,
+                Bipush(20),
+                Ireturn,
+                IconstM1,
+                Ireturn,
+            ]
+        };
+        // Builds the switch with its targets relative to itself, given the
+        // targets as offsets into the code.
+        let resolve = |switch: fn(i32, i32, i32) -> Instruction| {
+            let offsets = with_offsets(&cases(switch(0, 0, 0)));
+            let relative = |index: usize| offsets[index].0 as i32 - offsets[1].0 as i32;
+            cases(switch(relative(6), relative(2), relative(4)))
+        };
+        let lookup = resolve(|default, one, two| Lookupswitch {
+            default,
+            pairs: vec![(1, one), (2, two)],
+        });
+        let table = resolve(|default, one, two| Tableswitch {
+            default,
+            low: 1,
+            high: 2,
+            offsets: vec![one, two],
+        });
+        let class = ClassBuilder::new("Test")
+            .method("lookup", "(I)I", (1, 1), &lookup)
+            .method("table", "(I)I", (1, 1), &table)
+            .build();
+        let mut vm = vm(&[&class]);
+        for name in ["lookup", "table"] {
+            for (n, expected) in [(1, 10), (2, 20), (3, -1), (i32::MIN, -1)] {
+                let result =
+                    frame(&class, name, "(I)I", vec![OperandItem::Integer(n)]).execute(&mut vm);
+                assert!(
+                    matches!(result, Ok(FrameResult::Returned(OperandItem::Integer(value))) if value == expected),
+                    "{name}({n})"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn runs_a_counted_loop_compiled_by_javac() {
+        // Sample.sum(n) adds up 0 to n - 1 with a for loop, whose increment
+        // is an iinc and whose condition is an if_icmpge.
+        const SAMPLE: &[u8] =
+            include_bytes!("../../crates/runevm_classfile/tests/fixtures/Sample.class");
+        let mut vm = vm(&[SAMPLE]);
+        let mut sum =
+            |n| frame(SAMPLE, "sum", "(I)I", vec![OperandItem::Integer(n)]).execute(&mut vm);
+        assert!(matches!(
+            sum(5),
+            Ok(FrameResult::Returned(OperandItem::Integer(10)))
+        ));
+        assert!(matches!(
+            sum(0),
+            Ok(FrameResult::Returned(OperandItem::Integer(0)))
+        ));
+    }
+
+    #[test]
+    fn dup2_of_two_ints_duplicates_both() {
+        let class = ClassBuilder::new("Test")
+            .method("run", "()V", (4, 0), &[Iconst1, Iconst2, Dup2, Return])
+            .build();
+        let mut vm = vm(&[&class]);
+        let mut frame = frame(&class, "run", "()V", Vec::new());
+        assert!(matches!(frame.execute(&mut vm), Ok(FrameResult::Finished)));
+        assert_eq!(frame.operand_stack, [1, 2, 1, 2].map(OperandItem::Integer));
+    }
+
+    #[test]
+    fn dup2_of_a_long_duplicates_one_value() {
+        let class = ClassBuilder::new("Test")
+            .method("run", "()V", (4, 0), &[Lconst1, Dup2, Return])
+            .build();
+        let mut vm = vm(&[&class]);
+        let mut frame = frame(&class, "run", "()V", Vec::new());
+        assert!(matches!(frame.execute(&mut vm), Ok(FrameResult::Finished)));
+        assert_eq!(
+            frame.operand_stack,
+            [
+                OperandItem::Long(1),
+                OperandItem::Padding,
+                OperandItem::Long(1),
+                OperandItem::Padding,
+            ]
+        );
+    }
+
+    #[test]
+    fn dup2_cannot_split_a_long() {
+        let class = ClassBuilder::new("Test")
+            .method("run", "()V", (4, 0), &[Lconst1, Iconst1, Dup2, Return])
+            .build();
+        let mut vm = vm(&[]);
+        assert!(matches!(
+            frame(&class, "run", "()V", Vec::new()).execute(&mut vm),
+            Err(FrameError::TypeMismatch)
+        ));
+    }
+
+    #[test]
+    fn multianewarray_builds_nested_arrays() {
+        let mut class = ClassBuilder::new("Test");
+        let matrix = class.pool.add_class("[[I").unwrap();
+        let create = [Iconst2, Iconst3, Multianewarray(matrix, 2), Astore(0)];
+        let length = |code: &[Instruction]| [&create[..], code, &[Arraylength, Ireturn]].concat();
+        let class = class
+            .method("rows", "()I", (2, 1), &length(&[Aload(0)]))
+            .method(
+                "columns",
+                "()I",
+                (2, 1),
+                &length(&[Aload(0), Iconst1, Aaload]),
+            )
+            .method(
+                "cell",
+                "()I",
+                (3, 1),
+                &[
+                    &create[..],
+                    &[Aload(0), Iconst1, Aaload, Iconst2, Iaload, Ireturn],
+                ]
+                .concat(),
+            )
+            .build();
+        let mut vm = vm(&[&class]);
+
+        assert_eq!(run(&mut vm, &class, "rows", "()I"), OperandItem::Integer(2));
+        assert_eq!(
+            run(&mut vm, &class, "columns", "()I"),
+            OperandItem::Integer(3)
+        );
+        assert_eq!(run(&mut vm, &class, "cell", "()I"), OperandItem::Integer(0));
+    }
+
+    #[test]
+    fn wide_loads_and_stores_reach_every_local() {
+        let class = ClassBuilder::new("Test")
+            .method(
+                "run",
+                "()I",
+                (2, 301),
+                &[
+                    Bipush(40),
+                    Istore(300),
+                    Iconst2,
+                    Wide(0x36, 2),
+                    Iload(300),
+                    Wide(0x15, 2),
+                    Iadd,
+                    Ireturn,
+                ],
+            )
+            .build();
+        let mut vm = vm(&[&class]);
+        assert_eq!(run(&mut vm, &class, "run", "()I"), OperandItem::Integer(42));
+    }
+
+    #[test]
+    fn arguments_beyond_max_locals_are_an_error() {
+        let class = ClassBuilder::new("Test")
+            .method("run", "(I)V", (0, 1), &[Return])
+            .build();
+        let class = Rc::new(parse_class(&class).unwrap().1);
+        let arguments = vec![OperandItem::Integer(1), OperandItem::Integer(2)];
+        match Frame::new(class, 0, arguments) {
+            Err(FrameError::TooManyArguments {
+                class,
+                name,
+                arguments: 2,
+                max_locals: 1,
+                ..
+            }) => assert_eq!((class.as_str(), name.as_str()), ("Test", "run")),
+            Err(err) => panic!("unexpected error {err}"),
+            Ok(_) => panic!("expected too many arguments"),
+        }
+    }
+
+    #[test]
+    fn invoking_a_native_method_without_a_native_fails() {
+        let mut class = ClassBuilder::new("Test");
+        let answer = class.pool.add_method_ref("Test", "answer", "()I").unwrap();
+        let class = class
+            .native_method("answer", "()I")
+            .method("run", "()I", (1, 0), &[Invokestatic(answer), Ireturn])
+            .build();
+        let mut vm = vm(&[&class]);
+
+        match frame(&class, "run", "()I", Vec::new()).execute(&mut vm) {
+            Err(FrameError::NoSuchMethod { class, name, .. }) => {
+                assert_eq!((class.as_str(), name.as_str()), ("Test", "answer"))
+            }
+            Err(err) => panic!("unexpected error {err}"),
+            Ok(_) => panic!("expected the call to fail"),
+        }
+        match frame(&class, "answer", "()I", Vec::new()).execute(&mut vm) {
+            Err(FrameError::NoCode { name, .. }) => assert_eq!(name, "answer"),
+            Err(err) => panic!("unexpected error {err}"),
+            Ok(_) => panic!("expected a frame without code to fail"),
+        }
+    }
+
+    #[test]
+    fn invoking_a_missing_method_fails() {
+        let mut class = ClassBuilder::new("Test");
+        let missing = class.pool.add_method_ref("Test", "missing", "()V").unwrap();
+        let class = class
+            .method("run", "()V", (0, 0), &[Invokestatic(missing), Return])
+            .build();
+        let mut vm = vm(&[&class]);
+
+        let parsed = parse_class(&class).unwrap().1;
+        assert!(parsed.get_method("missing", "()V").is_none());
+        assert!(parsed.get_method("run", "()I").is_none());
+        assert!(matches!(
+            vm.class_loader.resolve_method("Test", "missing", "()V"),
+            Ok(None)
+        ));
+        match frame(&class, "run", "()V", Vec::new()).execute(&mut vm) {
+            Err(FrameError::NoSuchMethod {
+                class,
+                name,
+                descriptor,
+            }) => assert_eq!(
+                (class.as_str(), name.as_str(), descriptor.as_str()),
+                ("Test", "missing", "()V")
+            ),
+            Err(err) => panic!("unexpected error {err}"),
+            Ok(_) => panic!("expected the call to fail"),
+        }
+    }
+}
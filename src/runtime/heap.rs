@@ -0,0 +1,171 @@
+use super::object::{Array, Object};
+use std::collections::HashMap;
+
+/// A handle to an object on the [`Heap`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Reference(usize);
+
+/// Something that lives on the heap: an instance of a class, an array, a
+/// `java/lang/String`, which holds its text directly, or a `java/lang/Class`,
+/// which holds the binary name of the class it mirrors.
+pub enum HeapObject {
+    Instance(Object),
+    Array(Array),
+    String(String),
+    Class(String),
+}
+
+impl HeapObject {
+    /// The binary name of the object's class.
+    pub fn class_name(&self) -> &str {
+        match self {
+            HeapObject::Instance(object) => &object.name,
+            HeapObject::Array(array) => &array.name,
+            HeapObject::String(_) => "java/lang/String",
+            HeapObject::Class(_) => "java/lang/Class",
+        }
+    }
+
+    pub fn as_instance(&self) -> Option<&Object> {
+        match self {
+            HeapObject::Instance(object) => Some(object),
+            _ => None,
+        }
+    }
+
+    pub fn as_instance_mut(&mut self) -> Option<&mut Object> {
+        match self {
+            HeapObject::Instance(object) => Some(object),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&Array> {
+        match self {
+            HeapObject::Array(array) => Some(array),
+            _ => None,
+        }
+    }
+
+    pub fn as_array_mut(&mut self) -> Option<&mut Array> {
+        match self {
+            HeapObject::Array(array) => Some(array),
+            _ => None,
+        }
+    }
+
+    pub fn as_string(&self) -> Option<&str> {
+        match self {
+            HeapObject::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// The binary name of the class this object mirrors, if it's a
+    /// `java/lang/Class`.
+    pub fn as_class(&self) -> Option<&str> {
+        match self {
+            HeapObject::Class(name) => Some(name),
+            _ => None,
+        }
+    }
+}
+
+/// Owns every object the program allocates. Objects are never freed yet.
+#[derive(Default)]
+pub struct Heap {
+    objects: Vec<HeapObject>,
+    /// The strings created from string constants, so that equal literals
+    /// are the same object.
+    interned: HashMap<String, Reference>,
+    /// The `java/lang/Class` object of each class that has been asked for,
+    /// keyed by binary name.
+    mirrors: HashMap<String, Reference>,
+    /// How many times each held monitor has been entered and not yet exited.
+    /// There is only one thread, so it owns every monitor that is held.
+    monitors: HashMap<Reference, u32>,
+}
+
+impl Heap {
+    pub fn new() -> Heap {
+        Heap::default()
+    }
+
+    pub fn allocate(&mut self, object: Object) -> Reference {
+        self.push(HeapObject::Instance(object))
+    }
+
+    pub fn allocate_array(&mut self, array: Array) -> Reference {
+        self.push(HeapObject::Array(array))
+    }
+
+    pub fn allocate_string(&mut self, value: String) -> Reference {
+        self.push(HeapObject::String(value))
+    }
+
+    /// Returns the one string object holding `value`, allocating it the
+    /// first time it is asked for.
+    pub fn intern(&mut self, value: &str) -> Reference {
+        if let Some(&reference) = self.interned.get(value) {
+            return reference;
+        }
+
+        let reference = self.allocate_string(value.to_owned());
+        self.interned.insert(value.to_owned(), reference);
+        reference
+    }
+
+    /// Returns the one `java/lang/Class` object for the class with the given
+    /// binary name, allocating it the first time it is asked for.
+    pub fn mirror(&mut self, class: &str) -> Reference {
+        if let Some(&reference) = self.mirrors.get(class) {
+            return reference;
+        }
+
+        let reference = self.push(HeapObject::Class(class.to_owned()));
+        self.mirrors.insert(class.to_owned(), reference);
+        reference
+    }
+
+    fn push(&mut self, object: HeapObject) -> Reference {
+        self.objects.push(object);
+        Reference(self.objects.len() - 1)
+    }
+
+    /// Enters the monitor of the object `reference` points to. Monitors are
+    /// reentrant, so entering one that is already held just counts again.
+    pub fn enter_monitor(&mut self, reference: Reference) {
+        *self.monitors.entry(reference).or_insert(0) += 1;
+    }
+
+    /// Exits the monitor of the object `reference` points to once, returning
+    /// `false` if it isn't held.
+    pub fn exit_monitor(&mut self, reference: Reference) -> bool {
+        let count = match self.monitors.get_mut(&reference) {
+            Some(count) => count,
+            None => return false,
+        };
+        *count -= 1;
+        if *count == 0 {
+            self.monitors.remove(&reference);
+        }
+        true
+    }
+
+    pub fn get(&self, reference: Reference) -> &HeapObject {
+        &self.objects[reference.0]
+    }
+
+    pub fn get_mut(&mut self, reference: Reference) -> &mut HeapObject {
+        &mut self.objects[reference.0]
+    }
+
+    /// The number of objects that have been allocated.
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+}
@@ -0,0 +1,58 @@
+use std::{fs::File, io::Read, path::Path};
+use zip::{result::ZipError, ZipArchive};
+
+/// A jar archive that classes can be loaded from.
+pub struct JarClassSource {
+    archive: ZipArchive<File>,
+}
+
+impl JarClassSource {
+    pub fn open(path: &Path) -> Result<JarClassSource, ZipError> {
+        Ok(JarClassSource {
+            archive: ZipArchive::new(File::open(path)?)?,
+        })
+    }
+
+    /// Reads the class file for the class with the given binary name, or
+    /// returns `None` if the archive doesn't hold it.
+    pub fn read_class(&mut self, name: &str) -> Result<Option<Vec<u8>>, ZipError> {
+        self.read(&format!("{name}.class"))
+    }
+
+    /// Returns the binary name of the class the manifest names as the
+    /// application's entry point, if it names one.
+    pub fn main_class(&mut self) -> Result<Option<String>, ZipError> {
+        let manifest = match self.read("META-INF/MANIFEST.MF")? {
+            Some(manifest) => String::from_utf8_lossy(&manifest).into_owned(),
+            None => return Ok(None),
+        };
+
+        // Long lines are wrapped, with each continuation line starting with
+        // a space.
+        let mut lines: Vec<String> = Vec::new();
+        for line in manifest.lines() {
+            match (line.strip_prefix(' '), lines.last_mut()) {
+                (Some(rest), Some(last)) => last.push_str(rest),
+                _ => lines.push(line.to_owned()),
+            }
+        }
+
+        Ok(lines.iter().find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            key.eq_ignore_ascii_case("Main-Class")
+                .then(|| value.trim().replace('.', "/"))
+        }))
+    }
+
+    fn read(&mut self, entry: &str) -> Result<Option<Vec<u8>>, ZipError> {
+        let mut file = match self.archive.by_name(entry) {
+            Ok(file) => file,
+            Err(ZipError::FileNotFound) => return Ok(None),
+            Err(err) => return Err(err),
+        };
+
+        let mut bytes = Vec::with_capacity(file.size() as usize);
+        file.read_to_end(&mut bytes).map_err(ZipError::Io)?;
+        Ok(Some(bytes))
+    }
+}
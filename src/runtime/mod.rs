@@ -1,3 +1,11 @@
+pub mod classloader;
 pub mod frame;
+pub mod heap;
+pub mod jar;
+pub mod natives;
 pub mod object;
+#[cfg(test)]
+mod testing;
 pub mod thread;
+pub mod trace;
+pub mod vm;
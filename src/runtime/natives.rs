@@ -0,0 +1,333 @@
+use super::{
+    frame::{Frame, FrameError, OperandItem},
+    heap::Heap,
+};
+use std::collections::HashMap;
+
+/// A method implemented by the VM rather than by bytecode. It receives the
+/// popped arguments, including the receiver for instance methods, and returns
+/// the value to push onto the caller's operand stack, if any.
+pub type NativeMethod =
+    fn(&mut Frame, &mut Heap, Vec<OperandItem>) -> Result<Option<OperandItem>, FrameError>;
+
+pub struct NativeRegistry {
+    methods: HashMap<(String, String, String), NativeMethod>,
+}
+
+impl Default for NativeRegistry {
+    fn default() -> Self {
+        NativeRegistry::new()
+    }
+}
+
+impl NativeRegistry {
+    pub fn new() -> NativeRegistry {
+        let mut registry = NativeRegistry {
+            methods: HashMap::new(),
+        };
+
+        registry.register(
+            "java/io/PrintStream",
+            "println",
+            "(Ljava/lang/String;)V",
+            println_string,
+        );
+        registry.register("java/io/PrintStream", "println", "(I)V", println_int);
+        registry.register("java/io/PrintStream", "println", "(J)V", println_long);
+        registry.register("java/io/PrintStream", "println", "(F)V", println_float);
+        registry.register("java/io/PrintStream", "println", "(D)V", println_double);
+        registry.register("java/lang/Object", "<init>", "()V", no_op);
+        registry.register(
+            "java/lang/Object",
+            "getClass",
+            "()Ljava/lang/Class;",
+            object_get_class,
+        );
+        registry.register(
+            "java/lang/Class",
+            "getName",
+            "()Ljava/lang/String;",
+            class_get_name,
+        );
+        registry.register("java/lang/Throwable", "<init>", "()V", no_op);
+        registry.register(
+            "java/lang/Throwable",
+            "<init>",
+            "(Ljava/lang/String;)V",
+            throwable_init,
+        );
+        registry.register(
+            "java/lang/Throwable",
+            "getMessage",
+            "()Ljava/lang/String;",
+            throwable_get_message,
+        );
+
+        registry
+    }
+
+    pub fn register(&mut self, class: &str, name: &str, descriptor: &str, method: NativeMethod) {
+        self.methods.insert(
+            (class.to_owned(), name.to_owned(), descriptor.to_owned()),
+            method,
+        );
+    }
+
+    pub fn get(&self, class: &str, name: &str, descriptor: &str) -> Option<NativeMethod> {
+        let key = (class.to_owned(), name.to_owned(), descriptor.to_owned());
+        self.methods.get(&key).copied()
+    }
+}
+
+fn no_op(
+    _: &mut Frame,
+    _: &mut Heap,
+    _: Vec<OperandItem>,
+) -> Result<Option<OperandItem>, FrameError> {
+    Ok(None)
+}
+
+fn object_get_class(
+    _: &mut Frame,
+    heap: &mut Heap,
+    args: Vec<OperandItem>,
+) -> Result<Option<OperandItem>, FrameError> {
+    match args.as_slice() {
+        [OperandItem::Reference(this)] => {
+            let class = heap.get(*this).class_name().to_owned();
+            Ok(Some(OperandItem::Reference(heap.mirror(&class))))
+        }
+        _ => Err(FrameError::TypeMismatch),
+    }
+}
+
+/// Returns the name of the class the way Java spells it, with dots between
+/// packages. Array classes keep their descriptor form, like `[I`.
+fn class_get_name(
+    _: &mut Frame,
+    heap: &mut Heap,
+    args: Vec<OperandItem>,
+) -> Result<Option<OperandItem>, FrameError> {
+    match args.as_slice() {
+        [OperandItem::Reference(this)] => {
+            let name = heap
+                .get(*this)
+                .as_class()
+                .ok_or(FrameError::TypeMismatch)?
+                .replace('/', ".");
+            Ok(Some(OperandItem::Reference(heap.allocate_string(name))))
+        }
+        _ => Err(FrameError::TypeMismatch),
+    }
+}
+
+fn println_string(
+    _: &mut Frame,
+    heap: &mut Heap,
+    args: Vec<OperandItem>,
+) -> Result<Option<OperandItem>, FrameError> {
+    match args.as_slice() {
+        [OperandItem::SystemOut, OperandItem::Reference(value)] => {
+            let value = heap
+                .get(*value)
+                .as_string()
+                .ok_or(FrameError::TypeMismatch)?;
+            println!("{value}");
+        }
+        [OperandItem::SystemOut, OperandItem::Null] => println!("null"),
+        _ => return Err(FrameError::TypeMismatch),
+    }
+    Ok(None)
+}
+
+fn println_int(
+    _: &mut Frame,
+    _: &mut Heap,
+    args: Vec<OperandItem>,
+) -> Result<Option<OperandItem>, FrameError> {
+    match args.as_slice() {
+        [OperandItem::SystemOut, OperandItem::Integer(value)] => println!("{value}"),
+        _ => return Err(FrameError::TypeMismatch),
+    }
+    Ok(None)
+}
+
+fn println_long(
+    _: &mut Frame,
+    _: &mut Heap,
+    args: Vec<OperandItem>,
+) -> Result<Option<OperandItem>, FrameError> {
+    match args.as_slice() {
+        [OperandItem::SystemOut, OperandItem::Long(value)] => {
+            println!("{value}")
+        }
+        _ => return Err(FrameError::TypeMismatch),
+    }
+    Ok(None)
+}
+
+fn println_float(
+    _: &mut Frame,
+    _: &mut Heap,
+    args: Vec<OperandItem>,
+) -> Result<Option<OperandItem>, FrameError> {
+    match args.as_slice() {
+        [OperandItem::SystemOut, OperandItem::Float(value)] => {
+            println!("{}", format_float(*value))
+        }
+        _ => return Err(FrameError::TypeMismatch),
+    }
+    Ok(None)
+}
+
+fn println_double(
+    _: &mut Frame,
+    _: &mut Heap,
+    args: Vec<OperandItem>,
+) -> Result<Option<OperandItem>, FrameError> {
+    match args.as_slice() {
+        [OperandItem::SystemOut, OperandItem::Double(value)] => {
+            println!("{}", format_double(*value))
+        }
+        _ => return Err(FrameError::TypeMismatch),
+    }
+    Ok(None)
+}
+
+fn throwable_init(
+    _: &mut Frame,
+    heap: &mut Heap,
+    args: Vec<OperandItem>,
+) -> Result<Option<OperandItem>, FrameError> {
+    match args.as_slice() {
+        [OperandItem::Reference(this), message] => {
+            let field = heap
+                .get_mut(*this)
+                .as_instance_mut()
+                .and_then(|this| this.field_mut("detailMessage", "Ljava/lang/String;"))
+                .ok_or(FrameError::TypeMismatch)?;
+            *field = message.clone();
+        }
+        _ => return Err(FrameError::TypeMismatch),
+    }
+    Ok(None)
+}
+
+fn throwable_get_message(
+    _: &mut Frame,
+    heap: &mut Heap,
+    args: Vec<OperandItem>,
+) -> Result<Option<OperandItem>, FrameError> {
+    match args.as_slice() {
+        [OperandItem::Reference(this)] => Ok(heap
+            .get(*this)
+            .as_instance()
+            .and_then(|this| this.field("detailMessage", "Ljava/lang/String;"))
+            .cloned()),
+        _ => Err(FrameError::TypeMismatch),
+    }
+}
+
+/// Formats a float the way `Float.toString` does, like `1.0E10` or `NaN`.
+pub fn format_float(value: f32) -> String {
+    java_decimal(&format!("{value:e}"))
+}
+
+/// Formats a double the way `Double.toString` does, like `1.0E10` or `NaN`.
+pub fn format_double(value: f64) -> String {
+    java_decimal(&format!("{value:e}"))
+}
+
+/// Rewrites the shortest scientific notation Rust gives a float, like
+/// `-1.5e-5`, the way Java writes it. Magnitudes from 10^-3 up to 10^7 are
+/// written as plain decimals and the rest in scientific notation with an
+/// `E`, both always with a digit after the point.
+fn java_decimal(scientific: &str) -> String {
+    let (mantissa, exponent) = match scientific {
+        "NaN" => return "NaN".to_owned(),
+        "inf" => return "Infinity".to_owned(),
+        "-inf" => return "-Infinity".to_owned(),
+        _ => match scientific.split_once('e') {
+            Some((mantissa, exponent)) => (mantissa, exponent.parse::<i32>().unwrap_or(0)),
+            None => return scientific.to_owned(),
+        },
+    };
+    let (sign, mantissa) = match mantissa.strip_prefix('-') {
+        Some(mantissa) => ("-", mantissa),
+        None => ("", mantissa),
+    };
+    let digits: String = mantissa.chars().filter(|c| *c != '.').collect();
+
+    if digits == "0" {
+        format!("{sign}0.0")
+    } else if (0..7).contains(&exponent) {
+        let point = exponent as usize + 1;
+        if digits.len() > point {
+            format!("{sign}{}.{}", &digits[..point], &digits[point..])
+        } else {
+            format!("{sign}{digits:0<point$}.0")
+        }
+    } else if (-3..0).contains(&exponent) {
+        let zeros = "0".repeat((-exponent - 1) as usize);
+        format!("{sign}0.{zeros}{digits}")
+    } else if digits.len() > 1 {
+        format!("{sign}{}.{}E{exponent}", &digits[..1], &digits[1..])
+    } else {
+        format!("{sign}{digits}.0E{exponent}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::testing::{run, vm, ClassBuilder};
+    use runevm_classfile::Instruction::*;
+
+    #[test]
+    fn formats_floats_like_java() {
+        assert_eq!(format_float(1.0), "1.0");
+        assert_eq!(format_float(-0.0), "-0.0");
+        assert_eq!(format_float(0.1), "0.1");
+        assert_eq!(format_float(1.5e-3), "0.0015");
+        assert_eq!(format_float(1.0e-4), "1.0E-4");
+        assert_eq!(format_float(9999999.0), "9999999.0");
+        assert_eq!(format_float(1.0e7), "1.0E7");
+        assert_eq!(format_float(f32::NAN), "NaN");
+        assert_eq!(format_float(f32::NEG_INFINITY), "-Infinity");
+    }
+
+    #[test]
+    fn formats_doubles_like_java() {
+        assert_eq!(format_double(100.0), "100.0");
+        assert_eq!(format_double(-2.5), "-2.5");
+        assert_eq!(format_double(1.0e10), "1.0E10");
+        assert_eq!(format_double(123456789.0), "1.23456789E8");
+        assert_eq!(format_double(-1.25e-5), "-1.25E-5");
+        assert_eq!(format_double(f64::INFINITY), "Infinity");
+        assert_eq!(format_double(0.1 + 0.2), "0.30000000000000004");
+    }
+
+    fn answer(
+        _: &mut Frame,
+        _: &mut Heap,
+        _: Vec<OperandItem>,
+    ) -> Result<Option<OperandItem>, FrameError> {
+        Ok(Some(OperandItem::Integer(42)))
+    }
+
+    #[test]
+    fn calls_a_registered_native() {
+        let mut class = ClassBuilder::new("Test");
+        let native = class
+            .pool
+            .add_method_ref("test/Fake", "answer", "()I")
+            .unwrap();
+        let class = class
+            .method("run", "()I", (1, 0), &[Invokestatic(native), Ireturn])
+            .build();
+        let mut vm = vm(&[&class]);
+        assert!(vm.natives.get("test/Fake", "answer", "()I").is_none());
+        vm.natives.register("test/Fake", "answer", "()I", answer);
+        assert_eq!(run(&mut vm, &class, "run", "()I"), OperandItem::Integer(42));
+    }
+}
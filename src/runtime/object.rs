@@ -1,4 +1,145 @@
+use super::frame::{FrameError, OperandItem};
+use std::collections::HashMap;
+
+#[derive(Clone)]
 pub struct Object {
+    /// The binary name of the object's class.
     pub name: String,
-    pub fields: Vec<Field>,
-}
\ No newline at end of file
+    /// The values of the instance fields, keyed by name and descriptor.
+    pub fields: HashMap<(String, String), OperandItem>,
+}
+
+impl Object {
+    /// Creates an object with every field set to its zero value.
+    pub fn new(name: &str, fields: Vec<(String, String)>) -> Object {
+        let fields = fields
+            .into_iter()
+            .map(|(name, descriptor)| {
+                let value = OperandItem::zero(&descriptor);
+                ((name, descriptor), value)
+            })
+            .collect();
+
+        Object {
+            name: name.to_owned(),
+            fields,
+        }
+    }
+
+    pub fn field(&self, name: &str, descriptor: &str) -> Option<&OperandItem> {
+        self.fields.get(&(name.to_owned(), descriptor.to_owned()))
+    }
+
+    pub fn field_mut(&mut self, name: &str, descriptor: &str) -> Option<&mut OperandItem> {
+        self.fields
+            .get_mut(&(name.to_owned(), descriptor.to_owned()))
+    }
+}
+
+#[derive(Clone)]
+pub struct Array {
+    /// The binary name of the array's class, like `[Ljava/lang/String;`.
+    pub name: String,
+    pub elements: Elements,
+}
+
+/// The elements of an array. Primitive arrays hold their values unboxed, in
+/// the width of the component type.
+#[derive(Clone)]
+pub enum Elements {
+    Boolean(Vec<u8>),
+    Byte(Vec<i8>),
+    Char(Vec<u16>),
+    Short(Vec<i16>),
+    Int(Vec<i32>),
+    Long(Vec<i64>),
+    Float(Vec<f32>),
+    Double(Vec<f64>),
+    Reference(Vec<OperandItem>),
+}
+
+impl Array {
+    /// Creates an array of `length` elements, each set to the zero value of
+    /// the component type.
+    pub fn new(name: String, length: usize) -> Array {
+        let elements = match name.as_bytes().get(1) {
+            Some(b'Z') => Elements::Boolean(vec![0; length]),
+            Some(b'B') => Elements::Byte(vec![0; length]),
+            Some(b'C') => Elements::Char(vec![0; length]),
+            Some(b'S') => Elements::Short(vec![0; length]),
+            Some(b'I') => Elements::Int(vec![0; length]),
+            Some(b'J') => Elements::Long(vec![0; length]),
+            Some(b'F') => Elements::Float(vec![0.0; length]),
+            Some(b'D') => Elements::Double(vec![0.0; length]),
+            _ => Elements::Reference(vec![OperandItem::Null; length]),
+        };
+        Array { name, elements }
+    }
+
+    pub fn len(&self) -> usize {
+        match &self.elements {
+            Elements::Boolean(elements) => elements.len(),
+            Elements::Byte(elements) => elements.len(),
+            Elements::Char(elements) => elements.len(),
+            Elements::Short(elements) => elements.len(),
+            Elements::Int(elements) => elements.len(),
+            Elements::Long(elements) => elements.len(),
+            Elements::Float(elements) => elements.len(),
+            Elements::Double(elements) => elements.len(),
+            Elements::Reference(elements) => elements.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Reads the element at `index`, widened to the type it has on the operand
+    /// stack. Returns `None` if the index is out of bounds.
+    pub fn load(&self, index: usize) -> Option<OperandItem> {
+        Some(match &self.elements {
+            Elements::Boolean(elements) => OperandItem::Integer(*elements.get(index)? as i32),
+            Elements::Byte(elements) => OperandItem::Integer(*elements.get(index)? as i32),
+            Elements::Char(elements) => OperandItem::Integer(*elements.get(index)? as i32),
+            Elements::Short(elements) => OperandItem::Integer(*elements.get(index)? as i32),
+            Elements::Int(elements) => OperandItem::Integer(*elements.get(index)?),
+            Elements::Long(elements) => OperandItem::Long(*elements.get(index)?),
+            Elements::Float(elements) => OperandItem::Float(*elements.get(index)?),
+            Elements::Double(elements) => OperandItem::Double(*elements.get(index)?),
+            Elements::Reference(elements) => elements.get(index)?.clone(),
+        })
+    }
+
+    /// Writes `value` to the element at `index`, truncating ints stored into
+    /// narrower arrays. Returns `false` if the index is out of bounds.
+    pub fn store(&mut self, index: usize, value: OperandItem) -> Result<bool, FrameError> {
+        if index >= self.len() {
+            return Ok(false);
+        }
+
+        match (&mut self.elements, value) {
+            (Elements::Boolean(elements), OperandItem::Integer(value)) => {
+                elements[index] = value as u8 & 1
+            }
+            (Elements::Byte(elements), OperandItem::Integer(value)) => {
+                elements[index] = value as i8
+            }
+            (Elements::Char(elements), OperandItem::Integer(value)) => {
+                elements[index] = value as u16
+            }
+            (Elements::Short(elements), OperandItem::Integer(value)) => {
+                elements[index] = value as i16
+            }
+            (Elements::Int(elements), OperandItem::Integer(value)) => elements[index] = value,
+            (Elements::Long(elements), OperandItem::Long(value)) => elements[index] = value,
+            (Elements::Float(elements), OperandItem::Float(value)) => elements[index] = value,
+            (Elements::Double(elements), OperandItem::Double(value)) => elements[index] = value,
+            (Elements::Reference(elements), value) if value.is_reference() => {
+                elements[index] = value
+            }
+            _ => return Err(FrameError::TypeMismatch),
+        }
+
+        Ok(true)
+    }
+}
@@ -0,0 +1,202 @@
+//! Builds classes in memory and runs their methods, for the runtime's tests.
+
+use super::{
+    classloader::ClassLoader,
+    frame::{Frame, FrameResult, OperandItem},
+    vm::Vm,
+};
+use runevm_classfile::{
+    parse_class, Attribute, ClassAccessFlags, ClassFile, ConstantPool, ConstantPoolBuilder,
+    ExceptionTableEntry, Field, FieldAccessFields, Instruction, Method, MethodAccessFlags, Version,
+};
+use std::{mem, rc::Rc};
+
+/// A class whose methods are all public and static, built up one member at a
+/// time.
+pub struct ClassBuilder {
+    name: String,
+    pub pool: ConstantPoolBuilder,
+    fields: Vec<Field>,
+    methods: Vec<Method>,
+}
+
+impl ClassBuilder {
+    pub fn new(name: &str) -> ClassBuilder {
+        ClassBuilder {
+            name: name.to_owned(),
+            pool: ConstantPool::builder(),
+            fields: Vec::new(),
+            methods: Vec::new(),
+        }
+    }
+
+    pub fn field(
+        &mut self,
+        access_flags: FieldAccessFields,
+        name: &str,
+        descriptor: &str,
+    ) -> &mut ClassBuilder {
+        self.field_with_attributes(access_flags, name, descriptor, Vec::new())
+    }
+
+    /// Adds a field initialized to the constant at `value` through a
+    /// ConstantValue attribute.
+    pub fn constant_field(
+        &mut self,
+        access_flags: FieldAccessFields,
+        name: &str,
+        descriptor: &str,
+        value: u16,
+    ) -> &mut ClassBuilder {
+        self.pool.add_utf8("ConstantValue").unwrap();
+        let attributes = vec![Attribute::ConstantValue(value)];
+        self.field_with_attributes(access_flags, name, descriptor, attributes)
+    }
+
+    fn field_with_attributes(
+        &mut self,
+        access_flags: FieldAccessFields,
+        name: &str,
+        descriptor: &str,
+        attributes: Vec<Attribute>,
+    ) -> &mut ClassBuilder {
+        let field = Field {
+            access_flags,
+            name_index: self.pool.add_utf8(name).unwrap(),
+            descriptor_index: self.pool.add_utf8(descriptor).unwrap(),
+            attributes,
+        };
+        self.fields.push(field);
+        self
+    }
+
+    pub fn method(
+        &mut self,
+        name: &str,
+        descriptor: &str,
+        (max_stack, max_locals): (u16, u16),
+        code: &[Instruction],
+    ) -> &mut ClassBuilder {
+        self.method_with_handlers(name, descriptor, (max_stack, max_locals), code, Vec::new())
+    }
+
+    pub fn method_with_handlers(
+        &mut self,
+        name: &str,
+        descriptor: &str,
+        (max_stack, max_locals): (u16, u16),
+        code: &[Instruction],
+        exceptions: Vec<ExceptionTableEntry>,
+    ) -> &mut ClassBuilder {
+        let method = Method {
+            access_flags: MethodAccessFlags::PUBLIC | MethodAccessFlags::STATIC,
+            name_index: self.pool.add_utf8(name).unwrap(),
+            descriptor_index: self.pool.add_utf8(descriptor).unwrap(),
+            attributes: vec![Attribute::Code {
+                max_stack,
+                max_locals,
+                code: with_offsets(code),
+                exceptions,
+                attributes: Vec::new(),
+            }],
+        };
+        self.methods.push(method);
+        self
+    }
+
+    /// Adds a method flagged `NATIVE`, which has no code.
+    pub fn native_method(&mut self, name: &str, descriptor: &str) -> &mut ClassBuilder {
+        let method = Method {
+            access_flags: MethodAccessFlags::PUBLIC
+                | MethodAccessFlags::STATIC
+                | MethodAccessFlags::NATIVE,
+            name_index: self.pool.add_utf8(name).unwrap(),
+            descriptor_index: self.pool.add_utf8(descriptor).unwrap(),
+            attributes: Vec::new(),
+        };
+        self.methods.push(method);
+        self
+    }
+
+    pub fn build(&mut self) -> Vec<u8> {
+        let this_class = self.pool.add_class(&self.name).unwrap();
+        let super_class = self.pool.add_class("java/lang/Object").unwrap();
+        self.pool.add_utf8("Code").unwrap();
+
+        let class = ClassFile {
+            version: Version {
+                major: 61,
+                minor: 0,
+            },
+            constant_pool: mem::take(&mut self.pool).build(),
+            access_flags: ClassAccessFlags::PUBLIC | ClassAccessFlags::SUPER,
+            this_class,
+            super_class,
+            interfaces: Vec::new(),
+            fields: mem::take(&mut self.fields),
+            methods: mem::take(&mut self.methods),
+            attributes: Vec::new(),
+        };
+        class.to_bytes().unwrap()
+    }
+}
+
+/// Pairs each instruction with the offset it is encoded at.
+pub fn with_offsets(code: &[Instruction]) -> Vec<(u32, Instruction)> {
+    let mut out = Vec::new();
+    code.iter()
+        .map(|inst| {
+            let offset = out.len() as u32;
+            inst.encode(&mut out);
+            (offset, inst.clone())
+        })
+        .collect()
+}
+
+/// Creates a VM with `classes` defined and nothing on its classpath.
+pub fn vm(classes: &[&[u8]]) -> Vm {
+    let mut class_loader = ClassLoader::new(Vec::new());
+    for class in classes {
+        class_loader.define(class).unwrap();
+    }
+    Vm::new(class_loader)
+}
+
+/// Creates a frame for a method of `class` without verifying the class, so
+/// malformed code can be run too.
+pub fn frame(class: &[u8], name: &str, descriptor: &str, arguments: Vec<OperandItem>) -> Frame {
+    let class = parse_class(class).unwrap().1;
+    let pool = &class.constant_pool;
+    let method = class
+        .methods
+        .iter()
+        .position(|method| {
+            pool.utf8(method.name_index).ok() == Some(name)
+                && pool.utf8(method.descriptor_index).ok() == Some(descriptor)
+        })
+        .unwrap();
+    Frame::new(Rc::new(class), method, arguments).unwrap()
+}
+
+/// Runs a method of `class` in a frame of its own and returns the value it
+/// returned. The method must not call other bytecode methods.
+pub fn run(vm: &mut Vm, class: &[u8], name: &str, descriptor: &str) -> OperandItem {
+    match frame(class, name, descriptor, Vec::new()).execute(vm) {
+        Ok(FrameResult::Returned(value)) => value,
+        Ok(_) => panic!("{name}{descriptor} did not return a value"),
+        Err(err) => panic!("{name}{descriptor} failed: {err}"),
+    }
+}
+
+/// Defines `classes`, runs `class`'s `main(String[])` method, and returns
+/// what it stored in its static `result` field of type `descriptor`.
+pub fn main_result(classes: &[&[u8]], class: &str, descriptor: &str) -> OperandItem {
+    let mut vm = vm(classes);
+    if let Err(err) = vm.run_main(class, Vec::new()) {
+        panic!("{class}.main failed: {err}");
+    }
+    vm.class_loader
+        .get_static(&mut vm.heap, class, "result", descriptor)
+        .unwrap()
+        .unwrap()
+}
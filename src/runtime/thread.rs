@@ -1,26 +1,123 @@
-use super::frame::{Frame, FrameResult};
-use runevm_classfile::{ConstantPool, Method};
+use super::{
+    frame::{Frame, FrameError, FrameResult, OperandItem},
+    object::{Array, Elements},
+    vm::Vm,
+};
 
+/// The most frames a thread's stack can hold before a call overflows it.
+const MAX_FRAMES: usize = 1024;
+
+/// A thread of execution, which owns its stack of frames and works on the
+/// [`Vm`] it is given.
+#[derive(Default)]
 pub struct JavaThread {
     stack: Vec<Frame>,
 }
 
 impl JavaThread {
-    pub fn new(constant_pool: &ConstantPool, method: Method) -> JavaThread {
-        let mut stack: Vec<Frame> = Vec::new();
-        stack.push(Frame::new(constant_pool, method));
+    pub fn new() -> JavaThread {
+        JavaThread { stack: Vec::new() }
+    }
+
+    /// Initializes `class` and runs its `main(String[])` method to completion,
+    /// passing it `args`.
+    pub fn run_main(
+        &mut self,
+        vm: &mut Vm,
+        class: &str,
+        args: Vec<String>,
+    ) -> Result<(), FrameError> {
+        let descriptor = "([Ljava/lang/String;)V";
+        let (main_class, method) = vm
+            .class_loader
+            .resolve_method(class, "main", descriptor)?
+            .ok_or_else(|| FrameError::NoSuchMethod {
+                class: class.to_owned(),
+                name: "main".to_owned(),
+                descriptor: descriptor.to_owned(),
+            })?;
+        let args = Array {
+            name: "[Ljava/lang/String;".to_owned(),
+            elements: Elements::Reference(
+                args.into_iter()
+                    .map(|arg| OperandItem::Reference(vm.heap.allocate_string(arg)))
+                    .collect(),
+            ),
+        };
+        let args = OperandItem::Reference(vm.heap.allocate_array(args));
+        self.stack.push(Frame::new(main_class, method, vec![args])?);
+
+        // Superclass initializers come back first but need to run first, so
+        // they go on top of the stack.
+        let mut initializers = Vec::new();
+        while let Some((name, class, clinit)) = vm.class_loader.initialize(class)? {
+            initializers.push(Frame::initializer(name, class, clinit)?);
+        }
+        self.stack.extend(initializers.into_iter().rev());
 
-        JavaThread { stack }
+        self.run(vm)
     }
 
-    pub fn run(&mut self) {
-        while !self.stack.is_empty() {
-            let mut current = self.stack.pop().unwrap();
+    fn run(&mut self, vm: &mut Vm) -> Result<(), FrameError> {
+        while let Some(mut current) = self.stack.pop() {
+            let result = current.execute(vm)?;
+            if !matches!(result, FrameResult::NextFrame(_)) {
+                current.release_monitors(&mut vm.heap);
+            }
+            match result {
+                FrameResult::NextFrame(frame) => {
+                    if self.stack.len() + 2 > MAX_FRAMES {
+                        return Err(FrameError::StackOverflow);
+                    }
 
-            match current.execute() {
-                FrameResult::NextFrame(_) => todo!(),
+                    self.stack.push(current);
+                    self.stack.push(frame);
+                }
                 FrameResult::Finished => {}
+                FrameResult::Returned(value) => {
+                    if let Some(caller) = self.stack.last_mut() {
+                        caller.push_value(value)?;
+                    }
+                }
+                FrameResult::Thrown(exception) => {
+                    current.fail_initialization(&mut vm.class_loader);
+
+                    // Unwind until a caller has a handler for the exception,
+                    // keeping track of the frames it passes through in case
+                    // none does.
+                    let mut stack_trace = vec![current.stack_trace_element()?];
+                    loop {
+                        let caller = match self.stack.last_mut() {
+                            Some(caller) => caller,
+                            None => {
+                                let exception = vm.heap.get(exception);
+                                let message = match exception.as_instance().and_then(|object| {
+                                    object.field("detailMessage", "Ljava/lang/String;")
+                                }) {
+                                    Some(OperandItem::Reference(message)) => {
+                                        vm.heap.get(*message).as_string().map(str::to_owned)
+                                    }
+                                    _ => None,
+                                };
+                                return Err(FrameError::UncaughtException {
+                                    class: exception.class_name().to_owned(),
+                                    message,
+                                    stack_trace,
+                                });
+                            }
+                        };
+                        if caller.catch(exception, vm)? {
+                            break;
+                        }
+                        stack_trace.push(caller.stack_trace_element()?);
+                        caller.release_monitors(&mut vm.heap);
+                        caller.fail_initialization(&mut vm.class_loader);
+                        self.stack.pop();
+                    }
+                }
             }
         }
+
+        Ok(())
     }
 }
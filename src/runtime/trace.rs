@@ -0,0 +1,63 @@
+use super::frame::Frame;
+use runevm_classfile::Instruction;
+
+/// Watches the interpreter run, one instruction at a time.
+pub trait ExecutionTracer {
+    /// Called just before `inst`, at offset `pc` in the method `frame` is
+    /// running, is executed.
+    fn on_instruction(&mut self, frame: &Frame, pc: usize, inst: &Instruction);
+}
+
+/// Ignores every instruction. The [`Vm`](super::vm::Vm) uses it unless it is
+/// given another tracer.
+pub struct NoopTracer;
+
+impl ExecutionTracer for NoopTracer {
+    fn on_instruction(&mut self, _: &Frame, _: usize, _: &Instruction) {}
+}
+
+/// Prints each instruction to standard output as it runs, in the same form
+/// as a disassembled method.
+pub struct StdoutTracer;
+
+impl ExecutionTracer for StdoutTracer {
+    fn on_instruction(&mut self, frame: &Frame, pc: usize, inst: &Instruction) {
+        println!(
+            "{}",
+            inst.disassemble(pc as u32, &frame.class().constant_pool)
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::testing::{run, vm, ClassBuilder};
+    use runevm_classfile::Instruction::*;
+    use std::{cell::RefCell, rc::Rc};
+
+    /// Records the offset and opcode of each instruction that runs.
+    struct RecordingTracer(Rc<RefCell<Vec<(usize, u8)>>>);
+
+    impl ExecutionTracer for RecordingTracer {
+        fn on_instruction(&mut self, _: &Frame, pc: usize, inst: &Instruction) {
+            self.0.borrow_mut().push((pc, inst.opcode()));
+        }
+    }
+
+    #[test]
+    fn records_executed_opcodes() {
+        let class = ClassBuilder::new("Test")
+            .method("run", "()I", (2, 0), &[Iconst2, Bipush(40), Iadd, Ireturn])
+            .build();
+        let mut vm = vm(&[&class]);
+        let executed = Rc::new(RefCell::new(Vec::new()));
+        vm.tracer = Box::new(RecordingTracer(Rc::clone(&executed)));
+
+        run(&mut vm, &class, "run", "()I");
+        assert_eq!(
+            *executed.borrow(),
+            [(0, 0x05), (1, 0x10), (3, 0x60), (4, 0xac)]
+        );
+    }
+}
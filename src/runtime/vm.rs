@@ -0,0 +1,74 @@
+use super::{
+    classloader::ClassLoader,
+    frame::FrameError,
+    heap::Heap,
+    natives::NativeRegistry,
+    thread::JavaThread,
+    trace::{ExecutionTracer, NoopTracer},
+};
+
+/// The state shared by everything running in the virtual machine: the loaded
+/// classes along with their static fields, the heap and its interned
+/// strings, the native method implementations, and the tracer that watches
+/// the interpreter.
+pub struct Vm {
+    pub class_loader: ClassLoader,
+    pub heap: Heap,
+    pub natives: NativeRegistry,
+    pub tracer: Box<dyn ExecutionTracer>,
+}
+
+impl Vm {
+    pub fn new(class_loader: ClassLoader) -> Vm {
+        Vm {
+            class_loader,
+            heap: Heap::new(),
+            natives: NativeRegistry::new(),
+            tracer: Box::new(NoopTracer),
+        }
+    }
+
+    /// Runs `class`'s `main(String[])` method on a new thread, passing it
+    /// `args`.
+    pub fn run_main(&mut self, class: &str, args: Vec<String>) -> Result<(), FrameError> {
+        JavaThread::new().run_main(self, class, args)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::{frame::OperandItem, testing::ClassBuilder};
+    use runevm_classfile::{FieldAccessFields, Instruction::*};
+
+    #[test]
+    fn runs_main_of_a_loaded_class() {
+        let mut class = ClassBuilder::new("Main");
+        let count = class.pool.add_field_ref("Main", "count", "I").unwrap();
+        let class = class
+            .field(
+                FieldAccessFields::PUBLIC | FieldAccessFields::STATIC,
+                "count",
+                "I",
+            )
+            .method(
+                "main",
+                "([Ljava/lang/String;)V",
+                (1, 1),
+                &[Aload(0), Arraylength, Putstatic(count), Return],
+            )
+            .build();
+
+        let mut class_loader = ClassLoader::new(Vec::new());
+        assert_eq!(class_loader.define(&class).unwrap(), "Main");
+        let mut vm = Vm::new(class_loader);
+        vm.run_main("Main", vec!["a".to_owned(), "b".to_owned()])
+            .unwrap();
+        assert_eq!(
+            vm.class_loader
+                .get_static(&mut vm.heap, "Main", "count", "I")
+                .unwrap(),
+            Some(OperandItem::Integer(2))
+        );
+    }
+}
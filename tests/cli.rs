@@ -0,0 +1,65 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+fn fixtures() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures")
+}
+
+/// Runs the binary from an empty directory, so classes can't be found
+/// through the default classpath of `.`.
+fn runevm(args: &[&str], classpath_env: Option<&Path>) -> Output {
+    let empty = Path::new(env!("CARGO_TARGET_TMPDIR")).join("empty");
+    fs::create_dir_all(&empty).unwrap();
+
+    let mut command = Command::new(env!("CARGO_BIN_EXE_runevm"));
+    command
+        .args(args)
+        .current_dir(&empty)
+        .env_remove("CLASSPATH");
+    if let Some(classpath) = classpath_env {
+        command.env("CLASSPATH", classpath);
+    }
+    command.output().unwrap()
+}
+
+#[test]
+fn classpath_falls_back_to_environment() {
+    let output = runevm(&["Hello"], Some(&fixtures()));
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "hello from the classpath\n"
+    );
+}
+
+#[test]
+fn classpath_option_overrides_environment() {
+    let output = runevm(&["--classpath", ".", "Hello"], Some(&fixtures()));
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.starts_with("error: could not load main class Hello"));
+}
+
+#[test]
+fn missing_class_file_is_reported() {
+    let output = runevm(&["Missing.class"], None);
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.starts_with("error: could not read Missing.class"));
+    assert!(!stderr.contains("panicked"));
+}
+
+#[test]
+fn uncaught_exception_prints_a_stack_trace() {
+    let output = runevm(&["Uncaught"], Some(&fixtures()));
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "Exception in thread \"main\" java.lang.ArithmeticException: / by zero\n\
+         \tat Uncaught.divide(Uncaught.java:7)\n\
+         \tat Uncaught.main(Uncaught.java:3)\n"
+    );
+}
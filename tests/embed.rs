@@ -0,0 +1,77 @@
+use runevm::runtime::{classloader::ClassLoader, frame::OperandItem, vm::Vm};
+use runevm_classfile::{
+    Attribute, ClassAccessFlags, ClassFile, ConstantPool, Field, FieldAccessFields, Instruction,
+    Method, MethodAccessFlags, Version,
+};
+
+/// Builds a class whose `main` stores 40 + 2 in its static field `answer`.
+fn answer_class() -> Vec<u8> {
+    let mut pool = ConstantPool::builder();
+    let answer = pool.add_field_ref("Answer", "answer", "I").unwrap();
+    let this_class = pool.add_class("Answer").unwrap();
+    let super_class = pool.add_class("java/lang/Object").unwrap();
+    pool.add_utf8("Code").unwrap();
+
+    let code = [
+        Instruction::Bipush(40),
+        Instruction::Iconst2,
+        Instruction::Iadd,
+        Instruction::Putstatic(answer),
+        Instruction::Return,
+    ];
+    let mut bytes = Vec::new();
+    let code = code
+        .into_iter()
+        .map(|inst| {
+            let offset = bytes.len() as u32;
+            inst.encode(&mut bytes);
+            (offset, inst)
+        })
+        .collect();
+
+    let class = ClassFile {
+        version: Version {
+            major: 61,
+            minor: 0,
+        },
+        access_flags: ClassAccessFlags::PUBLIC | ClassAccessFlags::SUPER,
+        this_class,
+        super_class,
+        interfaces: Vec::new(),
+        fields: vec![Field {
+            access_flags: FieldAccessFields::STATIC,
+            name_index: pool.add_utf8("answer").unwrap(),
+            descriptor_index: pool.add_utf8("I").unwrap(),
+            attributes: Vec::new(),
+        }],
+        methods: vec![Method {
+            access_flags: MethodAccessFlags::PUBLIC | MethodAccessFlags::STATIC,
+            name_index: pool.add_utf8("main").unwrap(),
+            descriptor_index: pool.add_utf8("([Ljava/lang/String;)V").unwrap(),
+            attributes: vec![Attribute::Code {
+                max_stack: 2,
+                max_locals: 1,
+                code,
+                exceptions: Vec::new(),
+                attributes: Vec::new(),
+            }],
+        }],
+        attributes: Vec::new(),
+        constant_pool: pool.build(),
+    };
+    class.to_bytes().unwrap()
+}
+
+#[test]
+fn runs_main_of_an_in_memory_class() {
+    let mut class_loader = ClassLoader::new(Vec::new());
+    let name = class_loader.define(&answer_class()).unwrap();
+    let mut vm = Vm::new(class_loader);
+    vm.run_main(&name, Vec::new()).unwrap();
+    assert_eq!(
+        vm.class_loader
+            .get_static(&mut vm.heap, "Answer", "answer", "I")
+            .unwrap(),
+        Some(OperandItem::Integer(42))
+    );
+}
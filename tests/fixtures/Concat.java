@@ -0,0 +1,9 @@
+public class Concat {
+    static String literalAndInt(int i) {
+        return "x=" + i;
+    }
+
+    static String floats(float f, double d) {
+        return f + " " + d;
+    }
+}
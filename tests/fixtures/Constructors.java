@@ -0,0 +1,24 @@
+public class Constructors {
+    static int result;
+
+    static class Base {
+        int base;
+
+        Base(int base) {
+            this.base = base;
+        }
+    }
+
+    static class Derived extends Base {
+        int total;
+
+        Derived(int base) {
+            super(base * 2);
+            total = this.base + 1;
+        }
+    }
+
+    public static void main(String[] args) {
+        result = new Derived(5).total;
+    }
+}
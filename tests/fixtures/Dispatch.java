@@ -0,0 +1,34 @@
+public class Dispatch {
+    static int result;
+
+    static class Animal {
+        int legs() {
+            return 0;
+        }
+
+        int describe() {
+            return legs() * 10;
+        }
+    }
+
+    static class Bird extends Animal {
+        @Override
+        int legs() {
+            return 2;
+        }
+    }
+
+    static class Dog extends Animal {
+        @Override
+        int legs() {
+            return 4;
+        }
+    }
+
+    public static void main(String[] args) {
+        Animal bird = new Bird();
+        Animal dog = new Dog();
+        Animal animal = new Animal();
+        result = bird.describe() + dog.legs() * 100 + animal.legs();
+    }
+}
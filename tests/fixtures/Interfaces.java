@@ -0,0 +1,26 @@
+public class Interfaces {
+    static int result;
+
+    interface Shape {
+        int sides();
+    }
+
+    static class Triangle implements Shape {
+        public int sides() {
+            return 3;
+        }
+    }
+
+    static class Square implements Shape {
+        public int sides() {
+            return 4;
+        }
+    }
+
+    public static void main(String[] args) {
+        Shape[] shapes = {new Triangle(), new Square()};
+        for (Shape shape : shapes) {
+            result = result * 10 + shape.sides();
+        }
+    }
+}
@@ -0,0 +1,24 @@
+public class Synchronized {
+    static int result;
+
+    int count;
+
+    int bump() {
+        synchronized (this) {
+            count++;
+        }
+        return count;
+    }
+
+    synchronized int bumpTwice() {
+        synchronized (this) {
+            count += 2;
+        }
+        return count;
+    }
+
+    public static void main(String[] args) {
+        Synchronized counter = new Synchronized();
+        result = counter.bump() + counter.bumpTwice() * 10;
+    }
+}
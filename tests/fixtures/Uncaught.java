@@ -0,0 +1,9 @@
+public class Uncaught {
+    public static void main(String[] args) {
+        divide(1, 0);
+    }
+
+    static int divide(int a, int b) {
+        return a / b;
+    }
+}